@@ -0,0 +1,237 @@
+//! 插件统计事件：仅在用户同意遥测后收集，按批次转发给宿主配置的接收端
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// 未配置接收端时，统计批次以该事件发给前端
+pub const PLUGINSYSTEM_ANALYTICS_EVENT: &str = "astrobox://pluginsystem/analytics";
+
+// 缓冲达到该数量时立即转发
+const ANALYTICS_BATCH_SIZE: usize = 20;
+// 未满一批时最多等待该时长后转发
+const ANALYTICS_FLUSH_DELAY: Duration = Duration::from_secs(10);
+// 每个插件每分钟最多记录的事件数，超出的直接丢弃
+const ANALYTICS_EVENTS_PER_MINUTE: u32 = 60;
+const ANALYTICS_RATE_WINDOW: Duration = Duration::from_secs(60);
+const ANALYTICS_MAX_PROPERTIES: usize = 32;
+const ANALYTICS_MAX_TEXT_LEN: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsEvent {
+    pub plugin: String,
+    pub name: String,
+    pub properties: BTreeMap<String, String>,
+    pub timestamp_ms: i64,
+}
+
+/// 统计批次的接收端，由宿主应用配置
+pub type AnalyticsSink = Arc<dyn Fn(Vec<AnalyticsEvent>) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackOutcome {
+    Buffered,
+    FlushNow,
+    RateLimited,
+}
+
+#[derive(Default)]
+struct AnalyticsBuffer {
+    pending: Vec<AnalyticsEvent>,
+    windows: HashMap<String, (Instant, u32)>,
+    flush_scheduled: bool,
+}
+
+impl AnalyticsBuffer {
+    fn push(&mut self, event: AnalyticsEvent, now: Instant) -> TrackOutcome {
+        let window = self.windows.entry(event.plugin.clone()).or_insert((now, 0));
+        if now.duration_since(window.0) >= ANALYTICS_RATE_WINDOW {
+            *window = (now, 0);
+        }
+        if window.1 >= ANALYTICS_EVENTS_PER_MINUTE {
+            return TrackOutcome::RateLimited;
+        }
+        window.1 += 1;
+
+        self.pending.push(event);
+        if self.pending.len() >= ANALYTICS_BATCH_SIZE {
+            TrackOutcome::FlushNow
+        } else {
+            TrackOutcome::Buffered
+        }
+    }
+
+    fn take(&mut self) -> Vec<AnalyticsEvent> {
+        self.flush_scheduled = false;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+static TELEMETRY_CONSENT: AtomicBool = AtomicBool::new(false);
+static ANALYTICS_BUFFER: Lazy<StdMutex<AnalyticsBuffer>> =
+    Lazy::new(|| StdMutex::new(AnalyticsBuffer::default()));
+static ANALYTICS_SINK: Lazy<StdMutex<Option<AnalyticsSink>>> = Lazy::new(|| StdMutex::new(None));
+
+/// 设置用户是否同意遥测；撤回同意时丢弃尚未转发的事件
+pub fn set_telemetry_consent(consent: bool) {
+    TELEMETRY_CONSENT.store(consent, Ordering::SeqCst);
+    if !consent {
+        ANALYTICS_BUFFER
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner())
+            .take();
+    }
+}
+
+pub fn telemetry_consent() -> bool {
+    TELEMETRY_CONSENT.load(Ordering::SeqCst)
+}
+
+/// 配置统计批次的接收端，`None` 表示以事件形式发给前端
+pub fn set_analytics_sink(sink: Option<AnalyticsSink>) {
+    *ANALYTICS_SINK
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner()) = sink;
+}
+
+fn truncate_text(mut text: String) -> String {
+    if text.len() > ANALYTICS_MAX_TEXT_LEN {
+        let mut end = ANALYTICS_MAX_TEXT_LEN;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+/// 记录一条插件统计事件；用户未同意遥测或超出频率限制时静默丢弃
+pub(crate) fn track(
+    app_handle: &AppHandle,
+    plugin_name: &str,
+    name: String,
+    properties: Vec<(String, String)>,
+) {
+    if !telemetry_consent() || name.trim().is_empty() {
+        return;
+    }
+    let event = AnalyticsEvent {
+        plugin: plugin_name.to_string(),
+        name: truncate_text(name),
+        properties: properties
+            .into_iter()
+            .take(ANALYTICS_MAX_PROPERTIES)
+            .map(|(key, value)| (truncate_text(key), truncate_text(value)))
+            .collect(),
+        timestamp_ms: chrono::Utc::now().timestamp_millis(),
+    };
+
+    let (outcome, schedule) = {
+        let mut buffer = ANALYTICS_BUFFER
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        let outcome = buffer.push(event, Instant::now());
+        let schedule = outcome == TrackOutcome::Buffered && !buffer.flush_scheduled;
+        if schedule {
+            buffer.flush_scheduled = true;
+        }
+        (outcome, schedule)
+    };
+
+    match outcome {
+        TrackOutcome::RateLimited => {
+            log::debug!(
+                "[plugin:{}] analytics event dropped: rate limit exceeded",
+                plugin_name
+            );
+        }
+        TrackOutcome::FlushNow => flush(app_handle),
+        TrackOutcome::Buffered if schedule => {
+            let app_handle = app_handle.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(ANALYTICS_FLUSH_DELAY).await;
+                flush(&app_handle);
+            });
+        }
+        TrackOutcome::Buffered => {}
+    }
+}
+
+fn flush(app_handle: &AppHandle) {
+    let batch = ANALYTICS_BUFFER
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .take();
+    // 等待期间用户可能撤回了同意
+    if batch.is_empty() || !telemetry_consent() {
+        return;
+    }
+    let sink = ANALYTICS_SINK
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .clone();
+    match sink {
+        Some(sink) => sink(batch),
+        None => {
+            if let Err(err) = app_handle.emit(PLUGINSYSTEM_ANALYTICS_EVENT, &batch) {
+                log::warn!("[pluginsystem] Failed to emit analytics batch: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::time::{Duration, Instant};
+
+    use super::{
+        ANALYTICS_BATCH_SIZE, ANALYTICS_EVENTS_PER_MINUTE, AnalyticsBuffer, AnalyticsEvent,
+        TrackOutcome,
+    };
+
+    fn event(plugin: &str) -> AnalyticsEvent {
+        AnalyticsEvent {
+            plugin: plugin.to_string(),
+            name: "opened".to_string(),
+            properties: BTreeMap::new(),
+            timestamp_ms: 0,
+        }
+    }
+
+    #[test]
+    fn flushes_full_batches() {
+        let mut buffer = AnalyticsBuffer::default();
+        let now = Instant::now();
+        for _ in 1..ANALYTICS_BATCH_SIZE {
+            assert_eq!(buffer.push(event("demo"), now), TrackOutcome::Buffered);
+        }
+        assert_eq!(buffer.push(event("demo"), now), TrackOutcome::FlushNow);
+        assert_eq!(buffer.take().len(), ANALYTICS_BATCH_SIZE);
+    }
+
+    #[test]
+    fn rate_limits_each_plugin_per_window() {
+        let mut buffer = AnalyticsBuffer::default();
+        let now = Instant::now();
+        for _ in 0..ANALYTICS_EVENTS_PER_MINUTE {
+            assert_ne!(buffer.push(event("noisy"), now), TrackOutcome::RateLimited);
+            buffer.take();
+        }
+        assert_eq!(buffer.push(event("noisy"), now), TrackOutcome::RateLimited);
+        assert_ne!(buffer.push(event("quiet"), now), TrackOutcome::RateLimited);
+
+        let later = now + Duration::from_secs(61);
+        assert_ne!(
+            buffer.push(event("noisy"), later),
+            TrackOutcome::RateLimited
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use crate::bindings::astrobox::psys_host;
+
+use super::{HostString, PluginCtx};
+
+impl psys_host::analytics::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "analytics.track")
+        )
+    )]
+    fn track(
+        &mut self,
+        event_name: HostString,
+        properties: Vec<(HostString, HostString)>,
+    ) -> wasmtime::Result<()> {
+        let properties = properties
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        crate::analytics::track(
+            &self.app_handle,
+            self.plugin_name(),
+            event_name.into(),
+            properties,
+        );
+        Ok(())
+    }
+}
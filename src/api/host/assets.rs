@@ -0,0 +1,319 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::{DeflateDecoder, GzDecoder};
+
+use crate::bindings::astrobox::psys_host;
+
+use super::{HostString, HostVec, PluginCtx};
+
+// 单次 glob 最多返回的文件数，避免插件目录过大时拖慢宿主
+const ASSETS_GLOB_MAX_RESULTS: usize = 4096;
+// 解压后的最大字节数，防止压缩炸弹撑爆宿主内存
+const ASSETS_DECOMPRESSED_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 通配符匹配的双指针实现：只回溯到最近一个星号，耗时不超过两者长度之积
+fn wildcard_match<P, T>(
+    pattern: &[P],
+    text: &[T],
+    is_star: impl Fn(&P) -> bool,
+    matches_one: impl Fn(&P, &T) -> bool,
+) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // 最近一个星号的位置，以及它当前吞掉的文本末尾
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(item) if is_star(item) => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(item) if matches_one(item, &text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(is_star)
+}
+
+/// 简单的 glob 匹配：`*` 和 `?` 不跨越 `/`，`**` 匹配任意层级目录
+fn glob_match(pattern: &str, path: &str) -> bool {
+    fn segment_match(pattern: &str, segment: &str) -> bool {
+        wildcard_match(
+            pattern.as_bytes(),
+            segment.as_bytes(),
+            |c| *c == b'*',
+            |c, t| *c == b'?' || c == t,
+        )
+    }
+    let pattern = pattern.split('/').collect::<Vec<_>>();
+    let path = path.split('/').collect::<Vec<_>>();
+    wildcard_match(
+        &pattern,
+        &path,
+        |segment| *segment == "**",
+        |pattern, segment| segment_match(pattern, segment),
+    )
+}
+
+// 只接受插件目录内的相对路径
+pub(crate) fn sanitize_declared(entry: &str) -> Option<PathBuf> {
+    let path = Path::new(entry.trim());
+    let mut sanitized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => sanitized.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!sanitized.as_os_str().is_empty()).then_some(sanitized)
+}
+
+fn relative_name(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts = relative
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
+
+/// 列出 manifest `additional_files` 声明的文件，声明为目录时包含其下所有文件；
+/// 不跟随符号链接，结果不会超出插件目录
+pub(crate) fn declared_files(root: &Path, additional_files: &[String]) -> BTreeSet<String> {
+    let mut files = BTreeSet::new();
+    let mut pending = additional_files
+        .iter()
+        .filter_map(|entry| sanitize_declared(entry))
+        .map(|entry| root.join(entry))
+        .collect::<Vec<_>>();
+    while let Some(current) = pending.pop() {
+        if files.len() >= ASSETS_GLOB_MAX_RESULTS {
+            break;
+        }
+        let Ok(metadata) = fs::symlink_metadata(&current) else {
+            continue;
+        };
+        if metadata.is_file() {
+            if let Some(name) = relative_name(root, &current) {
+                files.insert(name);
+            }
+        } else if metadata.is_dir() {
+            let Ok(entries) = fs::read_dir(&current) else {
+                continue;
+            };
+            pending.extend(entries.flatten().map(|entry| entry.path()));
+        }
+    }
+    files
+}
+
+/// 插件目录下的全部文件，与插件通过预打开目录能看到的范围一致；
+/// 不跟随符号链接，跳过以 `.` 开头的宿主元数据条目
+fn plugin_dir_files(root: &Path) -> BTreeSet<String> {
+    let mut files = BTreeSet::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if files.len() >= ASSETS_GLOB_MAX_RESULTS {
+                return files;
+            }
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                if let Some(name) = relative_name(root, &entry.path()) {
+                    files.insert(name);
+                }
+            }
+        }
+    }
+    files
+}
+
+/// 匹配 `additional_files` 声明的文件和插件目录下的文件，结果不会超出插件目录
+fn glob_plugin_files(root: &Path, additional_files: &[String], pattern: &str) -> Vec<String> {
+    let pattern = pattern.trim().trim_start_matches("./");
+    let mut files = declared_files(root, additional_files);
+    files.extend(plugin_dir_files(root));
+    files
+        .into_iter()
+        .filter(|name| glob_match(pattern, name))
+        .take(ASSETS_GLOB_MAX_RESULTS)
+        .collect()
+}
+
+/// 按扩展名解压：`.gz` 使用 gzip，`.zst` 使用 zstd，`.deflate` 使用原始 deflate
+fn decompress(name: &str, data: &[u8], max_bytes: u64) -> Result<Vec<u8>, String> {
+    let reader: Box<dyn Read + '_> = if name.ends_with(".gz") {
+        Box::new(GzDecoder::new(data))
+    } else if name.ends_with(".zst") {
+        Box::new(zstd::stream::read::Decoder::new(data).map_err(|err| err.to_string())?)
+    } else if name.ends_with(".deflate") {
+        Box::new(DeflateDecoder::new(data))
+    } else {
+        return Err(format!("unsupported compressed asset: {name}"));
+    };
+    // 多读一个字节用于判断是否超出上限
+    let mut output = Vec::new();
+    reader
+        .take(max_bytes + 1)
+        .read_to_end(&mut output)
+        .map_err(|err| format!("corrupt compressed asset {name}: {err}"))?;
+    if output.len() as u64 > max_bytes {
+        return Err(format!(
+            "decompressed asset {name} exceeds {max_bytes} bytes"
+        ));
+    }
+    Ok(output)
+}
+
+fn read_declared_decompressed(
+    root: &Path,
+    additional_files: &[String],
+    name: &str,
+) -> Result<Vec<u8>, String> {
+    let name = name.trim().trim_start_matches("./");
+    if !declared_files(root, additional_files).contains(name) {
+        return Err(format!("asset not declared in additional_files: {name}"));
+    }
+    let data = fs::read(root.join(name)).map_err(|err| format!("failed to read {name}: {err}"))?;
+    decompress(name, &data, ASSETS_DECOMPRESSED_MAX_BYTES)
+}
+
+impl psys_host::assets::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "assets.glob")
+        )
+    )]
+    fn glob(&mut self, pattern: HostString) -> wasmtime::Result<HostVec<HostString>> {
+        Ok(
+            glob_plugin_files(self.plugin_root(), &self.additional_files, &pattern)
+                .into_iter()
+                .map(HostString::from)
+                .collect(),
+        )
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "assets.read_decompressed")
+        )
+    )]
+    fn read_decompressed(
+        &mut self,
+        name: HostString,
+    ) -> wasmtime::Result<Result<HostVec<u8>, HostString>> {
+        Ok(
+            read_declared_decompressed(self.plugin_root(), &self.additional_files, &name)
+                .map(HostVec::from)
+                .map_err(|err| {
+                    log::warn!("[plugin:{}] {}", self.plugin_name(), err);
+                    HostString::from(err)
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use super::{decompress, glob_match, glob_plugin_files};
+    use crate::test_support::temp_root;
+
+    #[test]
+    fn glob_patterns_respect_path_segments() {
+        assert!(glob_match("*.json", "en.json"));
+        assert!(!glob_match("*.json", "i18n/en.json"));
+        assert!(glob_match("i18n/*.json", "i18n/en.json"));
+        assert!(glob_match("**/*.json", "en.json"));
+        assert!(glob_match("**/*.json", "i18n/zh/cn.json"));
+        assert!(glob_match("i18n/**", "i18n/zh/cn.json"));
+        assert!(glob_match("config-?.toml", "config-a.toml"));
+        assert!(!glob_match("config-?.toml", "config-/.toml"));
+        assert!(glob_match("**", "a/b/c"));
+        assert!(glob_match("a/**/c", "a/c"));
+        assert!(!glob_match("a/**/c", "a/b/d"));
+    }
+
+    #[test]
+    fn glob_does_not_backtrack_exponentially() {
+        let path = "a".repeat(4096);
+        let pattern = format!("{}b", "a*".repeat(64));
+        assert!(!glob_match(&pattern, &path));
+        let pattern = format!("{}b", "**/".repeat(64));
+        let path = vec!["a"; 4096].join("/");
+        assert!(!glob_match(&pattern, &path));
+    }
+
+    #[test]
+    fn glob_stays_inside_the_plugin_dir() {
+        let temp = temp_root();
+        let base = temp.path();
+        let root = base.join("plugin");
+        fs::create_dir_all(root.join("i18n")).unwrap();
+        fs::create_dir_all(root.join(".meta")).unwrap();
+        fs::write(root.join("i18n/en.json"), "{}").unwrap();
+        fs::write(root.join("i18n/zh.json"), "{}").unwrap();
+        fs::write(root.join("config.json"), "{}").unwrap();
+        fs::write(root.join(".meta/state.json"), "{}").unwrap();
+        fs::write(base.join("outside.json"), "{}").unwrap();
+
+        let declared = vec!["./config.json".to_string(), "../outside.json".to_string()];
+        assert_eq!(
+            glob_plugin_files(&root, &declared, "**/*.json"),
+            vec!["config.json", "i18n/en.json", "i18n/zh.json"]
+        );
+        assert_eq!(
+            glob_plugin_files(&root, &declared, "i18n/*.json"),
+            vec!["i18n/en.json", "i18n/zh.json"]
+        );
+    }
+
+    #[test]
+    fn decompression_is_capped_and_rejects_corrupt_data() {
+        let payload = vec![b'a'; 4096];
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&payload).unwrap();
+        let gz = encoder.finish().unwrap();
+        let zst = zstd::encode_all(payload.as_slice(), 0).unwrap();
+
+        assert_eq!(decompress("data.json.gz", &gz, 4096).unwrap(), payload);
+        assert_eq!(decompress("data.json.zst", &zst, 4096).unwrap(), payload);
+        assert!(decompress("data.json.gz", &gz, 1024).is_err());
+        assert!(decompress("data.json.gz", b"not gzip", 4096).is_err());
+        assert!(decompress("data.json", &gz, 4096).is_err());
+    }
+}
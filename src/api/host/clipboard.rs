@@ -4,7 +4,7 @@ use serde_json::{Value, json};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use wasmtime::component::{Accessor, FutureReader};
 
-use super::{HostString, PluginCtx, permission::check_permission_declared};
+use super::{HostCallSpan, HostString, PluginCtx, permission::check_permission_declared};
 
 const READ_PERMISSION: &str = "clipboard.read";
 const WRITE_PERMISSION: &str = "clipboard.write";
@@ -16,33 +16,41 @@ impl psys_host::clipboard::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<HostString, ()>>> + Send
     {
+        let span = HostCallSpan::new(accessor, "clipboard.read_text");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    READ_PERMISSION,
-                    clipboard_permission_params(&plugin_name),
-                )
-                .await
-                {
-                    return Ok::<core::result::Result<HostString, ()>, Error>(Err(()));
-                }
-
-                match app_handle.clipboard().read_text() {
-                    Ok(content) => {
-                        Ok::<core::result::Result<HostString, ()>, Error>(Ok(content.into()))
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        READ_PERMISSION,
+                        clipboard_permission_params(&plugin_name),
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<HostString, ()>, Error>(Err(()));
                     }
-                    Err(err) => {
-                        log::warn!("[plugin:{}] clipboard read_text failed: {err}", plugin_name);
-                        Ok::<core::result::Result<HostString, ()>, Error>(Err(()))
+
+                    match app_handle.clipboard().read_text() {
+                        Ok(content) => {
+                            Ok::<core::result::Result<HostString, ()>, Error>(Ok(content.into()))
+                        }
+                        Err(err) => {
+                            log::warn!(
+                                "[plugin:{}] clipboard read_text failed: {err}",
+                                plugin_name
+                            );
+                            Ok::<core::result::Result<HostString, ()>, Error>(Err(()))
+                        }
                     }
-                }
-            })
+                }),
+            )
         });
         async move { future }
     }
@@ -51,35 +59,40 @@ impl psys_host::clipboard::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
         text: HostString,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "clipboard.write_text");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let text = text.to_string();
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    WRITE_PERMISSION,
-                    clipboard_permission_params(&plugin_name),
-                )
-                .await
-                {
-                    return Ok::<core::result::Result<(), ()>, Error>(Err(()));
-                }
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        WRITE_PERMISSION,
+                        clipboard_permission_params(&plugin_name),
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
 
-                match app_handle.clipboard().write_text(text) {
-                    Ok(()) => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
-                    Err(err) => {
-                        log::warn!(
-                            "[plugin:{}] clipboard write_text failed: {err}",
-                            plugin_name
-                        );
-                        Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                    match app_handle.clipboard().write_text(text) {
+                        Ok(()) => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
+                        Err(err) => {
+                            log::warn!(
+                                "[plugin:{}] clipboard write_text failed: {err}",
+                                plugin_name
+                            );
+                            Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                        }
                     }
-                }
-            })
+                }),
+            )
         });
         async move { future }
     }
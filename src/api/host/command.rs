@@ -0,0 +1,31 @@
+use crate::bindings::astrobox::psys_host;
+
+use super::{HostString, PluginCtx};
+
+impl psys_host::command::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "command.respond")
+        )
+    )]
+    fn respond(
+        &mut self,
+        request_id: u64,
+        result: core::result::Result<HostString, HostString>,
+    ) -> wasmtime::Result<bool> {
+        let result = result.map(String::from).map_err(String::from);
+        let delivered = crate::plugin_command::respond(self.plugin_name(), request_id, result);
+        if !delivered {
+            // 调用方已超时放弃，或请求不属于该插件
+            log::debug!(
+                "[plugin:{}] command response {} discarded",
+                self.plugin_name(),
+                request_id
+            );
+        }
+        Ok(delivered)
+    }
+}
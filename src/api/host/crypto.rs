@@ -0,0 +1,78 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::bindings::astrobox::psys_host;
+
+use super::{HostVec, PluginCtx};
+
+// HMAC 密钥长度上限，超过块大小的密钥会先被哈希，再长也没有意义
+const HMAC_KEY_MAX_LEN: usize = 1024;
+// 单次计算的数据长度上限
+const HMAC_DATA_MAX_LEN: usize = 16 * 1024 * 1024;
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Option<[u8; 32]> {
+    if key.is_empty() || key.len() > HMAC_KEY_MAX_LEN || data.len() > HMAC_DATA_MAX_LEN {
+        return None;
+    }
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).ok()?;
+    mac.update(data);
+    Some(mac.finalize().into_bytes().into())
+}
+
+impl psys_host::crypto::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "crypto.hmac_sha256")
+        )
+    )]
+    fn hmac_sha256(
+        &mut self,
+        key: HostVec<u8>,
+        data: HostVec<u8>,
+    ) -> wasmtime::Result<core::result::Result<HostVec<u8>, ()>> {
+        match hmac_sha256(&key, &data) {
+            Some(tag) => Ok(Ok(HostVec::from(tag.to_vec()))),
+            None => {
+                log::warn!(
+                    "[plugin:{}] crypto.hmac_sha256 rejected: key must be 1..={} bytes and data at most {} bytes",
+                    self.plugin_name(),
+                    HMAC_KEY_MAX_LEN,
+                    HMAC_DATA_MAX_LEN
+                );
+                Ok(Err(()))
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "crypto.constant_time_eq")
+        )
+    )]
+    fn constant_time_eq(&mut self, a: HostVec<u8>, b: HostVec<u8>) -> wasmtime::Result<bool> {
+        // 长度不同直接返回 false，只有内容比较是常数时间的
+        Ok(a.len() == b.len() && bool::from(a.as_slice().ct_eq(b.as_slice())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hmac_sha256;
+
+    #[test]
+    fn hmac_matches_rfc4231_vector() {
+        let tag = hmac_sha256(b"Jefe", b"what do ya want for nothing?").unwrap();
+        assert_eq!(
+            hex::encode(tag),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(hmac_sha256(b"", b"data").is_none());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::bindings::astrobox::psys_host;
-use anyhow::{Context, Error};
+use anyhow::{Context, Error, anyhow};
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
 use corelib::device::xiaomi::XiaomiDevice;
 use frontbridge::invoke_frontend;
 use serde::Deserialize;
@@ -7,9 +9,28 @@ use serde_json::json;
 use tauri::Manager;
 use wasmtime::component::{Accessor, FutureReader};
 
-use super::{HostString, HostVec, PluginCtx, permission::check_permission_declared};
+use super::{
+    HostCallSpan, HostString, HostVec, PluginCtx,
+    permission::{
+        cached_permission_grants, check_permission_declared, is_permission_declared,
+        record_permission_use,
+    },
+};
 
 const FRONT_DEVICE_LIST_METHOD: &str = "host/device/get_device_list";
+const FRONT_ACTIVE_DEVICE_METHOD: &str = "host/device/get_active_device";
+const VIBRATE_PATTERN_MAX_STEPS: usize = 32;
+const VIBRATE_STEP_MAX_MS: u32 = 5_000;
+const WATCH_NOTIFICATION_TITLE_MAX_CHARS: usize = 64;
+const WATCH_NOTIFICATION_BODY_MAX_CHARS: usize = 512;
+// `health` 授予全部健康数据，细分权限只授予对应字段
+const HEALTH_PERMISSIONS: [&str; 5] = [
+    "health",
+    "health.steps",
+    "health.heart_rate",
+    "health.sleep",
+    "health.blood_oxygen",
+];
 
 #[derive(Debug, Deserialize)]
 struct StoredDeviceRecord {
@@ -28,6 +49,266 @@ impl StoredDeviceRecord {
     }
 }
 
+/// 插件获准读取的健康数据字段
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct HealthScope {
+    steps: bool,
+    heart_rate: bool,
+    sleep: bool,
+    blood_oxygen: bool,
+}
+
+impl HealthScope {
+    fn grant(&mut self, permission: &str) {
+        match permission {
+            "health" => {
+                *self = Self {
+                    steps: true,
+                    heart_rate: true,
+                    sleep: true,
+                    blood_oxygen: true,
+                }
+            }
+            "health.steps" => self.steps = true,
+            "health.heart_rate" => self.heart_rate = true,
+            "health.sleep" => self.sleep = true,
+            "health.blood_oxygen" => self.blood_oxygen = true,
+            _ => {}
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 未获授权的字段一律置空，在数据返回插件前完成裁剪
+    fn redact(
+        &self,
+        snapshot: psys_host::device::HealthSnapshot,
+    ) -> psys_host::device::HealthSnapshot {
+        psys_host::device::HealthSnapshot {
+            timestamp_ms: snapshot.timestamp_ms,
+            steps: snapshot.steps.filter(|_| self.steps),
+            heart_rate: snapshot.heart_rate.filter(|_| self.heart_rate),
+            sleep_minutes: snapshot.sleep_minutes.filter(|_| self.sleep),
+            blood_oxygen: snapshot.blood_oxygen.filter(|_| self.blood_oxygen),
+        }
+    }
+}
+
+/// 按已保存的授权计算插件可读取的健康字段。只有声明的健康权限一项都没有授权时才向用户请求，
+/// 之后的调用直接使用保存的授权，不再逐项确认
+async fn granted_health_scope(
+    app_handle: &tauri::AppHandle,
+    permissions: &[String],
+    plugin_name: &str,
+    addr: &str,
+) -> HealthScope {
+    let declared = HEALTH_PERMISSIONS
+        .into_iter()
+        .filter(|permission| is_permission_declared(permissions, permission))
+        .collect::<Vec<_>>();
+    let mut granted = cached_permission_grants(plugin_name, addr, &declared);
+    if !granted.is_empty() {
+        // 直接使用保存的授权时不经过 check_permission_declared，这里补记使用情况
+        for permission in &granted {
+            record_permission_use(plugin_name, permission);
+        }
+    } else {
+        for permission in &declared {
+            if check_permission_declared(
+                app_handle,
+                permissions,
+                *permission,
+                json!({ "plugin": plugin_name, "addr": addr }),
+            )
+            .await
+            {
+                granted.push(*permission);
+            }
+        }
+    }
+
+    let mut scope = HealthScope::default();
+    for permission in granted {
+        scope.grant(permission);
+    }
+    scope
+}
+
+/// 请求设备最新的健康数据（未裁剪）。corelib 目前没有健康数据接口，接入前一律返回不支持
+async fn request_health_snapshot(
+    device_addr: String,
+) -> Result<psys_host::device::HealthSnapshot, Error> {
+    Err(anyhow!(
+        "Health snapshots are not supported by corelib yet (device {})",
+        device_addr
+    ))
+}
+
+/// 设备所在时区：能确定 IANA 时区时按其规则处理夏令时，否则只能使用固定偏移
+enum DeviceZone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl DeviceZone {
+    /// 设备只上报当前的 UTC 偏移（分钟）。手表时间由手机同步，偏移与手机所在时区此刻的偏移一致时
+    /// 沿用该时区的规则，否则按固定偏移换算
+    fn from_offset_minutes(
+        offset_minutes: i32,
+        host_zone: Option<Tz>,
+        now: &NaiveDateTime,
+    ) -> Option<Self> {
+        let offset = FixedOffset::east_opt(offset_minutes.checked_mul(60)?)?;
+        match host_zone {
+            Some(zone) if zone.offset_from_utc_datetime(now).fix() == offset => {
+                Some(Self::Named(zone))
+            }
+            _ => Some(Self::Fixed(offset)),
+        }
+    }
+
+    fn offset_at_utc(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match self {
+            Self::Named(zone) => zone.offset_from_utc_datetime(utc).fix(),
+            Self::Fixed(offset) => *offset,
+        }
+    }
+
+    fn from_local(&self, local: &NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Self::Named(zone) => match zone.from_local_datetime(local) {
+                LocalResult::Single(time) => Some(time.fixed_offset()),
+                // 夏令时结束时同一时刻出现两次，取较早的一次
+                LocalResult::Ambiguous(earliest, _) => Some(earliest.fixed_offset()),
+                // 夏令时开始时跳过的时刻按跳变前的偏移换算，即顺延实际跳变的时长（不一定是一小时）。
+                // 跳变前一天的偏移就是跳变前的偏移，时区不会在一天内连续跳变两次
+                LocalResult::None => {
+                    let before = zone
+                        .offset_from_utc_datetime(&(*local - chrono::Duration::days(1)))
+                        .fix();
+                    let utc = *local - chrono::Duration::seconds(before.local_minus_utc().into());
+                    Some(zone.from_utc_datetime(&utc).fixed_offset())
+                }
+            },
+            Self::Fixed(offset) => offset.from_local_datetime(local).single(),
+        }
+    }
+}
+
+/// UTC 毫秒时间戳转换为设备本地时间的毫秒数（本地时刻按 UTC 计数）
+fn utc_to_device_local(zone: &DeviceZone, epoch_ms: i64) -> Option<i64> {
+    let utc = DateTime::<Utc>::from_timestamp_millis(epoch_ms)?.naive_utc();
+    let offset = zone.offset_at_utc(&utc);
+    epoch_ms.checked_add(i64::from(offset.local_minus_utc()) * 1000)
+}
+
+fn device_local_to_utc(zone: &DeviceZone, local_ms: i64) -> Option<i64> {
+    let local = DateTime::<Utc>::from_timestamp_millis(local_ms)?.naive_utc();
+    zone.from_local(&local).map(|time| time.timestamp_millis())
+}
+
+/// 检查 `device` 权限和设备是否在线，读取设备状态前调用
+async fn can_query_device(
+    app_handle: &tauri::AppHandle,
+    permissions: &[String],
+    plugin_name: &str,
+    addr: &str,
+) -> bool {
+    check_permission_declared(
+        app_handle,
+        permissions,
+        "device",
+        json!({ "plugin": plugin_name, "addr": addr }),
+    )
+    .await
+        && is_device_connected(addr).await
+}
+
+/// 向设备请求当前时间（UTC 毫秒）。corelib 目前没有读取设备时钟的接口，接入前一律返回不支持
+async fn request_device_time(device_addr: String) -> Result<i64, Error> {
+    Err(anyhow!(
+        "Device time is not supported by corelib yet (device {})",
+        device_addr
+    ))
+}
+
+/// 读取设备的语言、UTC 偏移和时间制式。corelib 目前不暴露这些设置，接入前一律返回不支持，
+/// 依赖偏移的时间换算随之返回 `None`
+async fn read_device_settings(
+    device_addr: String,
+) -> Result<psys_host::device::SettingsInfo, Error> {
+    Err(anyhow!(
+        "Device settings are not supported by corelib yet (device {})",
+        device_addr
+    ))
+}
+
+async fn is_device_connected(addr: &str) -> bool {
+    let addr = addr.to_string();
+    corelib::ecs::with_rt_mut(move |rt| rt.component_ref::<XiaomiDevice>(addr.as_str()).is_some())
+        .await
+}
+
+/// 下发振动模式。corelib 目前没有振动接口，接入前一律返回不支持
+async fn vibrate_device(device_addr: String, _pattern: Vec<u32>) -> Result<(), Error> {
+    Err(anyhow!(
+        "Haptics is not supported by corelib yet (device {})",
+        device_addr
+    ))
+}
+
+/// 推送通知到手表屏幕，字段已按手表限制截断。corelib 目前没有下发通知的接口，接入前一律返回不支持
+async fn send_notification_to_device(
+    device_addr: String,
+    _title: String,
+    _body: String,
+    _icon: Option<String>,
+) -> Result<(), Error> {
+    Err(anyhow!(
+        "Watch notifications are not supported by corelib yet (device {})",
+        device_addr
+    ))
+}
+
+/// 校验振动模式（交替的开/关时长，单位毫秒），非法时返回 `None`
+fn normalize_vibrate_pattern(pattern: &[u32]) -> Option<Vec<u32>> {
+    if pattern.is_empty() || pattern.len() > VIBRATE_PATTERN_MAX_STEPS {
+        return None;
+    }
+    if pattern.iter().all(|duration| *duration == 0) {
+        return None;
+    }
+    Some(
+        pattern
+            .iter()
+            .map(|duration| (*duration).min(VIBRATE_STEP_MAX_MS))
+            .collect(),
+    )
+}
+
+/// 按手表限制截断通知字段（按字符计），发生截断时输出警告
+fn truncate_notification_field(
+    plugin_name: &str,
+    field: &str,
+    value: &str,
+    max_chars: usize,
+) -> String {
+    match value.char_indices().nth(max_chars) {
+        Some((byte_idx, _)) => {
+            log::warn!(
+                "[plugin:{}] watch notification {} truncated to {} chars",
+                plugin_name,
+                field,
+                max_chars
+            );
+            value[..byte_idx].to_string()
+        }
+        None => value.to_string(),
+    }
+}
+
 impl psys_host::device::Host for PluginCtx {}
 
 impl psys_host::device::HostWithStore for PluginCtx {
@@ -35,43 +316,50 @@ impl psys_host::device::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostVec<psys_host::device::DeviceInfo>>> + Send
     {
+        let span = HostCallSpan::new(accessor, "device.get_device_list");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
             let app_handle = app_handle.clone();
-            FutureReader::new(instance, &mut access, async move {
-                log::info!("[plugin:{}] device list request (history)", plugin_name);
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "device",
-                    json!({ "plugin": plugin_name.clone() }),
-                )
-                .await
-                {
-                    return Ok::<HostVec<psys_host::device::DeviceInfo>, Error>(HostVec::new());
-                }
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    log::info!("[plugin:{}] device list request (history)", plugin_name);
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "device",
+                        json!({ "plugin": plugin_name.clone() }),
+                    )
+                    .await
+                    {
+                        return Ok::<HostVec<psys_host::device::DeviceInfo>, Error>(HostVec::new());
+                    }
 
-                let devices: Vec<StoredDeviceRecord> =
-                    invoke_frontend(&app_handle, FRONT_DEVICE_LIST_METHOD, ())
-                        .await
-                        .context("invoke frontend get_device_list")?;
-
-                let mut ret: HostVec<psys_host::device::DeviceInfo> = HostVec::new();
-                devices
-                    .into_iter()
-                    .filter_map(StoredDeviceRecord::into_psys_device)
-                    .for_each(|dev| ret.push(dev));
-
-                log::info!(
-                    "[plugin:{}] device list return {} items",
-                    plugin_name,
-                    ret.len()
-                );
-                Ok::<HostVec<psys_host::device::DeviceInfo>, Error>(ret)
-            })
+                    let devices: Vec<StoredDeviceRecord> =
+                        invoke_frontend(&app_handle, FRONT_DEVICE_LIST_METHOD, ())
+                            .await
+                            .context("invoke frontend get_device_list")?;
+
+                    let ret = normalize_device_list(
+                        &plugin_name,
+                        devices
+                            .into_iter()
+                            .filter_map(StoredDeviceRecord::into_psys_device)
+                            .collect(),
+                    );
+
+                    log::info!(
+                        "[plugin:{}] device list return {} items",
+                        plugin_name,
+                        ret.len()
+                    );
+                    Ok::<HostVec<psys_host::device::DeviceInfo>, Error>(ret)
+                }),
+            )
         });
         async move { future }
     }
@@ -80,43 +368,128 @@ impl psys_host::device::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostVec<psys_host::device::DeviceInfo>>> + Send
     {
+        let span = HostCallSpan::new(accessor, "device.get_connected_device_list");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                log::info!("[plugin:{}] connected device list request", plugin_name);
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "device",
-                    json!({ "plugin": plugin_name.clone() }),
-                )
-                .await
-                {
-                    return Ok::<HostVec<psys_host::device::DeviceInfo>, Error>(HostVec::new());
-                }
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    log::info!("[plugin:{}] connected device list request", plugin_name);
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "device",
+                        json!({ "plugin": plugin_name.clone() }),
+                    )
+                    .await
+                    {
+                        return Ok::<HostVec<psys_host::device::DeviceInfo>, Error>(HostVec::new());
+                    }
 
-                let ret = corelib::ecs::with_rt_mut(|rt| {
-                    rt.device_ids()
-                        .filter_map(|device_id| {
-                            rt.component_ref::<XiaomiDevice>(device_id.as_str())
-                                .map(|device| psys_host::device::DeviceInfo {
-                                    addr: device.addr().to_string(),
-                                    name: device.name().to_string(),
-                                })
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .await;
-                log::info!(
-                    "[plugin:{}] connected device list return {} items",
-                    plugin_name,
-                    ret.len()
-                );
-                Ok::<HostVec<psys_host::device::DeviceInfo>, Error>(ret)
-            })
+                    let ret = corelib::ecs::with_rt_mut(|rt| {
+                        rt.device_ids()
+                            .filter_map(|device_id| {
+                                rt.component_ref::<XiaomiDevice>(device_id.as_str())
+                                    .map(|device| psys_host::device::DeviceInfo {
+                                        addr: device.addr().to_string(),
+                                        name: device.name().to_string(),
+                                    })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .await;
+                    let ret = normalize_device_list(&plugin_name, ret);
+                    log::info!(
+                        "[plugin:{}] connected device list return {} items",
+                        plugin_name,
+                        ret.len()
+                    );
+                    Ok::<HostVec<psys_host::device::DeviceInfo>, Error>(ret)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn connected_count<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<Output = FutureReader<u32>> + Send {
+        let span = HostCallSpan::new(accessor, "device.connected_count");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "device",
+                        json!({ "plugin": plugin_name }),
+                    )
+                    .await
+                    {
+                        return Ok::<u32, Error>(0);
+                    }
+
+                    let count = corelib::ecs::with_rt_mut(|rt| {
+                        rt.device_ids()
+                            .filter(|device_id| {
+                                rt.component_ref::<XiaomiDevice>(device_id.as_str())
+                                    .is_some()
+                            })
+                            .count()
+                    })
+                    .await;
+                    Ok::<u32, Error>(u32::try_from(count).unwrap_or(u32::MAX))
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn get_active_device<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<Output = FutureReader<Option<psys_host::device::DeviceInfo>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "device.get_active_device");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "device",
+                        json!({ "plugin": plugin_name.clone() }),
+                    )
+                    .await
+                    {
+                        return Ok::<Option<psys_host::device::DeviceInfo>, Error>(None);
+                    }
+
+                    // 与前端共用同一份“当前选中设备”状态，未选中设备时为 None
+                    let device: Option<StoredDeviceRecord> =
+                        invoke_frontend(&app_handle, FRONT_ACTIVE_DEVICE_METHOD, ())
+                            .await
+                            .context("invoke frontend get_active_device")?;
+                    Ok::<Option<psys_host::device::DeviceInfo>, Error>(
+                        device.and_then(StoredDeviceRecord::into_psys_device),
+                    )
+                }),
+            )
         });
         async move { future }
     }
@@ -125,12 +498,14 @@ impl psys_host::device::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
         device_addr: HostString,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "device.disconnect_device");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
-        let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
+        let future =
+            accessor.with(|mut access| {
+                FutureReader::new(instance, &mut access, span.instrument(async move {
                 let addr = device_addr.to_string();
 
                 if !check_permission_declared(
@@ -168,8 +543,471 @@ impl psys_host::device::HostWithStore for PluginCtx {
                 }
 
                 Ok::<core::result::Result<(), ()>, Error>(Ok(()))
-            })
+            }))
+            });
+        async move { future }
+    }
+
+    fn vibrate<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+        pattern: HostVec<u32>,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "device.vibrate");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = device_addr.to_string();
+                    let Some(pattern) = normalize_vibrate_pattern(pattern.as_slice()) else {
+                        log::warn!(
+                            "[plugin:{}] vibrate rejected: invalid pattern (len={})",
+                            plugin_name,
+                            pattern.len()
+                        );
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    };
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "device",
+                        json!({ "plugin": plugin_name.clone(), "addr": addr.clone() }),
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
+
+                    if !is_device_connected(&addr).await {
+                        log::warn!(
+                            "[plugin:{}] vibrate failed: device {} is offline",
+                            plugin_name,
+                            addr
+                        );
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
+
+                    match vibrate_device(addr, pattern).await {
+                        Ok(()) => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
+                        Err(err) => {
+                            log::warn!("[plugin:{}] vibrate failed: {err}", plugin_name);
+                            Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                        }
+                    }
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn send_watch_notification<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+        title: HostString,
+        body: HostString,
+        icon: Option<HostString>,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "device.send_watch_notification");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = device_addr.to_string();
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "notification",
+                        json!({ "plugin": plugin_name.clone(), "addr": addr.clone() }),
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
+
+                    if !is_device_connected(&addr).await {
+                        log::warn!(
+                            "[plugin:{}] send_watch_notification failed: device {} is offline",
+                            plugin_name,
+                            addr
+                        );
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
+
+                    let title = truncate_notification_field(
+                        &plugin_name,
+                        "title",
+                        title.as_str(),
+                        WATCH_NOTIFICATION_TITLE_MAX_CHARS,
+                    );
+                    let body = truncate_notification_field(
+                        &plugin_name,
+                        "body",
+                        body.as_str(),
+                        WATCH_NOTIFICATION_BODY_MAX_CHARS,
+                    );
+                    let icon = icon
+                        .map(|icon| icon.to_string())
+                        .filter(|icon| !icon.trim().is_empty());
+                    match send_notification_to_device(addr, title, body, icon).await {
+                        Ok(()) => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
+                        Err(err) => {
+                            log::warn!(
+                                "[plugin:{}] send_watch_notification failed: {err}",
+                                plugin_name
+                            );
+                            Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                        }
+                    }
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn get_device_time<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<Option<i64>>> + Send {
+        let span = HostCallSpan::new(accessor, "device.get_device_time");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = device_addr.to_string();
+                    if !can_query_device(&app_handle, permissions.as_ref(), &plugin_name, &addr)
+                        .await
+                    {
+                        return Ok::<Option<i64>, Error>(None);
+                    }
+                    match request_device_time(addr).await {
+                        Ok(epoch_ms) => Ok::<Option<i64>, Error>(Some(epoch_ms)),
+                        Err(err) => {
+                            log::warn!("[plugin:{}] get_device_time failed: {err}", plugin_name);
+                            Ok::<Option<i64>, Error>(None)
+                        }
+                    }
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn to_device_local<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+        epoch_ms: i64,
+    ) -> impl core::future::Future<Output = FutureReader<Option<i64>>> + Send {
+        convert_device_time(
+            accessor,
+            "device.to_device_local",
+            device_addr,
+            epoch_ms,
+            utc_to_device_local,
+        )
+    }
+
+    fn from_device_local<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+        local_ms: i64,
+    ) -> impl core::future::Future<Output = FutureReader<Option<i64>>> + Send {
+        convert_device_time(
+            accessor,
+            "device.from_device_local",
+            device_addr,
+            local_ms,
+            device_local_to_utc,
+        )
+    }
+
+    fn get_device_settings<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<Option<psys_host::device::SettingsInfo>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "device.get_device_settings");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = device_addr.to_string();
+                    if !can_query_device(&app_handle, permissions.as_ref(), &plugin_name, &addr)
+                        .await
+                    {
+                        return Ok::<Option<psys_host::device::SettingsInfo>, Error>(None);
+                    }
+                    match read_device_settings(addr).await {
+                        Ok(settings) => {
+                            Ok::<Option<psys_host::device::SettingsInfo>, Error>(Some(settings))
+                        }
+                        Err(err) => {
+                            log::warn!(
+                                "[plugin:{}] get_device_settings failed: {err}",
+                                plugin_name
+                            );
+                            Ok::<Option<psys_host::device::SettingsInfo>, Error>(None)
+                        }
+                    }
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn get_health_snapshot<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<Option<psys_host::device::HealthSnapshot>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "device.get_health_snapshot");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = device_addr.to_string();
+                    let scope = granted_health_scope(
+                        &app_handle,
+                        permissions.as_ref(),
+                        &plugin_name,
+                        &addr,
+                    )
+                    .await;
+                    if scope.is_empty() || !is_device_connected(&addr).await {
+                        return Ok::<Option<psys_host::device::HealthSnapshot>, Error>(None);
+                    }
+
+                    let snapshot = match request_health_snapshot(addr).await {
+                        Ok(snapshot) => Some(snapshot),
+                        Err(err) => {
+                            log::warn!(
+                                "[plugin:{}] get_health_snapshot failed: {err}",
+                                plugin_name
+                            );
+                            None
+                        }
+                    };
+                    Ok::<Option<psys_host::device::HealthSnapshot>, Error>(
+                        snapshot.map(|snapshot| scope.redact(snapshot)),
+                    )
+                }),
+            )
         });
         async move { future }
     }
 }
+
+/// 设备列表按地址排序并去重，ECS 和前端返回的顺序不稳定，插件比较两次结果时不应受影响
+fn normalize_device_list(
+    plugin_name: &str,
+    mut devices: Vec<psys_host::device::DeviceInfo>,
+) -> Vec<psys_host::device::DeviceInfo> {
+    devices.sort_by_cached_key(|device| device.addr.to_ascii_uppercase());
+    let before = devices.len();
+    devices.dedup_by(|later, earlier| later.addr.eq_ignore_ascii_case(&earlier.addr));
+    if devices.len() != before {
+        log::warn!(
+            "[plugin:{}] device list contained {} duplicate address(es), dropped",
+            plugin_name,
+            before - devices.len()
+        );
+    }
+    devices
+}
+
+/// 手机所在的 IANA 时区
+fn host_time_zone() -> Option<Tz> {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse::<Tz>().ok())
+}
+
+/// 查询设备时区后换算时间戳，设备离线或没有时区设置时返回 `None`
+fn convert_device_time<T>(
+    accessor: &Accessor<T, PluginCtx>,
+    operation: &'static str,
+    device_addr: HostString,
+    value_ms: i64,
+    convert: fn(&DeviceZone, i64) -> Option<i64>,
+) -> impl core::future::Future<Output = FutureReader<Option<i64>>> + Send {
+    let span = HostCallSpan::new(accessor, operation);
+    let instance = accessor.instance();
+    let app_handle = accessor.with(|mut access| access.get().app_handle());
+    let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+    let permissions = accessor.with(|mut access| access.get().permissions());
+    let future = accessor.with(|mut access| {
+        FutureReader::new(
+            instance,
+            &mut access,
+            span.instrument(async move {
+                let addr = device_addr.to_string();
+                if !can_query_device(&app_handle, permissions.as_ref(), &plugin_name, &addr).await {
+                    return Ok::<Option<i64>, Error>(None);
+                }
+                let settings = match read_device_settings(addr).await {
+                    Ok(settings) => settings,
+                    Err(err) => {
+                        log::warn!("[plugin:{}] {} failed: {err}", plugin_name, operation);
+                        return Ok::<Option<i64>, Error>(None);
+                    }
+                };
+                let now = Utc::now().naive_utc();
+                let converted = DeviceZone::from_offset_minutes(
+                    settings.timezone_offset_minutes,
+                    host_time_zone(),
+                    &now,
+                )
+                .and_then(|zone| convert(&zone, value_ms));
+                Ok::<Option<i64>, Error>(converted)
+            }),
+        )
+    });
+    async move { future }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DeviceZone, HealthScope, device_local_to_utc, normalize_device_list, psys_host,
+        utc_to_device_local,
+    };
+
+    fn device(addr: &str, name: &str) -> psys_host::device::DeviceInfo {
+        psys_host::device::DeviceInfo {
+            addr: addr.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn device_list_is_sorted_and_deduplicated() {
+        let first = normalize_device_list(
+            "test",
+            vec![
+                device("CC:00", "band"),
+                device("AA:00", "watch"),
+                device("aa:00", "watch (stale)"),
+                device("BB:00", "ring"),
+            ],
+        );
+        let second = normalize_device_list(
+            "test",
+            vec![
+                device("BB:00", "ring"),
+                device("AA:00", "watch"),
+                device("CC:00", "band"),
+            ],
+        );
+        let addrs = |devices: &[psys_host::device::DeviceInfo]| {
+            devices
+                .iter()
+                .map(|device| device.addr.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(addrs(&first), vec!["AA:00", "BB:00", "CC:00"]);
+        assert_eq!(addrs(&first), addrs(&second));
+        assert_eq!(first[0].name, "watch");
+    }
+
+    #[test]
+    fn converts_across_dst_boundaries() {
+        let zone = DeviceZone::Named("Europe/Berlin".parse().unwrap());
+        // 2024-03-31 00:30 UTC，冬令时 +01:00
+        let before = 1_711_845_000_000;
+        assert_eq!(utc_to_device_local(&zone, before), Some(before + 3_600_000));
+        // 2024-03-31 01:30 UTC，夏令时 +02:00
+        let after = before + 3_600_000;
+        assert_eq!(utc_to_device_local(&zone, after), Some(after + 7_200_000));
+
+        for utc in [before, after] {
+            let local = utc_to_device_local(&zone, utc).unwrap();
+            assert_eq!(device_local_to_utc(&zone, local), Some(utc));
+        }
+    }
+
+    #[test]
+    fn skipped_local_time_moves_by_the_actual_gap() {
+        // Troll 站 2024-03-31 01:00 UTC 从 +00:00 直接跳到 +02:00，本地 01:00-03:00 不存在
+        let zone = DeviceZone::Named("Antarctica/Troll".parse().unwrap());
+        // 本地 02:30 落在两小时的跳变区间内，按跳变前的 +00:00 换算
+        let local = 1_711_852_200_000;
+        assert_eq!(device_local_to_utc(&zone, local), Some(local));
+    }
+
+    #[test]
+    fn device_offset_selects_the_host_zone_only_when_it_matches() {
+        let berlin: chrono_tz::Tz = "Europe/Berlin".parse().unwrap();
+        // 2024-07-01 00:00 UTC，柏林为夏令时 +02:00
+        let now = chrono::DateTime::from_timestamp(1_719_792_000, 0)
+            .unwrap()
+            .naive_utc();
+        assert!(matches!(
+            DeviceZone::from_offset_minutes(120, Some(berlin), &now),
+            Some(DeviceZone::Named(_))
+        ));
+        assert!(matches!(
+            DeviceZone::from_offset_minutes(60, Some(berlin), &now),
+            Some(DeviceZone::Fixed(_))
+        ));
+        assert!(matches!(
+            DeviceZone::from_offset_minutes(120, None, &now),
+            Some(DeviceZone::Fixed(_))
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_fixed_offset() {
+        let zone = DeviceZone::Fixed(chrono::FixedOffset::east_opt(8 * 3600).unwrap());
+        assert_eq!(utc_to_device_local(&zone, 0), Some(8 * 3_600_000));
+        assert_eq!(device_local_to_utc(&zone, 8 * 3_600_000), Some(0));
+    }
+
+    #[test]
+    fn health_snapshot_is_redacted_to_granted_fields() {
+        let response = || psys_host::device::HealthSnapshot {
+            timestamp_ms: Some(1),
+            steps: Some(8_000),
+            heart_rate: Some(72),
+            sleep_minutes: Some(420),
+            blood_oxygen: Some(98),
+        };
+
+        let mut steps_only = HealthScope::default();
+        steps_only.grant("health.steps");
+        let snapshot = steps_only.redact(response());
+        assert_eq!(snapshot.steps, Some(8_000));
+        assert_eq!(snapshot.heart_rate, None);
+        assert_eq!(snapshot.sleep_minutes, None);
+        assert_eq!(snapshot.blood_oxygen, None);
+
+        let mut full = HealthScope::default();
+        full.grant("health");
+        assert_eq!(full.redact(response()).heart_rate, Some(72));
+    }
+}
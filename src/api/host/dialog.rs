@@ -3,28 +3,33 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    io::Write,
+    io::{Read, Write},
     path::PathBuf,
     sync::{
-        Mutex as StdMutex,
+        Arc, Mutex as StdMutex,
         atomic::{AtomicU64, Ordering},
     },
 };
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_dialog::{DialogExt, FilePath, MessageDialogButtons, MessageDialogResult};
 use tauri_plugin_fs::{FsExt, OpenOptions};
 use tauri_plugin_opener::OpenerExt;
-use tokio::sync::oneshot;
+use tokio::io::AsyncReadExt;
+use tokio::sync::{oneshot, watch};
 use wasmtime::component::{Accessor, FutureReader};
 
 use crate::bindings::astrobox::psys_host;
 
-use super::{HostString, HostVec, PluginCtx};
+use super::{HostCallSpan, HostString, HostVec, PluginCtx};
 
 struct SaveFileSession {
     file: std::fs::File,
 }
 
+struct OpenFileSession {
+    file: std::fs::File,
+}
+
 #[derive(Clone)]
 struct DialogFileFilter {
     multiple: bool,
@@ -61,7 +66,58 @@ static SAVE_FILE_SESSION_ID: AtomicU64 = AtomicU64::new(1);
 static SAVE_FILE_SESSIONS: Lazy<StdMutex<HashMap<(String, u64), SaveFileSession>>> =
     Lazy::new(|| StdMutex::new(HashMap::new()));
 
+// `pick_file` 只内联返回不超过该大小的文件，更大的文件返回空数据，应改用 `open_file_start` 分块读取
+const PICK_FILE_INLINE_MAX_BYTES: u64 = 8 * 1024 * 1024;
+// `open_file_read_chunk` 单次最多返回的字节数
+const OPEN_FILE_CHUNK_MAX_BYTES: u32 = 1024 * 1024;
+
+// 分块读取会话：由 `open_file_start` 创建，读到末尾（返回空列表）或调用 `open_file_finish` 后释放，
+// 插件停止时一并释放。每个会话各有一把锁，读文件时不占用全局表
+static OPEN_FILE_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+static OPEN_FILE_SESSIONS: Lazy<StdMutex<HashMap<(String, u64), Arc<StdMutex<OpenFileSession>>>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+fn insert_open_file_session(plugin_name: String, file: std::fs::File) -> u64 {
+    let session_id = OPEN_FILE_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+    OPEN_FILE_SESSIONS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .insert(
+            (plugin_name, session_id),
+            Arc::new(StdMutex::new(OpenFileSession { file })),
+        );
+    session_id
+}
+
+fn remove_open_file_session(key: &(String, u64)) {
+    OPEN_FILE_SESSIONS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(key);
+}
+
+/// 插件停止时关闭其未结束的分块读取会话
+pub(crate) fn release_open_file_sessions(plugin_name: &str) {
+    OPEN_FILE_SESSIONS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .retain(|(owner, _), _| owner != plugin_name);
+}
+
+/// 为分享给插件的大文件创建分块读取会话，插件用 `open_file_read_chunk` 读取
+pub(crate) fn open_shared_file_session(plugin_name: &str, file: std::fs::File) -> u64 {
+    insert_open_file_session(plugin_name.to_string(), file)
+}
+
 impl psys_host::dialog::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "dialog.open_url")
+        )
+    )]
     fn open_url(&mut self, url: HostString) -> wasmtime::Result<()> {
         let app_handle = self.app_handle();
         let url: String = url.into();
@@ -70,6 +126,18 @@ impl psys_host::dialog::Host for PluginCtx {
         }
         Ok(())
     }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "dialog.cancel_pending")
+        )
+    )]
+    fn cancel_pending(&mut self) -> wasmtime::Result<bool> {
+        Ok(self.register_state().cancel_pending_dialogs())
+    }
 }
 
 impl psys_host::dialog::HostWithStore for PluginCtx {
@@ -80,31 +148,39 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
         info: psys_host::dialog::DialogInfo,
     ) -> impl core::future::Future<Output = FutureReader<psys_host::dialog::DialogResult>> + Send
     {
+        let span = HostCallSpan::new(accessor, "dialog.show_dialog");
         let instance = accessor.instance();
         let future = accessor.with(|mut access| {
-            let (app_handle, plugin_name) = {
+            let (app_handle, plugin_name, cancel) = {
                 let ctx = access.get();
-                (ctx.app_handle(), ctx.plugin_name().to_string())
+                (
+                    ctx.app_handle(),
+                    ctx.plugin_name().to_string(),
+                    ctx.register_state().dialog_cancel_signal(),
+                )
             };
-            FutureReader::new(instance, &mut access, async move {
-                match (dialog_type, style) {
-                    (
-                        psys_host::dialog::DialogType::Alert,
-                        psys_host::dialog::DialogStyle::System,
-                    ) => show_system_alert(app_handle, plugin_name, info).await,
-                    (_, psys_host::dialog::DialogStyle::Website) => {
-                        show_website_dialog(app_handle, plugin_name, dialog_type, info).await
-                    }
-                    _ => {
-                        log::warn!(
-                            "dialog::show_dialog receive an unimplemented combination, type={:?} style={:?}, and return the default result",
-                            dialog_type,
-                            style
-                        );
-                        Ok(default_dialog_result())
-                    }
-                }
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let dialog = show_dialog_with_style(
+                        app_handle.clone(),
+                        plugin_name.clone(),
+                        dialog_type,
+                        style,
+                        info,
+                    );
+                    until_cancelled(
+                        &app_handle,
+                        &plugin_name,
+                        "dialog.show_dialog",
+                        cancel,
+                        dialog,
+                    )
+                    .await
+                    .unwrap_or_else(|| Ok(default_dialog_result()))
+                }),
+            )
         });
         async move { future }
     }
@@ -115,19 +191,38 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
         filter: psys_host::dialog::FilterConfig,
     ) -> impl core::future::Future<Output = FutureReader<psys_host::dialog::PickResult>> + Send
     {
+        let span = HostCallSpan::new(accessor, "dialog.pick_file");
         let instance = accessor.instance();
         let future = accessor.with(|mut access| {
             let app_handle = {
                 let ctx = access.get();
                 ctx.app_handle()
             };
-            let plugin_root = {
+            let (plugin_root, plugin_name, cancel) = {
                 let ctx = access.get();
-                ctx.plugin_root().clone()
+                (
+                    ctx.plugin_root().clone(),
+                    ctx.plugin_name().to_string(),
+                    ctx.register_state().dialog_cancel_signal(),
+                )
             };
-            FutureReader::new(instance, &mut access, async move {
-                pick_file_with_dialog(app_handle, plugin_root, config, filter).await
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let picker =
+                        pick_file_with_dialog(app_handle.clone(), plugin_root, config, filter);
+                    until_cancelled(
+                        &app_handle,
+                        &plugin_name,
+                        "dialog.pick_file",
+                        cancel,
+                        picker,
+                    )
+                    .await
+                    .unwrap_or_else(|| Ok(empty_pick_result()))
+                }),
+            )
         });
         async move { future }
     }
@@ -138,20 +233,41 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
     ) -> impl core::future::Future<
         Output = FutureReader<core::result::Result<psys_host::dialog::SaveSession, ()>>,
     > + Send {
+        let span = HostCallSpan::new(accessor, "dialog.save_file_start");
         let instance = accessor.instance();
         let future = accessor.with(|mut access| {
             let app_handle = {
                 let ctx = access.get();
                 ctx.app_handle()
             };
-            let plugin_name = {
+            let (plugin_name, cancel) = {
                 let ctx = access.get();
-                ctx.plugin_name().to_string()
+                (
+                    ctx.plugin_name().to_string(),
+                    ctx.register_state().dialog_cancel_signal(),
+                )
             };
-            FutureReader::new(instance, &mut access, async move {
-                let result = save_file_start_with_dialog(app_handle, plugin_name, filter).await;
-                Ok::<core::result::Result<psys_host::dialog::SaveSession, ()>, Error>(result)
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let dialog = save_file_start_with_dialog(
+                        app_handle.clone(),
+                        plugin_name.clone(),
+                        filter,
+                    );
+                    let result = until_cancelled(
+                        &app_handle,
+                        &plugin_name,
+                        "dialog.save_file_start",
+                        cancel,
+                        dialog,
+                    )
+                    .await
+                    .unwrap_or(Err(()));
+                    Ok::<core::result::Result<psys_host::dialog::SaveSession, ()>, Error>(result)
+                }),
+            )
         });
         async move { future }
     }
@@ -161,13 +277,14 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
         session_id: u64,
         data: HostVec<u8>,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "dialog.save_file_write_chunk");
         let instance = accessor.instance();
         let future = accessor.with(|mut access| {
             let plugin_name = {
                 let ctx = access.get();
                 ctx.plugin_name().to_string()
             };
-            FutureReader::new(instance, &mut access, async move {
+            FutureReader::new(instance, &mut access, span.instrument(async move {
                 let key = (plugin_name.clone(), session_id);
                 let write_result = {
                     let mut sessions = SAVE_FILE_SESSIONS
@@ -194,7 +311,7 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
                     return Ok::<core::result::Result<(), ()>, Error>(Err(()));
                 }
                 Ok::<core::result::Result<(), ()>, Error>(Ok(()))
-            })
+            }))
         });
         async move { future }
     }
@@ -203,13 +320,14 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
         session_id: u64,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "dialog.save_file_finish");
         let instance = accessor.instance();
         let future = accessor.with(|mut access| {
             let plugin_name = {
                 let ctx = access.get();
                 ctx.plugin_name().to_string()
             };
-            FutureReader::new(instance, &mut access, async move {
+            FutureReader::new(instance, &mut access, span.instrument(async move {
                 let key = (plugin_name.clone(), session_id);
                 let mut session = {
                     let mut sessions = SAVE_FILE_SESSIONS
@@ -237,7 +355,7 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
                 }
 
                 Ok::<core::result::Result<(), ()>, Error>(Ok(()))
-            })
+            }))
         });
         async move { future }
     }
@@ -246,13 +364,15 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
         session_id: u64,
     ) -> impl core::future::Future<Output = FutureReader<()>> + Send {
+        let span = HostCallSpan::new(accessor, "dialog.save_file_abort");
         let instance = accessor.instance();
-        let future = accessor.with(|mut access| {
-            let plugin_name = {
-                let ctx = access.get();
-                ctx.plugin_name().to_string()
-            };
-            FutureReader::new(instance, &mut access, async move {
+        let future =
+            accessor.with(|mut access| {
+                let plugin_name = {
+                    let ctx = access.get();
+                    ctx.plugin_name().to_string()
+                };
+                FutureReader::new(instance, &mut access, span.instrument(async move {
                 let key = (plugin_name.clone(), session_id);
                 let removed = {
                     let mut sessions = SAVE_FILE_SESSIONS
@@ -268,10 +388,170 @@ impl psys_host::dialog::HostWithStore for PluginCtx {
                     );
                 }
                 Ok::<(), Error>(())
-            })
+            }))
+            });
+        async move { future }
+    }
+
+    fn open_file_start<T>(
+        accessor: &Accessor<T, Self>,
+        filter: psys_host::dialog::FilterConfig,
+    ) -> impl core::future::Future<
+        Output = FutureReader<core::result::Result<psys_host::dialog::OpenSession, ()>>,
+    > + Send {
+        let span = HostCallSpan::new(accessor, "dialog.open_file_start");
+        let instance = accessor.instance();
+        let future = accessor.with(|mut access| {
+            let (app_handle, plugin_name, cancel) = {
+                let ctx = access.get();
+                (
+                    ctx.app_handle(),
+                    ctx.plugin_name().to_string(),
+                    ctx.register_state().dialog_cancel_signal(),
+                )
+            };
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let dialog = open_file_start_with_dialog(
+                        app_handle.clone(),
+                        plugin_name.clone(),
+                        filter,
+                    );
+                    let result = until_cancelled(
+                        &app_handle,
+                        &plugin_name,
+                        "dialog.open_file_start",
+                        cancel,
+                        dialog,
+                    )
+                    .await
+                    .unwrap_or(Err(()));
+                    Ok::<core::result::Result<psys_host::dialog::OpenSession, ()>, Error>(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn open_file_read_chunk<T>(
+        accessor: &Accessor<T, Self>,
+        session_id: u64,
+        max_len: u32,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<HostVec<u8>, ()>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "dialog.open_file_read_chunk");
+        let instance = accessor.instance();
+        let future = accessor.with(|mut access| {
+            let plugin_name = access.get().plugin_name().to_string();
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let result = tokio::task::spawn_blocking(move || {
+                        read_open_file_chunk(&plugin_name, session_id, max_len)
+                    })
+                    .await
+                    .unwrap_or(Err(()));
+                    Ok::<core::result::Result<HostVec<u8>, ()>, Error>(result)
+                }),
+            )
         });
         async move { future }
     }
+
+    fn open_file_finish<T>(
+        accessor: &Accessor<T, Self>,
+        session_id: u64,
+    ) -> impl core::future::Future<Output = FutureReader<()>> + Send {
+        let span = HostCallSpan::new(accessor, "dialog.open_file_finish");
+        let instance = accessor.instance();
+        let future = accessor.with(|mut access| {
+            let plugin_name = access.get().plugin_name().to_string();
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    // 读到末尾时会话已自动释放，这里找不到会话不视为错误
+                    remove_open_file_session(&(plugin_name, session_id));
+                    Ok::<(), Error>(())
+                }),
+            )
+        });
+        async move { future }
+    }
+}
+
+async fn show_dialog_with_style(
+    app_handle: AppHandle,
+    plugin_name: String,
+    dialog_type: psys_host::dialog::DialogType,
+    style: psys_host::dialog::DialogStyle,
+    info: psys_host::dialog::DialogInfo,
+) -> Result<psys_host::dialog::DialogResult, Error> {
+    match (dialog_type, style) {
+        (psys_host::dialog::DialogType::Alert, psys_host::dialog::DialogStyle::System) => {
+            show_system_alert(app_handle, plugin_name, info).await
+        }
+        (_, psys_host::dialog::DialogStyle::Website) => {
+            show_website_dialog(app_handle, plugin_name, dialog_type, info).await
+        }
+        _ => {
+            log::warn!(
+                "dialog::show_dialog receive an unimplemented combination, type={:?} style={:?}, and return the default result",
+                dialog_type,
+                style
+            );
+            Ok(default_dialog_result())
+        }
+    }
+}
+
+/// 等待弹窗结果，插件被停用或主动取消时提前返回 `None`。
+/// 网页弹窗和前端文件选择器通过事件通知前端关闭；原生系统弹窗无法由宿主关闭，
+/// 用户之后的选择会被直接丢弃
+/// 对话框未返回前被取消或被丢弃（插件放弃了这次调用）时通知前端关闭对话框
+struct OpenDialog<'a> {
+    app_handle: &'a AppHandle,
+    plugin_name: &'a str,
+    operation: &'a str,
+    finished: bool,
+}
+
+impl Drop for OpenDialog<'_> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        log::info!("[plugin:{}] {} cancelled", self.plugin_name, self.operation);
+        let _ = self.app_handle.emit(
+            PLUGIN_DIALOG_CANCEL_EVENT,
+            serde_json::json!({ "plugin": self.plugin_name }),
+        );
+    }
+}
+
+async fn until_cancelled<F: core::future::Future>(
+    app_handle: &AppHandle,
+    plugin_name: &str,
+    operation: &str,
+    mut cancel: watch::Receiver<bool>,
+    future: F,
+) -> Option<F::Output> {
+    let mut dialog = OpenDialog {
+        app_handle,
+        plugin_name,
+        operation,
+        finished: false,
+    };
+    tokio::select! {
+        result = future => {
+            dialog.finished = true;
+            Some(result)
+        }
+        _ = cancel.wait_for(|cancelled| *cancelled) => None,
+    }
 }
 
 async fn show_system_alert(
@@ -395,58 +675,91 @@ async fn pick_file_with_dialog(
     filter: psys_host::dialog::FilterConfig,
 ) -> Result<psys_host::dialog::PickResult, Error> {
     let filter = DialogFileFilter::from(filter);
-    let selected = match pick_file_with_frontend(&app_handle, &filter).await {
-        Ok(selected) => selected,
-        Err(err) => {
-            log::warn!(
-                "dialog::pick_file frontend picker failed, falling back to direct dialog: {err}"
-            );
-            pick_file_with_direct_dialog(&app_handle, &filter).await
-        }
+    let Some(file_path) = select_file(&app_handle, &filter).await else {
+        return Ok(empty_pick_result());
     };
 
-    let Some(file_path) = selected else {
+    let file_name = resolve_file_name(&file_path);
+    if !config.read && config.copy_to.is_none() {
         return Ok(psys_host::dialog::PickResult {
-            name: HostString::default(),
-            data: HostVec::new(),
+            name: file_name.into(),
+            data: Vec::new(),
         });
-    };
+    }
 
-    let file_name = resolve_file_name(&file_path);
-    let should_read = config.read || config.copy_to.is_some();
-    let file_data = if should_read {
-        match app_handle.fs().read(file_path.clone()) {
-            Ok(data) => data,
-            Err(err) => {
-                log::error!("dialog::pick_file failed to read file: {err}");
-                Vec::new()
-            }
+    let mut options = OpenOptions::new();
+    options.read(true);
+    let file = match app_handle.fs().open(file_path, options) {
+        Ok(file) => file,
+        Err(err) => {
+            log::error!("dialog::pick_file failed to open file: {err}");
+            return Ok(psys_host::dialog::PickResult {
+                name: file_name.into(),
+                data: Vec::new(),
+            });
         }
-    } else {
-        Vec::new()
     };
+    let size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    // 只有不超过阈值的文件才内联返回，更大的文件应改用 `open_file_start` 分块读取
+    let inline = config.read && size <= PICK_FILE_INLINE_MAX_BYTES;
+    if config.read && !inline {
+        log::warn!(
+            "dialog::pick_file file is {} bytes (inline max {}), use open_file_start to read it in chunks",
+            size,
+            PICK_FILE_INLINE_MAX_BYTES
+        );
+    }
+    let mut file = tokio::fs::File::from_std(file);
+    let mut data = Vec::new();
+    if inline {
+        if let Err(err) = file.read_to_end(&mut data).await {
+            log::error!("dialog::pick_file failed to read file: {err}");
+            data.clear();
+        }
+    }
 
     if let Some(target_dir) = config.copy_to {
-        if let Some(dest) = build_copy_target(plugin_root, target_dir.into(), &file_name) {
+        if let Some(dest) = build_copy_target(plugin_root.clone(), target_dir.into(), &file_name) {
             if let Some(parent) = dest.parent() {
                 if let Err(err) = tokio::fs::create_dir_all(parent).await {
                     log::warn!("dialog::pick_file failed to create dir: {err}");
                 }
             }
-            if let Err(err) = tokio::fs::write(&dest, &file_data).await {
+            // 已内联读取的直接写出，否则流式复制，不把整个文件读入内存
+            let copied = if inline {
+                tokio::fs::write(&dest, &data).await
+            } else {
+                match tokio::fs::File::create(&dest).await {
+                    Ok(mut dest_file) => {
+                        tokio::io::copy(&mut file, &mut dest_file).await.map(|_| ())
+                    }
+                    Err(err) => Err(err),
+                }
+            };
+            if let Err(err) = copied {
                 log::warn!("dialog::pick_file failed to copy file: {err}");
             }
+            // 复制进插件目录的文件不经过 WASI 写入计数
+            super::storage::invalidate_storage_usage(&plugin_root);
         }
     }
 
-    let data = if config.read { file_data } else { Vec::new() };
-
     Ok(psys_host::dialog::PickResult {
         name: file_name.into(),
         data,
     })
 }
 
+async fn select_file(app_handle: &AppHandle, filter: &DialogFileFilter) -> Option<FilePath> {
+    match pick_file_with_frontend(app_handle, filter).await {
+        Ok(selected) => selected,
+        Err(err) => {
+            log::warn!("dialog frontend file picker failed, falling back to direct dialog: {err}");
+            pick_file_with_direct_dialog(app_handle, filter).await
+        }
+    }
+}
+
 async fn pick_file_with_frontend(
     app_handle: &AppHandle,
     filter: &DialogFileFilter,
@@ -563,6 +876,78 @@ async fn save_file_start_with_dialog(
     })
 }
 
+async fn open_file_start_with_dialog(
+    app_handle: AppHandle,
+    plugin_name: String,
+    filter: psys_host::dialog::FilterConfig,
+) -> core::result::Result<psys_host::dialog::OpenSession, ()> {
+    let filter = DialogFileFilter::from(filter);
+    let file_path = select_file(&app_handle, &filter).await.ok_or(())?;
+
+    let file_name = resolve_file_name(&file_path);
+    let mut options = OpenOptions::new();
+    options.read(true);
+
+    let file = match app_handle.fs().open(file_path, options) {
+        Ok(file) => file,
+        Err(err) => {
+            log::error!("dialog::open_file_start open source failed: {err}");
+            return Err(());
+        }
+    };
+    let size = file.metadata().map(|meta| meta.len()).ok();
+
+    let session_id = insert_open_file_session(plugin_name, file);
+
+    Ok(psys_host::dialog::OpenSession {
+        session_id,
+        name: file_name.into(),
+        size,
+    })
+}
+
+/// 读取下一块数据，返回空列表表示已读到末尾，此时会话随之释放。包含阻塞的文件读取
+fn read_open_file_chunk(
+    plugin_name: &str,
+    session_id: u64,
+    max_len: u32,
+) -> core::result::Result<HostVec<u8>, ()> {
+    let key = (plugin_name.to_string(), session_id);
+    let session = OPEN_FILE_SESSIONS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .get(&key)
+        .cloned();
+    let Some(session) = session else {
+        log::warn!(
+            "dialog::open_file_read_chunk session not found: plugin={} session_id={}",
+            plugin_name,
+            session_id
+        );
+        return Err(());
+    };
+
+    let mut chunk = Vec::new();
+    let limit = max_len.clamp(1, OPEN_FILE_CHUNK_MAX_BYTES) as u64;
+    let result = {
+        let mut session = session.lock().unwrap_or_else(|poison| poison.into_inner());
+        (&mut session.file).take(limit).read_to_end(&mut chunk)
+    };
+    if let Err(err) = result {
+        log::error!(
+            "dialog::open_file_read_chunk failed: plugin={} session_id={} err={err}",
+            plugin_name,
+            session_id
+        );
+        remove_open_file_session(&key);
+        return Err(());
+    }
+    if chunk.is_empty() {
+        remove_open_file_session(&key);
+    }
+    Ok(chunk)
+}
+
 fn build_button_config(buttons: Vec<ButtonSpec>) -> (MessageDialogButtons, Vec<ButtonSpec>) {
     if buttons.is_empty() {
         return (MessageDialogButtons::Ok, Vec::new());
@@ -605,6 +990,13 @@ fn resolve_dialog_result(result: MessageDialogResult, buttons: &[ButtonSpec]) ->
     clicked.into()
 }
 
+fn empty_pick_result() -> psys_host::dialog::PickResult {
+    psys_host::dialog::PickResult {
+        name: HostString::default(),
+        data: HostVec::new(),
+    }
+}
+
 fn default_dialog_result() -> psys_host::dialog::DialogResult {
     psys_host::dialog::DialogResult {
         clicked_btn_id: HostString::default(),
@@ -680,6 +1072,8 @@ impl From<psys_host::dialog::DialogButton> for ButtonSpec {
 
 const WEBSITE_DIALOG_METHOD: &str = "host/dialog/show_dialog";
 const FRONT_FILE_OPEN_PICKER_METHOD: &str = "host/file/open_picker";
+// 插件的弹窗被取消时通知前端关闭对应的网页弹窗/文件选择器
+const PLUGIN_DIALOG_CANCEL_EVENT: &str = "plugin-dialog-cancel";
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
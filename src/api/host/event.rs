@@ -1,67 +1,620 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
+use once_cell::sync::Lazy;
+
+use crate::EventRetryPolicy;
 use crate::bindings::astrobox::psys_host;
+use crate::flood::{EVENT_FLOOD_CODE, FloodVerdict};
+use crate::limits::DeadlineExceeded;
+use crate::manager::DeadLetter;
+use crate::plugin::{DispatchUnavailable, PluginRuntime};
+
+use super::{HostString, HostVec, PluginCtx};
+
+const EVENT_SCHEMA_MAX_ERRORS: usize = 3;
+// 单个二进制事件负载的上限
+const EVENT_BYTES_MAX_LEN: usize = 1024 * 1024;
+// 挂起期间发送事件返回给插件的错误
+const SUSPENDED_ERROR: &str = "plugins are suspended";
+
+struct EventSchema {
+    owner: String,
+    validator: Arc<jsonschema::Validator>,
+}
+
+// 事件名 -> 声明的 JSON Schema，未声明 schema 的事件不做校验
+static EVENT_SCHEMAS: Lazy<Mutex<HashMap<String, EventSchema>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn declare_event_schema(owner: &str, event_name: &str, schema: &str) -> Result<(), String> {
+    let event_name = event_name.trim();
+    if event_name.is_empty() {
+        return Err("event name is empty".to_string());
+    }
+
+    let schema: serde_json::Value =
+        serde_json::from_str(schema).map_err(|err| format!("schema is not valid JSON: {err}"))?;
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|err| format!("schema is not a valid JSON Schema: {err}"))?;
+
+    let mut guard = EVENT_SCHEMAS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if let Some(existing) = guard.get(event_name) {
+        if existing.owner != owner {
+            return Err(format!(
+                "schema for event '{}' is already declared by plugin '{}'",
+                event_name, existing.owner
+            ));
+        }
+    }
+    guard.insert(
+        event_name.to_string(),
+        EventSchema {
+            owner: owner.to_string(),
+            validator: Arc::new(validator),
+        },
+    );
+    Ok(())
+}
 
-use super::{HostString, PluginCtx};
+fn validate_event_payload(event_name: &str, payload: &str) -> Result<(), String> {
+    let validator = {
+        let guard = EVENT_SCHEMAS
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        match guard.get(event_name.trim()) {
+            Some(schema) => Arc::clone(&schema.validator),
+            None => return Ok(()),
+        }
+    };
+
+    let instance: serde_json::Value =
+        serde_json::from_str(payload).map_err(|err| format!("payload is not valid JSON: {err}"))?;
+    let errors = validator
+        .iter_errors(&instance)
+        .take(EVENT_SCHEMA_MAX_ERRORS)
+        .map(|err| format!("{} (at '{}')", err, err.instance_path))
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// 插件卸载或重启时移除其声明的事件 schema
+pub(crate) fn forget_event_schemas(owner: &str) {
+    let mut guard = EVENT_SCHEMAS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    guard.retain(|_, schema| schema.owner != owner);
+}
 
 impl psys_host::event::Host for PluginCtx {
-    fn send_event(&mut self, event_name: HostString, payload: HostString) -> wasmtime::Result<()> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "event.declare_schema")
+        )
+    )]
+    fn declare_schema(
+        &mut self,
+        event_name: HostString,
+        json_schema: HostString,
+    ) -> wasmtime::Result<core::result::Result<(), HostString>> {
+        let result = declare_event_schema(self.plugin_name(), &event_name, &json_schema);
+        if let Err(err) = &result {
+            log::warn!(
+                "[plugin:{}] declare_schema '{}' rejected: {}",
+                self.plugin_name(),
+                event_name,
+                err
+            );
+        }
+        Ok(result.map_err(HostString::from))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "event.send_event")
+        )
+    )]
+    fn send_event(
+        &mut self,
+        event_name: HostString,
+        payload: HostString,
+    ) -> wasmtime::Result<core::result::Result<(), HostString>> {
         let event_name = event_name.to_string();
         let payload_raw = payload.to_string();
         let source_plugin = self.plugin_name().to_string();
 
+        if crate::suspension::is_suspended() {
+            log::debug!(
+                "[plugin:{}] send_event '{}' rejected while plugins are suspended",
+                source_plugin,
+                event_name
+            );
+            return Ok(Err(HostString::from(SUSPENDED_ERROR.to_string())));
+        }
+
+        if !admit_outbound_event(&source_plugin) {
+            return Ok(Ok(()));
+        }
+
+        // 声明了 schema 的事件在派发前校验，避免不合法的负载到达接收方，校验失败的原因返回给发送方
+        if let Err(err) = validate_event_payload(&event_name, &payload_raw) {
+            let err = format!("payload does not match declared schema: {err}");
+            log::error!(
+                "[plugin:{}] send_event '{}' rejected: {}",
+                source_plugin,
+                event_name,
+                err
+            );
+            return Ok(Err(HostString::from(err)));
+        }
+
         let message = serde_json::json!({
             "eventName": event_name.clone(),
             "payload": payload_raw,
         })
         .to_string();
+        broadcast_plugin_event(source_plugin, event_name, PluginEvent::Json(message));
+        Ok(Ok(()))
+    }
 
-        let dispatch_name = event_name.clone();
-        let log_name = event_name;
-        let dispatch_payload = message.clone();
-
-        tauri::async_runtime::spawn({
-            let dispatch_payload = dispatch_payload;
-            let dispatch_name = dispatch_name;
-            let log_name = log_name;
-            let source_plugin = source_plugin;
-            async move {
-                if let Err(err) = crate::with_plugin_manager_async({
-                    let event_name = dispatch_name.clone();
-                    let payload = dispatch_payload.clone();
-                    let source_plugin = source_plugin.clone();
-                    move |pm| {
-                        let active_plugins = pm
-                            .plugins
-                            .iter()
-                            .filter(|(_, plugin)| plugin.state.loaded && !plugin.state.disabled)
-                            .filter(|(name, _)| name.as_str() != source_plugin.as_str())
-                            .map(|(name, plugin)| (name.clone(), plugin.runtime.clone()))
-                            .collect::<Vec<_>>();
-                        let event_name = event_name.clone();
-                        let payload = payload.clone();
-                        Box::pin(async move {
-                            for (name, runtime) in active_plugins {
-                                if let Err(err) =
-                                    runtime.dispatch_plugin_message(payload.clone()).await
-                                {
-                                    log::error!(
-                                        "Failed to deliver plugin event '{}' to {}: {err}",
-                                        event_name.as_str(),
-                                        name
-                                    );
-                                }
-                            }
-                        })
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "event.send_event_bytes")
+        )
+    )]
+    fn send_event_bytes(
+        &mut self,
+        event_name: HostString,
+        payload: HostVec<u8>,
+    ) -> wasmtime::Result<core::result::Result<(), HostString>> {
+        let event_name = event_name.to_string();
+        let source_plugin = self.plugin_name().to_string();
+
+        if crate::suspension::is_suspended() {
+            log::debug!(
+                "[plugin:{}] send_event_bytes '{}' rejected while plugins are suspended",
+                source_plugin,
+                event_name
+            );
+            return Ok(Err(HostString::from(SUSPENDED_ERROR.to_string())));
+        }
+        if !admit_outbound_event(&source_plugin) {
+            return Ok(Ok(()));
+        }
+        if payload.len() > EVENT_BYTES_MAX_LEN {
+            let err = format!(
+                "payload is {} bytes (max {})",
+                payload.len(),
+                EVENT_BYTES_MAX_LEN
+            );
+            log::error!(
+                "[plugin:{}] send_event_bytes '{}' rejected: {}",
+                source_plugin,
+                event_name,
+                err
+            );
+            return Ok(Err(HostString::from(err)));
+        }
+
+        let event = PluginEvent::Bytes {
+            event_name: event_name.clone(),
+            payload: Arc::new(payload.to_vec()),
+        };
+        broadcast_plugin_event(source_plugin, event_name, event);
+        Ok(Ok(()))
+    }
+}
+
+/// 计入插件的事件速率，返回是否继续派发；首次超出预算且策略为停止时在后台停止插件
+fn admit_outbound_event(source_plugin: &str) -> bool {
+    admit_flood_verdict(
+        source_plugin,
+        crate::flood::record_outbound_event(source_plugin),
+        report_event_flood,
+    )
+}
+
+fn admit_flood_verdict(
+    source_plugin: &str,
+    verdict: FloodVerdict,
+    report_fatal: impl FnOnce(String, String, String),
+) -> bool {
+    match verdict {
+        FloodVerdict::Allow => true,
+        FloodVerdict::Drop => false,
+        FloodVerdict::Disable => {
+            report_fatal(
+                source_plugin.to_string(),
+                EVENT_FLOOD_CODE.to_string(),
+                "plugin exceeded its event-rate budget".to_string(),
+            );
+            false
+        }
+    }
+}
+
+/// 经 `report_fatal` 停止插件，需要等待当前调用返回，放到独立任务中执行
+fn report_event_flood(plugin_name: String, code: String, message: String) {
+    tokio::spawn(async move {
+        let result = crate::with_plugin_manager_async(move |pm| {
+            Box::pin(async move { pm.report_fatal(&plugin_name, code, message).await })
+        })
+        .await;
+        if let Err(err) = result {
+            log::error!("[pluginsystem] failed to stop flooding plugin: {err}");
+        }
+    });
+}
+
+/// 插件间事件：字符串事件使用 JSON 信封，二进制事件原样交给插件的 `on-event-bytes` 导出
+#[derive(Clone)]
+enum PluginEvent {
+    Json(String),
+    Bytes {
+        event_name: String,
+        payload: Arc<Vec<u8>>,
+    },
+}
+
+impl PluginEvent {
+    async fn deliver(&self, runtime: &PluginRuntime) -> anyhow::Result<()> {
+        match self {
+            Self::Json(message) => runtime.dispatch_plugin_message(message.clone()).await,
+            Self::Bytes {
+                event_name,
+                payload,
+            } => runtime.dispatch_plugin_bytes(event_name, payload).await,
+        }
+    }
+
+    // 死信只保存文本，二进制负载以 base64 信封记录
+    fn dead_letter_payload(&self) -> String {
+        match self {
+            Self::Json(message) => message.clone(),
+            Self::Bytes {
+                event_name,
+                payload,
+            } => bytes_event_envelope(event_name, payload),
+        }
+    }
+}
+
+/// 未导出 `on-event-bytes` 的插件收到的二进制事件信封，负载以 base64 编码
+pub(crate) fn bytes_event_envelope(event_name: &str, payload: &[u8]) -> String {
+    serde_json::json!({
+        "eventName": event_name,
+        "payloadBase64": BASE64_STANDARD.encode(payload),
+    })
+    .to_string()
+}
+
+fn broadcast_plugin_event(source_plugin: String, event_name: String, event: PluginEvent) {
+    tauri::async_runtime::spawn(async move {
+        match crate::with_plugin_manager_async({
+            let event_name = event_name.clone();
+            let event = event.clone();
+            let source_plugin = source_plugin.clone();
+            move |pm| {
+                let active_plugins = pm
+                    .plugins
+                    .iter()
+                    .filter(|(_, plugin)| plugin.state.loaded && !plugin.state.disabled)
+                    .filter(|(name, _)| name.as_str() != source_plugin.as_str())
+                    .filter(|(name, _)| !crate::suspension::is_plugin_suspended(name))
+                    .map(|(name, plugin)| (name.clone(), plugin.runtime.clone()))
+                    .collect::<Vec<_>>();
+                let retry = pm.event_retry();
+                Box::pin(async move {
+                    let mut failed = Vec::new();
+                    for (name, runtime) in active_plugins {
+                        if let Err(err) = event.deliver(&runtime).await {
+                            log::error!(
+                                "Failed to deliver plugin event '{}' to {}: {err}",
+                                event_name.as_str(),
+                                name
+                            );
+                            failed.push((name, err));
+                        }
                     }
+                    (retry, failed)
                 })
-                .await
-                {
-                    log::error!(
-                        "Failed to broadcast plugin event '{}': {err}",
-                        log_name.as_str()
-                    );
+            }
+        })
+        .await
+        {
+            Ok((retry, failed)) => {
+                // 退避等待放在插件线程之外，避免阻塞命令队列
+                for (name, error) in failed {
+                    tauri::async_runtime::spawn(retry_plugin_message(
+                        retry,
+                        name,
+                        source_plugin.clone(),
+                        event_name.clone(),
+                        event.clone(),
+                        error,
+                    ));
                 }
             }
-        });
-        Ok(())
+            Err(err) => {
+                log::error!(
+                    "Failed to broadcast plugin event '{}': {err}",
+                    event_name.as_str()
+                );
+            }
+        }
+    });
+}
+
+/// 插件暂时无法接收（挂起、实例重建中）或调用超时的失败才重试，wasm trap 等重试也不会成功
+fn is_transient_delivery_error(err: &anyhow::Error) -> bool {
+    err.is::<DispatchUnavailable>() || err.is::<DeadlineExceeded>()
+}
+
+#[derive(Debug)]
+enum RetryOutcome {
+    Delivered { attempts: u32 },
+    // 目标插件已停用或被移除，事件不再投递
+    Abandoned,
+    DeadLetter { error: String, attempts: u32 },
+}
+
+/// 按退避策略重试，`attempt` 返回 `None` 表示目标插件已不可用
+async fn retry_delivery<F, Fut>(
+    retry: EventRetryPolicy,
+    mut error: anyhow::Error,
+    mut attempt: F,
+) -> RetryOutcome
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<anyhow::Result<()>>>,
+{
+    let mut attempts = 1;
+    if retry.enabled {
+        for retry_index in 1..=retry.max_retries {
+            if !is_transient_delivery_error(&error) {
+                break;
+            }
+            tokio::time::sleep(retry.backoff(retry_index)).await;
+            attempts += 1;
+            match attempt().await {
+                None => return RetryOutcome::Abandoned,
+                Some(Ok(())) => return RetryOutcome::Delivered { attempts },
+                Some(Err(err)) => error = err,
+            }
+        }
+    }
+    RetryOutcome::DeadLetter {
+        error: error.to_string(),
+        attempts,
+    }
+}
+
+/// 重试派发失败的事件，重试耗尽或失败不可重试时记入目标插件的死信。
+/// 每次重试都经插件管理线程重新取目标插件，跳过已停用、已移除或被挂起的插件
+async fn retry_plugin_message(
+    retry: EventRetryPolicy,
+    name: String,
+    source_plugin: String,
+    event_name: String,
+    event: PluginEvent,
+    error: anyhow::Error,
+) {
+    let outcome = retry_delivery(retry, error, || {
+        let name = name.clone();
+        let event = event.clone();
+        async move {
+            let result = crate::with_plugin_manager_async(move |pm| {
+                let runtime = pm
+                    .plugins
+                    .get(&name)
+                    .filter(|plugin| plugin.state.loaded && !plugin.state.disabled)
+                    .map(|plugin| plugin.runtime.clone());
+                Box::pin(async move {
+                    let runtime = runtime?;
+                    if crate::suspension::is_plugin_suspended(&name) {
+                        return Some(Err(
+                            DispatchUnavailable::new(&name, "plugin is suspended").into()
+                        ));
+                    }
+                    Some(event.deliver(&runtime).await)
+                })
+            })
+            .await;
+            match result {
+                Ok(outcome) => outcome,
+                Err(err) => Some(Err(err)),
+            }
+        }
+    })
+    .await;
+
+    let (error, attempts) = match outcome {
+        RetryOutcome::Delivered { attempts } => {
+            log::info!(
+                "[plugin:{}] event '{}' delivered after {} attempts",
+                name,
+                event_name,
+                attempts
+            );
+            return;
+        }
+        RetryOutcome::Abandoned => {
+            log::info!(
+                "[plugin:{}] event '{}' dropped: plugin is no longer running",
+                name,
+                event_name
+            );
+            return;
+        }
+        RetryOutcome::DeadLetter { error, attempts } => (error, attempts),
+    };
+
+    let letter = DeadLetter {
+        event_name,
+        source_plugin,
+        payload: event.dead_letter_payload(),
+        error,
+        attempts,
+        timestamp_ms: chrono::Utc::now().timestamp_millis(),
+    };
+    let result = crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.record_dead_letter(&name, letter) })
+    })
+    .await;
+    if let Err(err) = result {
+        log::error!("[pluginsystem] Failed to record dead letter: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
+
+    use super::{
+        RetryOutcome, admit_flood_verdict, bytes_event_envelope, declare_event_schema,
+        forget_event_schemas, retry_delivery, validate_event_payload,
+    };
+    use crate::EventRetryPolicy;
+    use crate::flood::{
+        EVENT_FLOOD_CODE, EventFloodAction, EventFloodPolicy, record_at, reset_event_rate,
+    };
+    use crate::plugin::DispatchUnavailable;
+
+    fn retry_policy(max_retries: u32) -> EventRetryPolicy {
+        EventRetryPolicy {
+            enabled: true,
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+        }
+    }
+
+    fn suspended() -> anyhow::Error {
+        DispatchUnavailable::new("retry-test", "plugin is suspended").into()
+    }
+
+    #[test]
+    fn declared_schema_rejects_mismatched_payload() {
+        let schema =
+            r#"{"type":"object","required":["id"],"properties":{"id":{"type":"integer"}}}"#;
+        declare_event_schema("schema-test", "schema-test/event", schema).unwrap();
+
+        assert!(validate_event_payload("schema-test/event", r#"{"id":1}"#).is_ok());
+        assert!(validate_event_payload("schema-test/event", r#"{"id":"x"}"#).is_err());
+        assert!(validate_event_payload("schema-test/event", "not json").is_err());
+        assert!(declare_event_schema("other-plugin", "schema-test/event", schema).is_err());
+
+        forget_event_schemas("schema-test");
+        assert!(validate_event_payload("schema-test/event", "not json").is_ok());
+    }
+
+    #[test]
+    fn event_flood_reports_fatal_once_and_refuses_later_events() {
+        let policy = EventFloodPolicy {
+            max_events_per_sec: 5,
+            window: Duration::from_secs(1),
+            action: EventFloodAction::Disable,
+        };
+        let now = std::time::Instant::now();
+        let mut reported = Vec::new();
+        let admitted = (0..50)
+            .filter(|_| {
+                admit_flood_verdict(
+                    "flood-test",
+                    record_at("flood-test", &policy, now),
+                    |plugin, code, _| reported.push((plugin, code)),
+                )
+            })
+            .count();
+
+        assert_eq!(admitted, 5);
+        assert_eq!(
+            reported,
+            vec![("flood-test".to_string(), EVENT_FLOOD_CODE.to_string())]
+        );
+        reset_event_rate("flood-test");
+    }
+
+    #[test]
+    fn bytes_envelope_round_trips_non_utf8_payload() {
+        let payload = [0xff, 0xfe, 0x00, 0x80, b'a', 0xc3];
+        assert!(std::str::from_utf8(&payload).is_err());
+
+        let envelope: serde_json::Value =
+            serde_json::from_str(&bytes_event_envelope("blob", &payload)).unwrap();
+        assert_eq!(envelope["eventName"], "blob");
+        let decoded = BASE64_STANDARD
+            .decode(envelope["payloadBase64"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_until_delivered() {
+        let calls = AtomicU32::new(0);
+        let outcome = retry_delivery(retry_policy(3), suspended(), || {
+            let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            async move { Some(if call < 2 { Err(suspended()) } else { Ok(()) }) }
+        })
+        .await;
+        assert!(matches!(outcome, RetryOutcome::Delivered { attempts: 3 }));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_go_to_dead_letters() {
+        let outcome = retry_delivery(retry_policy(2), suspended(), || async {
+            Some(Err(suspended()))
+        })
+        .await;
+        match outcome {
+            RetryOutcome::DeadLetter { error, attempts } => {
+                assert_eq!(attempts, 3);
+                assert!(error.contains("plugin is suspended"));
+            }
+            other => panic!("unexpected outcome: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn permanent_failures_and_disabled_retry_skip_retrying() {
+        let calls = AtomicU32::new(0);
+        let attempt = || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Some(Ok(())) }
+        };
+        let trap = retry_delivery(retry_policy(3), anyhow::anyhow!("wasm trap"), attempt).await;
+        assert!(matches!(trap, RetryOutcome::DeadLetter { attempts: 1, .. }));
+
+        let disabled = retry_delivery(EventRetryPolicy::default(), suspended(), attempt).await;
+        assert!(matches!(
+            disabled,
+            RetryOutcome::DeadLetter { attempts: 1, .. }
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn stopped_target_abandons_the_event() {
+        let outcome = retry_delivery(retry_policy(3), suspended(), || async { None }).await;
+        assert!(matches!(outcome, RetryOutcome::Abandoned));
     }
 }
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+
+use crate::bindings::astrobox::psys_host;
+
+use super::{HostString, PluginCtx};
+
+// 插件根目录下保存各插件特性开关的目录，以 `.` 开头不会被当作插件加载，插件更新时也不会被清除
+pub(crate) const FLAGS_DIR: &str = ".flags";
+
+type FlagMap = Arc<Map<String, Value>>;
+
+// 文件路径 -> (读取时的修改时间, 开关)；文件被用户直接修改后按修改时间自动重新读取
+static FLAGS_CACHE: Lazy<Mutex<HashMap<PathBuf, (Option<SystemTime>, FlagMap)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 插件的特性开关文件：`<插件根目录>/.flags/<插件名>.json`，内容为 JSON 对象
+pub(crate) fn flags_path(plugins_root: &Path, plugin_name: &str) -> PathBuf {
+    plugins_root
+        .join(FLAGS_DIR)
+        .join(format!("{plugin_name}.json"))
+}
+
+fn read_flags_file(path: &Path) -> Map<String, Value> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return Map::new(),
+    };
+    match serde_json::from_str::<Value>(&content) {
+        Ok(Value::Object(flags)) => flags,
+        Ok(_) => {
+            log::warn!(
+                "[pluginsystem] flags file {} is not a JSON object, ignored",
+                path.display()
+            );
+            Map::new()
+        }
+        Err(err) => {
+            log::warn!(
+                "[pluginsystem] failed to parse flags file {}: {err}",
+                path.display()
+            );
+            Map::new()
+        }
+    }
+}
+
+// 读取开关；按修改时间重新读取且内容与缓存不同时第二项为 `true`，首次读取不算变化
+fn reload_flags(path: &Path) -> (FlagMap, bool) {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let mut cache = FLAGS_CACHE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let previous = match cache.get(path) {
+        Some((cached_at, flags)) if *cached_at == modified => return (Arc::clone(flags), false),
+        Some((_, flags)) => Some(Arc::clone(flags)),
+        None => None,
+    };
+    let flags = Arc::new(read_flags_file(path));
+    cache.insert(path.to_path_buf(), (modified, Arc::clone(&flags)));
+    let changed = previous.is_some_and(|previous| previous != flags);
+    (flags, changed)
+}
+
+/// 读取插件的特性开关；文件被直接修改过时向插件派发 `flags-changed` 事件
+pub(crate) fn load_flags(plugins_root: &Path, plugin_name: &str) -> FlagMap {
+    let (flags, changed) = reload_flags(&flags_path(plugins_root, plugin_name));
+    if changed {
+        notify_flags_changed(plugin_name.to_string());
+    }
+    flags
+}
+
+// 读取开关的可能正是插件自己的宿主调用，派发需要等待其返回，放到独立任务中执行
+fn notify_flags_changed(plugin_name: String) {
+    tokio::spawn(async move {
+        let result = crate::with_plugin_manager_async(move |pm| {
+            let result = pm.notify_flags_changed(&plugin_name);
+            Box::pin(async move { result })
+        })
+        .await
+        .and_then(|result| result);
+        if let Err(err) = result {
+            log::warn!("[pluginsystem] failed to deliver flags change: {err}");
+        }
+    });
+}
+
+/// 插件被移除时删除其特性开关
+pub(crate) fn forget_flags(plugins_root: &Path, plugin_name: &str) {
+    let path = flags_path(plugins_root, plugin_name);
+    if let Err(err) = fs::remove_file(&path) {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            log::warn!(
+                "[pluginsystem] failed to remove flags file {}: {err}",
+                path.display()
+            );
+        }
+    }
+    FLAGS_CACHE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(&path);
+}
+
+/// 覆盖写入插件的特性开关
+pub(crate) fn store_flags(path: &Path, flags: &Map<String, Value>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(flags)?;
+    fs::write(path, content)
+        .with_context(|| format!("failed to write flags file {}", path.display()))?;
+    FLAGS_CACHE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(path);
+    Ok(())
+}
+
+fn bool_flag(flags: &Map<String, Value>, key: &str) -> Option<bool> {
+    flags.get(key).and_then(Value::as_bool)
+}
+
+fn string_flag(flags: &Map<String, Value>, key: &str) -> Option<String> {
+    flags.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+impl PluginCtx {
+    fn flags(&self) -> FlagMap {
+        let plugins_root = self
+            .plugin_root()
+            .parent()
+            .unwrap_or(self.plugin_root().as_path());
+        load_flags(plugins_root, self.plugin_name())
+    }
+
+    fn warn_mistyped_flag(&self, flags: &Map<String, Value>, key: &str, expected: &str) {
+        if flags.contains_key(key) {
+            log::warn!(
+                "[plugin:{}] flag '{}' is not a {}, using default",
+                self.plugin_name(),
+                key,
+                expected
+            );
+        }
+    }
+}
+
+impl psys_host::flags::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "flags.get_bool")
+        )
+    )]
+    fn get_bool(&mut self, key: HostString, default: bool) -> wasmtime::Result<bool> {
+        let flags = self.flags();
+        Ok(bool_flag(&flags, &key).unwrap_or_else(|| {
+            self.warn_mistyped_flag(&flags, &key, "boolean");
+            default
+        }))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "flags.get_string")
+        )
+    )]
+    fn get_string(&mut self, key: HostString, default: HostString) -> wasmtime::Result<HostString> {
+        let flags = self.flags();
+        Ok(match string_flag(&flags, &key) {
+            Some(value) => HostString::from(value),
+            None => {
+                self.warn_mistyped_flag(&flags, &key, "string");
+                default
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::{bool_flag, flags_path, forget_flags, reload_flags, store_flags, string_flag};
+    use crate::test_support::temp_root;
+
+    #[test]
+    fn flags_fall_back_on_missing_or_mistyped_keys() {
+        let temp = temp_root();
+        let root = temp.path();
+        let path = flags_path(root, "demo");
+
+        assert!(reload_flags(&path).0.is_empty());
+
+        let flags = json!({ "beta": true, "endpoint": "https://example.com", "count": 3 });
+        store_flags(&path, flags.as_object().unwrap()).unwrap();
+        let (flags, changed) = reload_flags(&path);
+        assert!(!changed);
+        assert_eq!(bool_flag(&flags, "beta"), Some(true));
+        assert_eq!(
+            string_flag(&flags, "endpoint").as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(bool_flag(&flags, "count"), None);
+        assert_eq!(string_flag(&flags, "missing"), None);
+    }
+
+    #[test]
+    fn direct_edits_are_detected_and_removal_deletes_the_file() {
+        let temp = temp_root();
+        let root = temp.path();
+        let path = flags_path(root, "demo");
+        store_flags(&path, json!({ "beta": false }).as_object().unwrap()).unwrap();
+        assert!(!reload_flags(&path).1);
+
+        // 用户直接修改文件：只改修改时间不算变化，内容变化时才通知插件
+        let touch = |content: &str, secs: u64| {
+            fs::write(&path, content).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        };
+        touch(r#"{ "beta": false }"#, 1_000);
+        assert!(!reload_flags(&path).1);
+        touch(r#"{ "beta": true }"#, 2_000);
+        let (flags, changed) = reload_flags(&path);
+        assert!(changed);
+        assert_eq!(bool_flag(&flags, "beta"), Some(true));
+        assert!(!reload_flags(&path).1);
+
+        forget_flags(root, "demo");
+        assert!(!path.exists());
+        assert!(reload_flags(&path).0.is_empty());
+    }
+}
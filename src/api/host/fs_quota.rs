@@ -0,0 +1,146 @@
+//! 插件目录的 WASI 写入配额：替换 wasi:filesystem 中会增加文件大小的函数，
+//! 写入前按 `storage` 模块的用量计数检查配额，超出时向插件返回磁盘已满
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use bytes::Bytes;
+use wasmtime::component::{Linker, Resource};
+use wasmtime_wasi::filesystem::{Descriptor, WasiFilesystemView};
+use wasmtime_wasi::p2::bindings::filesystem::types::{ErrorCode, Filesize, HostDescriptor};
+use wasmtime_wasi::p2::{DynOutputStream, OutputStream, Pollable, StreamError, StreamResult};
+
+use super::PluginCtx;
+use super::storage::charge_storage_write;
+
+// 需与 wasmtime-wasi 注册的 WASI 版本一致，否则无法覆盖原有实现
+const WASI_FILESYSTEM_TYPES: &str = "wasi:filesystem/types@0.2.6";
+
+fn storage_full() -> anyhow::Error {
+    std::io::Error::from(std::io::ErrorKind::StorageFull).into()
+}
+
+/// 包装插件打开的文件写入流，每次写入前计入配额
+struct QuotaOutputStream {
+    inner: DynOutputStream,
+    dir: PathBuf,
+}
+
+#[async_trait::async_trait]
+impl Pollable for QuotaOutputStream {
+    async fn ready(&mut self) {
+        self.inner.ready().await
+    }
+}
+
+#[async_trait::async_trait]
+impl OutputStream for QuotaOutputStream {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        if !charge_storage_write(&self.dir, bytes.len() as u64) {
+            return Err(StreamError::LastOperationFailed(storage_full()));
+        }
+        self.inner.write(bytes)
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        self.inner.flush()
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        self.inner.check_write()
+    }
+
+    fn write_zeroes(&mut self, nelem: usize) -> StreamResult<()> {
+        if !charge_storage_write(&self.dir, nelem as u64) {
+            return Err(StreamError::LastOperationFailed(storage_full()));
+        }
+        self.inner.write_zeroes(nelem)
+    }
+
+    async fn cancel(&mut self) {
+        self.inner.cancel().await
+    }
+}
+
+fn wrap_output_stream(
+    ctx: &mut PluginCtx,
+    stream: Resource<DynOutputStream>,
+) -> Result<Resource<DynOutputStream>> {
+    let inner = ctx.table.delete(stream)?;
+    let wrapped: DynOutputStream = Box::new(QuotaOutputStream {
+        inner,
+        dir: ctx.plugin_root().clone(),
+    });
+    Ok(ctx.table.push(wrapped)?)
+}
+
+/// 在 `p2::add_to_linker_async` 之后调用，覆盖文件写入相关的函数
+pub(crate) fn add_to_linker(linker: &mut Linker<PluginCtx>) -> Result<()> {
+    linker.allow_shadowing(true);
+    let mut types = linker.instance(WASI_FILESYSTEM_TYPES)?;
+
+    types.func_wrap_async(
+        "[method]descriptor.write",
+        |mut store, (fd, buffer, offset): (Resource<Descriptor>, Vec<u8>, Filesize)| {
+            Box::new(async move {
+                let ctx = store.data_mut();
+                if !charge_storage_write(ctx.plugin_root(), buffer.len() as u64) {
+                    return Ok((Err(ErrorCode::InsufficientSpace),));
+                }
+                match ctx.filesystem().write(fd, buffer, offset).await {
+                    Ok(written) => Ok((Ok(written),)),
+                    Err(err) => Ok((Err(err.downcast()?),)),
+                }
+            })
+        },
+    )?;
+
+    types.func_wrap_async(
+        "[method]descriptor.set-size",
+        |mut store, (fd, size): (Resource<Descriptor>, Filesize)| {
+            Box::new(async move {
+                let ctx = store.data_mut();
+                let current = match ctx.filesystem().stat(Resource::new_borrow(fd.rep())).await {
+                    Ok(stat) => stat.size,
+                    Err(err) => return Ok((Err(err.downcast()?),)),
+                };
+                if !charge_storage_write(ctx.plugin_root(), size.saturating_sub(current)) {
+                    return Ok((Err(ErrorCode::InsufficientSpace),));
+                }
+                match ctx.filesystem().set_size(fd, size).await {
+                    Ok(()) => Ok((Ok(()),)),
+                    Err(err) => Ok((Err(err.downcast()?),)),
+                }
+            })
+        },
+    )?;
+
+    types.func_wrap_async(
+        "[method]descriptor.write-via-stream",
+        |mut store, (fd, offset): (Resource<Descriptor>, Filesize)| {
+            Box::new(async move {
+                let ctx = store.data_mut();
+                match ctx.filesystem().write_via_stream(fd, offset) {
+                    Ok(stream) => Ok((Ok(wrap_output_stream(ctx, stream)?),)),
+                    Err(err) => Ok((Err(err.downcast()?),)),
+                }
+            })
+        },
+    )?;
+
+    types.func_wrap_async(
+        "[method]descriptor.append-via-stream",
+        |mut store, (fd,): (Resource<Descriptor>,)| {
+            Box::new(async move {
+                let ctx = store.data_mut();
+                match ctx.filesystem().append_via_stream(fd) {
+                    Ok(stream) => Ok((Ok(wrap_output_stream(ctx, stream)?),)),
+                    Err(err) => Ok((Err(err.downcast()?),)),
+                }
+            })
+        },
+    )?;
+
+    linker.allow_shadowing(false);
+    Ok(())
+}
@@ -0,0 +1,646 @@
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::BodyExt;
+use hyper_util::rt::TokioIo;
+use once_cell::sync::Lazy;
+use tokio::net::TcpStream;
+use wasmtime::component::{Component, Linker, Resource};
+use wasmtime::{Engine, StoreContextMut};
+use wasmtime_wasi_http::bindings::http::outgoing_handler::{
+    FutureIncomingResponse, OutgoingRequest, RequestOptions,
+};
+use wasmtime_wasi_http::bindings::http::types::{DnsErrorPayload, ErrorCode};
+use wasmtime_wasi_http::body::HyperOutgoingBody;
+use wasmtime_wasi_http::types::{
+    HostFutureIncomingResponse, IncomingResponse, OutgoingRequestConfig,
+    default_send_request_handler,
+};
+use wasmtime_wasi_http::{HttpResult, hyper_request_error};
+
+use super::PluginCtx;
+
+const OUTGOING_HANDLER_INTERFACE: &str = "wasi:http/outgoing-handler@";
+
+// 进程内只探测一次当前平台能否发起 HTTPS 请求，不可用时记录原因
+static HTTP_UNAVAILABLE_REASON: Lazy<Option<String>> = Lazy::new(|| {
+    let reason = probe_http_support().err();
+    if let Some(reason) = &reason {
+        log::warn!("[pluginsystem] wasi-http running in degraded mode: {reason}");
+    }
+    reason
+});
+
+// 初始化 TLS 配置：构建缺少 rustls 加密后端或根证书时会在这里 panic，
+// wasmtime-wasi-http 的默认发送逻辑使用同样的配置，会以同样的方式失败
+fn probe_http_support() -> Result<(), String> {
+    std::panic::catch_unwind(|| {
+        Lazy::force(&TLS_CONNECTOR);
+    })
+    .map_err(|panic| {
+        let detail = panic
+            .downcast_ref::<&str>()
+            .map(|detail| detail.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        format!("TLS is not available on this platform: {detail}")
+    })
+}
+
+/// wasi-http 在当前平台不可用时返回原因；插件发起的请求会直接收到该错误而不是陷入 trap
+pub(crate) fn http_unavailable_reason() -> Option<&'static str> {
+    HTTP_UNAVAILABLE_REASON.as_deref()
+}
+
+/// wasi-http 不可用时用桩函数替换各组件导入的 `outgoing-handler.handle`，请求直接返回错误。
+/// `types` 接口仍由 wasmtime-wasi-http 提供，插件照常构造请求
+pub(crate) fn add_unavailable_stub_to_linker<'a>(
+    linker: &mut Linker<PluginCtx>,
+    engine: &Engine,
+    components: impl IntoIterator<Item = &'a Component>,
+    reason: &'static str,
+) -> anyhow::Result<()> {
+    // 按组件实际导入的版本注册，精确匹配优先于 wasmtime-wasi-http 注册的版本
+    let mut interfaces = BTreeSet::new();
+    for component in components {
+        for (name, _) in component.component_type().imports(engine) {
+            if name.starts_with(OUTGOING_HANDLER_INTERFACE) {
+                interfaces.insert(name.to_string());
+            }
+        }
+    }
+    linker.allow_shadowing(true);
+    for interface in interfaces {
+        linker.instance(&interface)?.func_wrap(
+            "handle",
+            move |mut store: StoreContextMut<'_, PluginCtx>,
+                  (request, options): (
+                Resource<OutgoingRequest>,
+                Option<Resource<RequestOptions>>,
+            )| {
+                let table = &mut store.data_mut().table;
+                let _ = table.delete(request);
+                if let Some(options) = options {
+                    let _ = table.delete(options);
+                }
+                let error = ErrorCode::InternalError(Some(format!(
+                    "http unavailable on this platform: {reason}"
+                )));
+                Ok((Err::<Resource<FutureIncomingResponse>, _>(error),))
+            },
+        )?;
+    }
+    linker.allow_shadowing(false);
+    Ok(())
+}
+
+/// 按累计读取量限制响应体大小的包装，逐块透传而不做缓冲
+struct LimitedBody<B> {
+    inner: B,
+    limit: u64,
+    received: u64,
+}
+
+impl<B> LimitedBody<B> {
+    fn new(inner: B, limit: u64) -> Self {
+        Self {
+            inner,
+            limit,
+            received: 0,
+        }
+    }
+}
+
+impl<B> Body for LimitedBody<B>
+where
+    B: Body<Data = Bytes, Error = ErrorCode> + Unpin,
+{
+    type Data = Bytes;
+    type Error = ErrorCode;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = match Pin::new(&mut self.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => frame,
+            other => return other,
+        };
+        if let Some(data) = frame.data_ref() {
+            self.received = self.received.saturating_add(data.len() as u64);
+            if self.received > self.limit {
+                return Poll::Ready(Some(Err(ErrorCode::HttpResponseBodySize(Some(self.limit)))));
+            }
+        }
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// manifest `allowed_ips` 中的一条规则：单个 IP 或 CIDR 网段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IpRule {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpRule {
+    pub(crate) fn parse(rule: &str) -> Option<Self> {
+        let rule = rule.trim();
+        let (addr, prefix) = match rule.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+            None => (rule, None),
+        };
+        let network = IpAddr::from_str(addr).ok()?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max_prefix);
+        (prefix <= max_prefix).then_some(Self { network, prefix })
+    }
+
+    pub(crate) fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+// 与 wasmtime-wasi-http 默认发送逻辑相同的 TLS 配置
+static TLS_CONNECTOR: Lazy<tokio_rustls::TlsConnector> = Lazy::new(|| {
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+    };
+    let config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    tokio_rustls::TlsConnector::from(Arc::new(config))
+});
+
+fn dns_error() -> ErrorCode {
+    ErrorCode::DnsError(DnsErrorPayload {
+        rcode: None,
+        info_code: None,
+    })
+}
+
+/// 解析目标主机并校验所有解析结果都在允许列表内，返回之后要连接的地址。
+/// 连接只使用这里校验过的地址，不再二次解析，防止 DNS 重绑定
+async fn resolve_allowed_destination<R, F>(
+    plugin_name: &str,
+    allowed_ips: &[IpRule],
+    request: &hyper::Request<HyperOutgoingBody>,
+    use_tls: bool,
+    resolve: R,
+) -> Result<SocketAddr, ErrorCode>
+where
+    R: FnOnce(String, u16) -> F,
+    F: Future<Output = std::io::Result<Vec<SocketAddr>>>,
+{
+    let authority = request
+        .uri()
+        .authority()
+        .cloned()
+        .ok_or(ErrorCode::HttpRequestUriInvalid)?;
+    let port = authority
+        .port_u16()
+        .unwrap_or(if use_tls { 443 } else { 80 });
+    let host = authority.host().trim_matches(['[', ']']).to_string();
+    let resolved = resolve(host, port).await.map_err(|_| dns_error())?;
+    let Some(first) = resolved.first().copied() else {
+        return Err(dns_error());
+    };
+
+    if let Some(blocked) = resolved
+        .iter()
+        .find(|addr| !allowed_ips.iter().any(|rule| rule.contains(addr.ip())))
+    {
+        log::warn!(
+            "[plugin:{}] http request to {} blocked: resolved IP {} is not allowlisted",
+            plugin_name,
+            authority,
+            blocked.ip()
+        );
+        return Err(ErrorCode::DestinationIpProhibited);
+    }
+    Ok(first)
+}
+
+/// 连接已校验的地址并发送请求；TLS 的 SNI 和证书校验仍使用请求中的原始主机名
+async fn send_to_pinned_address(
+    mut request: hyper::Request<HyperOutgoingBody>,
+    config: OutgoingRequestConfig,
+    addr: SocketAddr,
+) -> Result<IncomingResponse, ErrorCode> {
+    let OutgoingRequestConfig {
+        use_tls,
+        connect_timeout,
+        first_byte_timeout,
+        between_bytes_timeout,
+    } = config;
+    let authority = request
+        .uri()
+        .authority()
+        .cloned()
+        .ok_or(ErrorCode::HttpRequestUriInvalid)?;
+    let tcp_stream = tokio::time::timeout(connect_timeout, TcpStream::connect(addr))
+        .await
+        .map_err(|_| ErrorCode::ConnectionTimeout)?
+        .map_err(|_| ErrorCode::ConnectionRefused)?;
+
+    let (mut sender, worker) = if use_tls {
+        let host = authority.host().trim_matches(['[', ']']).to_string();
+        let server_name = rustls::pki_types::ServerName::try_from(host).map_err(|_| dns_error())?;
+        let stream = TLS_CONNECTOR
+            .connect(server_name, tcp_stream)
+            .await
+            .map_err(|_| ErrorCode::TlsProtocolError)?;
+        let (sender, conn) = tokio::time::timeout(
+            connect_timeout,
+            hyper::client::conn::http1::handshake(TokioIo::new(stream)),
+        )
+        .await
+        .map_err(|_| ErrorCode::ConnectionTimeout)?
+        .map_err(hyper_request_error)?;
+        let worker = wasmtime_wasi::runtime::spawn(async move {
+            if let Err(err) = conn.await {
+                log::debug!("[pluginsystem] http connection closed with error: {err}");
+            }
+        });
+        (sender, worker)
+    } else {
+        let (sender, conn) = tokio::time::timeout(
+            connect_timeout,
+            hyper::client::conn::http1::handshake(TokioIo::new(tcp_stream)),
+        )
+        .await
+        .map_err(|_| ErrorCode::ConnectionTimeout)?
+        .map_err(hyper_request_error)?;
+        let worker = wasmtime_wasi::runtime::spawn(async move {
+            if let Err(err) = conn.await {
+                log::debug!("[pluginsystem] http connection closed with error: {err}");
+            }
+        });
+        (sender, worker)
+    };
+
+    // 直连目标服务器时请求行只带路径，主机名放在 Host 头中
+    let host = hyper::header::HeaderValue::from_str(authority.as_str())
+        .map_err(|_| ErrorCode::HttpRequestUriInvalid)?;
+    request
+        .headers_mut()
+        .entry(hyper::header::HOST)
+        .or_insert(host);
+    *request.uri_mut() = hyper::Uri::builder()
+        .path_and_query(
+            request
+                .uri()
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or("/"),
+        )
+        .build()
+        .map_err(|_| ErrorCode::HttpRequestUriInvalid)?;
+
+    let resp = tokio::time::timeout(first_byte_timeout, sender.send_request(request))
+        .await
+        .map_err(|_| ErrorCode::ConnectionReadTimeout)?
+        .map_err(hyper_request_error)?
+        .map(|body| body.map_err(hyper_request_error).boxed());
+    Ok(IncomingResponse {
+        resp,
+        worker: Some(worker),
+        between_bytes_timeout,
+    })
+}
+
+/// 有 IP 允许列表时只解析一次目标主机，并直接连接校验过的地址
+async fn send_allowed_request<R, F>(
+    plugin_name: &str,
+    allowed_ips: &[IpRule],
+    request: hyper::Request<HyperOutgoingBody>,
+    config: OutgoingRequestConfig,
+    resolve: R,
+) -> Result<IncomingResponse, ErrorCode>
+where
+    R: FnOnce(String, u16) -> F,
+    F: Future<Output = std::io::Result<Vec<SocketAddr>>>,
+{
+    let addr =
+        resolve_allowed_destination(plugin_name, allowed_ips, &request, config.use_tls, resolve)
+            .await?;
+    send_to_pinned_address(request, config, addr).await
+}
+
+async fn lookup_host(host: String, port: u16) -> std::io::Result<Vec<SocketAddr>> {
+    Ok(tokio::net::lookup_host((host.as_str(), port))
+        .await?
+        .collect())
+}
+
+/// 发送插件的 HTTP 请求，响应体以流的形式交给插件，累计超过 `body_limit` 时返回错误
+pub(crate) fn send_plugin_request(
+    plugin_name: String,
+    allowed_ips: Arc<Vec<IpRule>>,
+    body_limit: u64,
+    request: hyper::Request<HyperOutgoingBody>,
+    config: OutgoingRequestConfig,
+) -> HttpResult<HostFutureIncomingResponse> {
+    let handle = wasmtime_wasi::runtime::spawn(async move {
+        if let Some(reason) = http_unavailable_reason() {
+            log::warn!("[plugin:{}] http request rejected: {}", plugin_name, reason);
+            return Ok(Err(ErrorCode::InternalError(Some(format!(
+                "http unavailable on this platform: {reason}"
+            )))));
+        }
+        let response = if allowed_ips.is_empty() {
+            default_send_request_handler(request, config).await
+        } else {
+            send_allowed_request(&plugin_name, &allowed_ips, request, config, lookup_host).await
+        };
+        let mut response = match response {
+            Ok(response) => response,
+            Err(err) => return Ok(Err(err)),
+        };
+        let (parts, body) = response.resp.into_parts();
+        let body = LimitedBody::new(body, body_limit)
+            .map_err(move |err| {
+                if matches!(err, ErrorCode::HttpResponseBodySize(_)) {
+                    log::warn!(
+                        "[plugin:{}] http response body exceeded {} bytes",
+                        plugin_name,
+                        body_limit
+                    );
+                }
+                err
+            })
+            .boxed();
+        response.resp = hyper::Response::from_parts(parts, body);
+        Ok(Ok(response))
+    });
+    Ok(HostFutureIncomingResponse::pending(handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use futures_util::stream;
+    use http_body::Frame;
+    use http_body_util::{BodyExt, Empty, StreamBody};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+    use wasmtime_wasi_http::body::HyperOutgoingBody;
+    use wasmtime_wasi_http::types::{
+        HostFutureIncomingResponse, IncomingResponse, OutgoingRequestConfig,
+    };
+
+    use super::{IpRule, LimitedBody, send_allowed_request, send_plugin_request};
+
+    fn tls_request(port: u16) -> hyper::Request<HyperOutgoingBody> {
+        let body = Empty::<Bytes>::new()
+            .map_err(|never| match never {})
+            .boxed();
+        hyper::Request::builder()
+            .uri(format!("https://rebind.example:{port}/"))
+            .body(body)
+            .unwrap()
+    }
+
+    fn tls_config() -> OutgoingRequestConfig {
+        OutgoingRequestConfig {
+            use_tls: true,
+            connect_timeout: Duration::from_secs(5),
+            first_byte_timeout: Duration::from_secs(5),
+            between_bytes_timeout: Duration::from_secs(5),
+        }
+    }
+
+    // 第一次解析返回允许的地址，之后的解析都指向不允许的地址
+    fn rebinding_resolver(
+        lookups: &AtomicUsize,
+    ) -> impl FnOnce(String, u16) -> std::future::Ready<std::io::Result<Vec<SocketAddr>>> + '_ {
+        move |_, port| {
+            let ip = if lookups.fetch_add(1, Ordering::SeqCst) == 0 {
+                "127.0.0.1"
+            } else {
+                "10.0.0.1"
+            };
+            std::future::ready(Ok(vec![SocketAddr::new(ip.parse().unwrap(), port)]))
+        }
+    }
+
+    fn chunked_body(
+        chunks: &[&'static [u8]],
+    ) -> StreamBody<impl futures_util::Stream<Item = Result<Frame<Bytes>, ErrorCode>> + Unpin> {
+        let frames = chunks
+            .iter()
+            .map(|chunk| Ok(Frame::data(Bytes::from_static(chunk))))
+            .collect::<Vec<_>>();
+        StreamBody::new(stream::iter(frames))
+    }
+
+    // 本地 HTTP 服务：先发送第一个分块，收到信号后再发送其余分块
+    async fn serve_chunked(listener: TcpListener, rest: oneshot::Receiver<()>) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let read = stream.read(&mut buf).await.unwrap();
+            assert!(read > 0);
+            request.extend_from_slice(&buf[..read]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nfirst\r\n")
+            .await
+            .unwrap();
+        stream.flush().await.unwrap();
+        if rest.await.is_ok() {
+            let _ = stream
+                .write_all(b"6\r\nsecond\r\n5\r\nthird\r\n0\r\n\r\n")
+                .await;
+        }
+    }
+
+    async fn fetch_local(port: u16, body_limit: u64) -> IncomingResponse {
+        let body = Empty::<Bytes>::new()
+            .map_err(|never| match never {})
+            .boxed();
+        let request = hyper::Request::builder()
+            .uri(format!("http://127.0.0.1:{port}/download"))
+            .body(body)
+            .unwrap();
+        let config = OutgoingRequestConfig {
+            use_tls: false,
+            ..tls_config()
+        };
+        let allowed = Arc::new(vec![IpRule::parse("127.0.0.1").unwrap()]);
+        let response = send_plugin_request(
+            "http-test".to_string(),
+            allowed,
+            body_limit,
+            request,
+            config,
+        )
+        .unwrap();
+        let HostFutureIncomingResponse::Pending(handle) = response else {
+            panic!("request should be pending");
+        };
+        handle.await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn plugin_request_streams_a_chunked_download() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (send_rest, rest) = oneshot::channel();
+        let server = tokio::spawn(serve_chunked(listener, rest));
+
+        // `worker` 驱动连接，读完响应体之前不能丢弃
+        let response = fetch_local(port, 64).await;
+        let mut body = response.resp.into_body();
+        // 服务端在读到第一块之前不会发送剩余分块，读到即说明响应体是逐块交付的
+        let first = body.frame().await.unwrap().unwrap();
+        assert_eq!(first.into_data().unwrap(), Bytes::from_static(b"first"));
+        send_rest.send(()).unwrap();
+
+        let mut rest = Vec::new();
+        while let Some(frame) = body.frame().await {
+            if let Ok(data) = frame.unwrap().into_data() {
+                rest.extend_from_slice(&data);
+            }
+        }
+        assert_eq!(rest, b"secondthird");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn plugin_body_limit_counts_the_whole_download() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (send_rest, rest) = oneshot::channel();
+        let server = tokio::spawn(serve_chunked(listener, rest));
+
+        // 每块都小于上限，累计超过上限时才失败
+        let response = fetch_local(port, 10).await;
+        let mut body = response.resp.into_body();
+        assert!(body.frame().await.unwrap().is_ok());
+        send_rest.send(()).unwrap();
+        assert!(matches!(
+            body.frame().await.unwrap(),
+            Err(ErrorCode::HttpResponseBodySize(Some(10)))
+        ));
+        drop(body);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn chunked_response_is_delivered_incrementally() {
+        let mut body = LimitedBody::new(chunked_body(&[b"first", b"second", b"third"]), 64);
+
+        for expected in [&b"first"[..], b"second", b"third"] {
+            let frame = body.frame().await.unwrap().unwrap();
+            assert_eq!(frame.into_data().unwrap(), Bytes::from_static(expected));
+        }
+        assert!(body.frame().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn body_limit_applies_to_total_bytes() {
+        let mut body = LimitedBody::new(chunked_body(&[b"1234", b"5678", b"9"]), 8);
+
+        assert!(body.frame().await.unwrap().is_ok());
+        assert!(body.frame().await.unwrap().is_ok());
+        assert!(matches!(
+            body.frame().await.unwrap(),
+            Err(ErrorCode::HttpResponseBodySize(Some(8)))
+        ));
+    }
+
+    #[test]
+    fn ip_rules_match_addresses_and_networks() {
+        let single = IpRule::parse("203.0.113.7").unwrap();
+        assert!(single.contains("203.0.113.7".parse().unwrap()));
+        assert!(!single.contains("203.0.113.8".parse().unwrap()));
+
+        let network = IpRule::parse("10.0.0.0/8").unwrap();
+        assert!(network.contains("10.20.30.40".parse().unwrap()));
+        assert!(network.contains("::ffff:10.1.1.1".parse().unwrap()));
+        assert!(!network.contains("192.168.0.1".parse().unwrap()));
+
+        let v6 = IpRule::parse("2001:db8::/32").unwrap();
+        assert!(v6.contains("2001:db8::1".parse().unwrap()));
+        assert!(!v6.contains("2001:db9::1".parse().unwrap()));
+
+        assert!(IpRule::parse("10.0.0.0/33").is_none());
+        assert!(IpRule::parse("example.com").is_none());
+    }
+
+    #[tokio::test]
+    async fn tls_request_connects_to_the_checked_address() {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // 不完成 TLS 握手，只确认连接到达了校验过的地址
+        let accepted = tokio::spawn(async move { listener.accept().await.is_ok() });
+        let lookups = AtomicUsize::new(0);
+        let allowed = [IpRule::parse("127.0.0.1").unwrap()];
+
+        let result = send_allowed_request(
+            "http-test",
+            &allowed,
+            tls_request(port),
+            tls_config(),
+            rebinding_resolver(&lookups),
+        )
+        .await;
+
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+        assert!(accepted.await.unwrap());
+        assert!(matches!(result, Err(ErrorCode::TlsProtocolError)));
+    }
+
+    #[tokio::test]
+    async fn disallowed_resolution_is_rejected_before_connecting() {
+        let lookups = AtomicUsize::new(1);
+        let allowed = [IpRule::parse("127.0.0.1").unwrap()];
+
+        let result = send_allowed_request(
+            "http-test",
+            &allowed,
+            tls_request(443),
+            tls_config(),
+            rebinding_resolver(&lookups),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ErrorCode::DestinationIpProhibited)));
+    }
+}
@@ -8,7 +8,7 @@ use log::error;
 use serde_json::json;
 use wasmtime::component::{Accessor, FutureReader};
 
-use super::{HostString, PluginCtx, permission::check_permission_declared};
+use super::{HostCallSpan, HostString, HostVec, PluginCtx, permission::check_permission_declared};
 
 impl psys_host::interconnect::Host for PluginCtx {}
 
@@ -19,15 +19,64 @@ impl psys_host::interconnect::HostWithStore for PluginCtx {
         pkg_name: HostString,
         data: HostString,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
-        let instance = accessor.instance();
-        let app_handle = accessor.with(|mut access| access.get().app_handle());
-        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
-        let permissions = accessor.with(|mut access| access.get().permissions());
-        let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
+        // 文本便捷封装，按 UTF-8 编码后走与二进制相同的发送路径
+        send_qaic(
+            accessor,
+            "interconnect.send_qaic_message",
+            device_addr,
+            pkg_name,
+            data.to_string().into_bytes(),
+        )
+    }
+
+    fn send_qaic_bytes<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+        pkg_name: HostString,
+        data: HostVec<u8>,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        send_qaic(
+            accessor,
+            "interconnect.send_qaic_bytes",
+            device_addr,
+            pkg_name,
+            qaic_payload_from_bytes(data),
+        )
+    }
+}
+
+// 二进制负载原样透传，不做任何 UTF-8 转换
+fn qaic_payload_from_bytes(data: HostVec<u8>) -> Vec<u8> {
+    data.into_iter().collect()
+}
+
+fn send_qaic<T>(
+    accessor: &Accessor<T, PluginCtx>,
+    operation: &'static str,
+    device_addr: HostString,
+    pkg_name: HostString,
+    payload: Vec<u8>,
+) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+    let span = HostCallSpan::new(accessor, operation);
+    let instance = accessor.instance();
+    let app_handle = accessor.with(|mut access| access.get().app_handle());
+    let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+    let permissions = accessor.with(|mut access| access.get().permissions());
+    let future = accessor.with(|mut access| {
+        FutureReader::new(
+            instance,
+            &mut access,
+            span.instrument(async move {
+                if crate::suspension::is_plugin_suspended(&plugin_name) {
+                    log::warn!(
+                        "[plugin:{}] {} rejected: plugin is suspended",
+                        plugin_name,
+                        operation
+                    );
+                    return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                }
                 let device_addr = device_addr.to_string();
                 let pkg_name = pkg_name.to_string();
-                let payload = data.to_string().into_bytes();
 
                 let params = json!({
                     "plugin": plugin_name,
@@ -52,10 +101,10 @@ impl psys_host::interconnect::HostWithStore for PluginCtx {
                         Ok::<core::result::Result<(), ()>, Error>(Err(()))
                     }
                 }
-            })
-        });
-        async move { future }
-    }
+            }),
+        )
+    });
+    async move { future }
 }
 
 async fn send_qaic_message_impl(
@@ -109,3 +158,17 @@ async fn dispatch_message(
     })
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_payload_is_passed_through_intact() {
+        let bytes = vec![0xff, 0xfe, 0x00, 0xc3, 0x28, 0x80, 0x41];
+        assert!(std::str::from_utf8(&bytes).is_err());
+
+        let payload = qaic_payload_from_bytes(HostVec::from(bytes.clone()));
+        assert_eq!(payload, bytes);
+    }
+}
@@ -0,0 +1,229 @@
+use serde_json::{Map, Number, Value};
+
+use crate::bindings::astrobox::psys_host;
+use psys_host::json::{JsonNode, JsonValue, ParseError};
+
+use super::PluginCtx;
+
+// WIT 不支持递归类型，JSON 树被展开为节点列表：下标 0 为根节点，
+// 数组/对象通过下标引用子节点，且子节点下标总是大于父节点
+fn push_node(nodes: &mut Vec<JsonNode>, value: &Value) -> u32 {
+    let index = nodes.len();
+    nodes.push(JsonNode::Null);
+    let node = match value {
+        Value::Null => JsonNode::Null,
+        Value::Bool(value) => JsonNode::Boolean(*value),
+        Value::Number(number) => {
+            if let Some(value) = number.as_i64() {
+                JsonNode::Int(value)
+            } else if let Some(value) = number.as_u64() {
+                JsonNode::Uint(value)
+            } else {
+                JsonNode::Float(number.as_f64().unwrap_or_default())
+            }
+        }
+        Value::String(value) => JsonNode::Str(value.clone()),
+        Value::Array(items) => {
+            JsonNode::Array(items.iter().map(|item| push_node(nodes, item)).collect())
+        }
+        Value::Object(entries) => JsonNode::Object(
+            entries
+                .iter()
+                .map(|(key, item)| (key.clone(), push_node(nodes, item)))
+                .collect(),
+        ),
+    };
+    nodes[index] = node;
+    index as u32
+}
+
+fn to_wit_value(value: &Value) -> JsonValue {
+    let mut nodes = Vec::new();
+    push_node(&mut nodes, value);
+    JsonValue { nodes }
+}
+
+// 与 serde_json 解析时的默认递归深度一致
+const JSON_MAX_DEPTH: usize = 128;
+// 单个 JSON 值最多包含的节点数
+const JSON_MAX_NODES: usize = 1 << 20;
+
+// 每个非根节点必须且只能被引用一次，保证节点列表是一棵树，转换开销与节点数成正比
+struct NodeReader<'a> {
+    nodes: &'a [JsonNode],
+    referenced: Vec<bool>,
+}
+
+impl NodeReader<'_> {
+    fn child_value(&mut self, parent: usize, child: u32, depth: usize) -> Result<Value, String> {
+        let child = child as usize;
+        if child <= parent || child >= self.nodes.len() {
+            return Err(format!("node {parent} has invalid child reference {child}"));
+        }
+        if std::mem::replace(&mut self.referenced[child], true) {
+            return Err(format!("node {child} is referenced more than once"));
+        }
+        self.node_value(child, depth + 1)
+    }
+
+    fn node_value(&mut self, index: usize, depth: usize) -> Result<Value, String> {
+        if depth > JSON_MAX_DEPTH {
+            return Err(format!("value nests deeper than {JSON_MAX_DEPTH} levels"));
+        }
+        let nodes = self.nodes;
+        let node = nodes
+            .get(index)
+            .ok_or_else(|| format!("node {index} does not exist"))?;
+        Ok(match node {
+            JsonNode::Null => Value::Null,
+            JsonNode::Boolean(value) => Value::Bool(*value),
+            JsonNode::Int(value) => Value::Number((*value).into()),
+            JsonNode::Uint(value) => Value::Number((*value).into()),
+            JsonNode::Float(value) => Number::from_f64(*value)
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            JsonNode::Str(value) => Value::String(value.clone()),
+            JsonNode::Array(children) => Value::Array(
+                children
+                    .iter()
+                    .map(|child| self.child_value(index, *child, depth))
+                    .collect::<Result<_, _>>()?,
+            ),
+            JsonNode::Object(entries) => Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, child)| Ok((key.clone(), self.child_value(index, *child, depth)?)))
+                    .collect::<Result<Map<_, _>, String>>()?,
+            ),
+        })
+    }
+}
+
+fn from_wit_value(value: &JsonValue) -> Result<Value, String> {
+    if value.nodes.is_empty() {
+        return Ok(Value::Null);
+    }
+    if value.nodes.len() > JSON_MAX_NODES {
+        return Err(format!("value has more than {JSON_MAX_NODES} nodes"));
+    }
+    let mut reader = NodeReader {
+        nodes: &value.nodes,
+        referenced: vec![false; value.nodes.len()],
+    };
+    reader.referenced[0] = true;
+    let result = reader.node_value(0, 0)?;
+    if let Some(orphan) = reader.referenced.iter().position(|referenced| !referenced) {
+        return Err(format!("node {orphan} is not referenced"));
+    }
+    Ok(result)
+}
+
+fn parse_json(text: &str) -> Result<JsonValue, ParseError> {
+    serde_json::from_str::<Value>(text)
+        .map(|value| to_wit_value(&value))
+        .map_err(|err| ParseError {
+            message: err.to_string(),
+            line: err.line() as u32,
+            column: err.column() as u32,
+        })
+}
+
+impl psys_host::json::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "json.parse")
+        )
+    )]
+    fn parse(&mut self, text: String) -> wasmtime::Result<Result<JsonValue, ParseError>> {
+        Ok(parse_json(&text))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "json.stringify")
+        )
+    )]
+    fn stringify(&mut self, value: JsonValue) -> wasmtime::Result<String> {
+        // 接口返回值只有字符串，格式错误的节点列表以错误返回给插件，而不是静默序列化为 null
+        let value = from_wit_value(&value).map_err(|err| {
+            log::warn!(
+                "[plugin:{}] json.stringify received malformed value: {err}",
+                self.plugin_name()
+            );
+            wasmtime::Error::msg(format!("json.stringify received malformed value: {err}"))
+        })?;
+        Ok(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{JsonNode, JsonValue, from_wit_value, parse_json};
+
+    #[test]
+    fn parse_round_trips_nested_values() {
+        let text = r#"{"name":"demo","tags":["a",1,-2,18446744073709551615,1.5],"nested":{"ok":true,"none":null}}"#;
+        let value = parse_json(text).unwrap();
+        assert!(matches!(value.nodes[0], JsonNode::Object(_)));
+
+        let expected: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(from_wit_value(&value).unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_error_reports_line_and_column() {
+        let err = parse_json("{\n  \"a\": 1,\n  \"b\": }").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.column, 8);
+    }
+
+    #[test]
+    fn rejects_cyclic_references() {
+        let value = JsonValue {
+            nodes: vec![JsonNode::Array(vec![0])],
+        };
+        assert!(from_wit_value(&value).is_err());
+        assert_eq!(
+            from_wit_value(&JsonValue { nodes: Vec::new() }).unwrap(),
+            json!(null)
+        );
+    }
+
+    #[test]
+    fn rejects_shared_and_orphaned_nodes() {
+        // 兄弟节点引用同一个子节点会让展开结果指数级增长
+        let shared = JsonValue {
+            nodes: vec![JsonNode::Array(vec![1, 1]), JsonNode::Null],
+        };
+        assert!(from_wit_value(&shared).is_err());
+
+        let orphaned = JsonValue {
+            nodes: vec![JsonNode::Array(vec![1]), JsonNode::Null, JsonNode::Null],
+        };
+        assert!(from_wit_value(&orphaned).is_err());
+    }
+
+    #[test]
+    fn rejects_values_nested_too_deeply() {
+        let depth = super::JSON_MAX_DEPTH + 1;
+        let mut nodes = (1..=depth as u32)
+            .map(|child| JsonNode::Array(vec![child]))
+            .collect::<Vec<_>>();
+        nodes.push(JsonNode::Null);
+        assert!(from_wit_value(&JsonValue { nodes }).is_err());
+
+        let mut nodes = (1..depth as u32)
+            .map(|child| JsonNode::Array(vec![child]))
+            .collect::<Vec<_>>();
+        nodes.push(JsonNode::Null);
+        assert!(from_wit_value(&JsonValue { nodes }).is_ok());
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use log::{Level, LevelFilter};
+use once_cell::sync::Lazy;
+
+use crate::bindings::astrobox::psys_host;
+
+use super::{HostString, PluginCtx};
+
+// 插件根目录下保存各插件日志级别的文件，插件更新时不会被清除
+const LOG_LEVELS_FILE: &str = ".log-levels.json";
+const PLUGIN_LOG_TARGET: &str = "pluginsystem::plugin::log";
+// 每个插件保留的最近日志行数，用于导出诊断包
+const RECENT_LOG_LIMIT: usize = 200;
+
+// 插件名 -> 插件设置的最低日志级别；未设置时使用宿主全局级别
+static PLUGIN_LOG_LEVELS: Lazy<Mutex<HashMap<String, LevelFilter>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static RECENT_PLUGIN_LOGS: Lazy<Mutex<HashMap<String, VecDeque<String>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn log_levels_path(plugins_root: &Path) -> PathBuf {
+    plugins_root.join(LOG_LEVELS_FILE)
+}
+
+fn read_log_levels(path: &Path) -> BTreeMap<String, String> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// `level` 为空时删除该插件的记录
+fn store_log_level(
+    plugins_root: &Path,
+    plugin_name: &str,
+    level: Option<LevelFilter>,
+) -> Result<()> {
+    let path = log_levels_path(plugins_root);
+    let mut levels = read_log_levels(&path);
+    let changed = match level {
+        Some(level) => {
+            levels.insert(plugin_name.to_string(), level.to_string().to_lowercase());
+            true
+        }
+        None => levels.remove(plugin_name).is_some(),
+    };
+    if !changed {
+        return Ok(());
+    }
+    let content = serde_json::to_string_pretty(&levels)?;
+    fs::write(&path, content)
+        .with_context(|| format!("failed to write log levels {}", path.display()))
+}
+
+/// 插件启动时恢复其持久化的日志级别
+pub(crate) fn restore_plugin_log_level(plugins_root: &Path, plugin_name: &str) {
+    let level = read_log_levels(&log_levels_path(plugins_root))
+        .get(plugin_name)
+        .and_then(|level| LevelFilter::from_str(level).ok());
+    let mut levels = PLUGIN_LOG_LEVELS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    match level {
+        Some(level) => {
+            levels.insert(plugin_name.to_string(), level);
+        }
+        None => {
+            levels.remove(plugin_name);
+        }
+    }
+}
+
+/// 设置并持久化插件的日志级别，`None` 恢复为宿主全局级别。
+/// 运维设置不受限制，插件自己设置的级别由调用方先限制在全局级别以内
+pub(crate) fn set_plugin_log_level(
+    plugins_root: &Path,
+    plugin_name: &str,
+    level: Option<LevelFilter>,
+) -> Result<()> {
+    {
+        let mut levels = PLUGIN_LOG_LEVELS
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        match level {
+            Some(level) => {
+                levels.insert(plugin_name.to_string(), level);
+            }
+            None => {
+                levels.remove(plugin_name);
+            }
+        }
+    }
+    store_log_level(plugins_root, plugin_name, level)
+}
+
+/// 插件被移除时丢弃其日志级别和最近日志
+pub(crate) fn forget_plugin_log_level(plugins_root: &Path, plugin_name: &str) {
+    if let Err(err) = set_plugin_log_level(plugins_root, plugin_name, None) {
+        log::warn!("[plugin:{}] failed to drop log level: {err}", plugin_name);
+    }
+    RECENT_PLUGIN_LOGS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(plugin_name);
+}
+
+// 插件不能把自己的级别调到比宿主全局级别更详细
+fn clamp_guest_level(level: LevelFilter) -> LevelFilter {
+    level.min(log::max_level())
+}
+
+fn plugin_log_level(plugin_name: &str) -> LevelFilter {
+    PLUGIN_LOG_LEVELS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .get(plugin_name)
+        .copied()
+        .unwrap_or_else(log::max_level)
+}
+
+/// 按插件的日志级别输出插件日志（包括捕获的 stdout / stderr）。
+/// 直接交给 logger，插件级别高于宿主全局级别时也能输出
+pub(crate) fn emit_plugin_log(plugin_name: &str, level: Level, target: &str, message: &str) {
+    if level > plugin_log_level(plugin_name) {
+        return;
+    }
+    remember_plugin_log(plugin_name, level, message);
+    log::logger().log(
+        &log::Record::builder()
+            .level(level)
+            .target(target)
+            .args(format_args!("[plugin:{}] {}", plugin_name, message))
+            .build(),
+    );
+}
+
+fn remember_plugin_log(plugin_name: &str, level: Level, message: &str) {
+    let mut logs = RECENT_PLUGIN_LOGS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let lines = logs.entry(plugin_name.to_string()).or_default();
+    if lines.len() >= RECENT_LOG_LIMIT {
+        lines.pop_front();
+    }
+    lines.push_back(format!(
+        "{} {:<5} {}",
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        level,
+        message
+    ));
+}
+
+/// 插件最近输出的日志，按时间先后排列
+pub(crate) fn recent_plugin_logs(plugin_name: &str) -> Vec<String> {
+    RECENT_PLUGIN_LOGS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .get(plugin_name)
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn from_host_level(level: psys_host::log::Level) -> Level {
+    match level {
+        psys_host::log::Level::Trace => Level::Trace,
+        psys_host::log::Level::Debug => Level::Debug,
+        psys_host::log::Level::Info => Level::Info,
+        psys_host::log::Level::Warn => Level::Warn,
+        psys_host::log::Level::Error => Level::Error,
+    }
+}
+
+// `off` 没有对应的插件级别，按最高的 error 返回
+fn to_host_level(level: LevelFilter) -> psys_host::log::Level {
+    match level.to_level() {
+        Some(Level::Trace) => psys_host::log::Level::Trace,
+        Some(Level::Debug) => psys_host::log::Level::Debug,
+        Some(Level::Info) => psys_host::log::Level::Info,
+        Some(Level::Warn) => psys_host::log::Level::Warn,
+        Some(Level::Error) | None => psys_host::log::Level::Error,
+    }
+}
+
+impl psys_host::log::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "log.emit")
+        )
+    )]
+    fn emit(&mut self, level: psys_host::log::Level, message: HostString) -> wasmtime::Result<()> {
+        emit_plugin_log(
+            self.plugin_name(),
+            from_host_level(level),
+            PLUGIN_LOG_TARGET,
+            &message,
+        );
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "log.set_level")
+        )
+    )]
+    fn set_level(&mut self, level: psys_host::log::Level) -> wasmtime::Result<()> {
+        let level = clamp_guest_level(from_host_level(level).to_level_filter());
+        let plugins_root = self
+            .plugin_root()
+            .parent()
+            .unwrap_or(self.plugin_root().as_path());
+        if let Err(err) = set_plugin_log_level(plugins_root, self.plugin_name(), Some(level)) {
+            log::warn!(
+                "[plugin:{}] failed to persist log level: {err}",
+                self.plugin_name()
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "log.level")
+        )
+    )]
+    fn level(&mut self) -> wasmtime::Result<psys_host::log::Level> {
+        Ok(to_host_level(plugin_log_level(self.plugin_name())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::LevelFilter;
+
+    use super::{
+        clamp_guest_level, forget_plugin_log_level, log_levels_path, plugin_log_level,
+        read_log_levels, restore_plugin_log_level, set_plugin_log_level, store_log_level,
+    };
+    use crate::test_support::temp_root;
+
+    #[test]
+    fn persisted_level_is_restored_per_plugin() {
+        let temp = temp_root();
+        let root = temp.path();
+
+        store_log_level(root, "verbose", Some(LevelFilter::Trace)).unwrap();
+        restore_plugin_log_level(root, "verbose");
+        restore_plugin_log_level(root, "quiet");
+        assert_eq!(plugin_log_level("verbose"), LevelFilter::Trace);
+        assert_eq!(plugin_log_level("quiet"), log::max_level());
+
+        // 卸载后级别和文件记录一并删除
+        forget_plugin_log_level(root, "verbose");
+        assert_eq!(plugin_log_level("verbose"), log::max_level());
+        assert!(!read_log_levels(&log_levels_path(root)).contains_key("verbose"));
+    }
+
+    #[test]
+    fn guest_levels_are_clamped_but_operator_levels_are_not() {
+        let temp = temp_root();
+        let root = temp.path();
+
+        assert!(clamp_guest_level(LevelFilter::Trace) <= log::max_level());
+        assert_eq!(clamp_guest_level(LevelFilter::Off), LevelFilter::Off);
+        set_plugin_log_level(root, "operated", Some(LevelFilter::Trace)).unwrap();
+        assert_eq!(plugin_log_level("operated"), LevelFilter::Trace);
+    }
+}
@@ -2,15 +2,79 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use tauri::AppHandle;
-use wasmtime::component::ResourceTable;
+use wasmtime::component::{Accessor, ResourceTable};
 use wasmtime_wasi::{WasiCtx, WasiCtxView, WasiView};
-use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
+use wasmtime_wasi_http::body::HyperOutgoingBody;
+use wasmtime_wasi_http::types::{HostFutureIncomingResponse, OutgoingRequestConfig};
+use wasmtime_wasi_http::{HttpResult, WasiHttpCtx, WasiHttpView};
 
+use crate::limits::{PluginLimiter, PluginResourceLimits};
 use crate::plugin::PluginRegisterState;
 
 pub(crate) type HostVec<T> = wasmtime::component::__internal::Vec<T>;
 pub(crate) type HostString = wasmtime::component::__internal::String;
 
+// 异步宿主调用的 future 由 `FutureReader` 持有，插件丢弃读端时随之被丢弃，不会在后台继续运行。
+// 只有登记在 future 之外的状态需要在丢弃时清理：
+// - transport.request / transport.ping 的响应等待者：`RequestWaiter` 丢弃时注销
+// - provider.resolve 的挂起请求：`PendingResolve` 丢弃时取消
+// - dialog.* 已弹出的对话框：`until_cancelled` 丢弃时通知前端关闭
+// 其余调用只持有 future 内的资源（http 连接、sync.acquire 的等待等），丢弃即释放；
+// secrets、storage 的 spawn_blocking 是短时操作，会执行完但结果被丢弃。
+// timer、self.report_fatal、ui 节流补发中 spawn 的任务有意独立于单次调用，随插件停止清理
+
+/// 宿主调用的 tracing span，创建时记录插件活动，调用完成后把耗时计入 `latency` 直方图
+pub(crate) struct HostCallSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    operation: &'static str,
+}
+
+impl HostCallSpan {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new<T>(accessor: &Accessor<T, PluginCtx>, operation: &'static str) -> Self {
+        let plugin = accessor.with(|mut access| {
+            let ctx = access.get();
+            ctx.register_state().touch_activity();
+            ctx.plugin_name().to_string()
+        });
+        Self {
+            span: tracing::debug_span!("host_call", plugin = %plugin, operation),
+            operation,
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline(always)]
+    pub(crate) fn new<T>(accessor: &Accessor<T, PluginCtx>, operation: &'static str) -> Self {
+        // 宿主调用也算作插件活动，避免正在工作的插件被空闲挂起
+        accessor.with(|mut access| access.get().register_state().touch_activity());
+        Self { operation }
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn instrument<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        let operation = self.operation;
+        tracing::Instrument::instrument(
+            async move {
+                let _timer = crate::latency::LatencyTimer::start(operation);
+                future.await
+            },
+            self.span,
+        )
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline(always)]
+    pub(crate) fn instrument<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        let operation = self.operation;
+        async move {
+            let _timer = crate::latency::LatencyTimer::start(operation);
+            future.await
+        }
+    }
+}
+
 pub struct PluginCtx {
     table: ResourceTable,
     wasi_ctx: WasiCtx,
@@ -20,6 +84,10 @@ pub struct PluginCtx {
     register_state: Arc<PluginRegisterState>,
     plugin_name: String,
     permissions: Arc<Vec<String>>,
+    allowed_ips: Arc<Vec<http::IpRule>>,
+    additional_files: Arc<Vec<String>>,
+    http_body_limit: u64,
+    limiter: PluginLimiter,
 }
 
 impl PluginCtx {
@@ -30,6 +98,9 @@ impl PluginCtx {
         plugin_name: String,
         register_state: Arc<PluginRegisterState>,
         permissions: Arc<Vec<String>>,
+        allowed_ips: Arc<Vec<http::IpRule>>,
+        additional_files: Arc<Vec<String>>,
+        limits: PluginResourceLimits,
     ) -> Self {
         Self {
             table: ResourceTable::new(),
@@ -40,6 +111,10 @@ impl PluginCtx {
             register_state,
             plugin_name,
             permissions,
+            allowed_ips,
+            additional_files,
+            http_body_limit: limits.http_body_bytes,
+            limiter: PluginLimiter::new(limits),
         }
     }
 
@@ -62,6 +137,14 @@ impl PluginCtx {
     pub(crate) fn permissions(&self) -> Arc<Vec<String>> {
         Arc::clone(&self.permissions)
     }
+
+    pub(crate) fn limiter(&self) -> &PluginLimiter {
+        &self.limiter
+    }
+
+    pub(crate) fn limiter_mut(&mut self) -> &mut PluginLimiter {
+        &mut self.limiter
+    }
 }
 
 impl WasiView for PluginCtx {
@@ -81,23 +164,53 @@ impl WasiHttpView for PluginCtx {
     fn ctx(&mut self) -> &mut WasiHttpCtx {
         &mut self.http_ctx
     }
+
+    fn send_request(
+        &mut self,
+        request: hyper::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> HttpResult<HostFutureIncomingResponse> {
+        http::send_plugin_request(
+            self.plugin_name.clone(),
+            Arc::clone(&self.allowed_ips),
+            self.http_body_limit,
+            request,
+            config,
+        )
+    }
 }
 
 impl wasmtime::component::HasData for PluginCtx {
     type Data<'a> = &'a mut PluginCtx;
 }
 
+mod analytics;
+pub(crate) mod assets;
 mod clipboard;
+mod command;
+mod crypto;
 mod device;
-mod dialog;
-mod event;
+pub(crate) mod dialog;
+pub(crate) mod event;
+pub(crate) mod flags;
+pub(crate) mod fs_quota;
+pub(crate) mod http;
 mod i18n;
 mod interconnect;
+mod json;
+pub(crate) mod logging;
 mod os;
-mod permission;
+pub(crate) mod permission;
+mod plugins;
+mod provider;
 mod provider_callback;
 mod queue;
 mod register;
+mod secrets;
+pub(crate) mod self_;
+pub(crate) mod sockets;
+pub(crate) mod storage;
+pub(crate) mod sync;
 mod thirdpartyapp;
 mod timer;
 mod transport;
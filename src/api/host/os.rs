@@ -1,27 +1,135 @@
 use crate::bindings::astrobox::psys_host;
+use crate::network::ConnectionType;
+use crate::power::PowerSource;
 use anyhow::{Context, Error};
 use chrono::Local;
 use frontbridge::invoke_frontend;
+use psys_host::os::{ActivityError, ActivityInfo};
+use serde::Deserialize;
+use serde_json::json;
 use wasmtime::component::{Accessor, FutureReader};
 
-use super::{HostString, PluginCtx};
+use super::{HostCallSpan, HostString, PluginCtx, permission::check_permission_declared};
 
 const FRONT_LANGUAGE_METHOD: &str = "host/os/astrobox_language";
 const FRONT_APPEARANCE_METHOD: &str = "host/os/appearance";
+// 由前端通过平台健康插件（HealthKit / Google Fit）读取手机自身的活动数据
+const FRONT_ACTIVITY_METHOD: &str = "host/os/activity_snapshot";
+// 读取手机活动数据的敏感权限，需在 manifest 中声明并经用户授权
+const ACTIVITY_PERMISSION: &str = "os.activity";
 
-impl psys_host::os::Host for PluginCtx {}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum ActivityStatus {
+    Ok,
+    // 平台没有健康数据接口或未安装对应服务
+    Unsupported,
+    // 用户在系统授权弹窗中拒绝
+    Denied,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActivityResponse {
+    status: ActivityStatus,
+    #[serde(default)]
+    timestamp_ms: Option<i64>,
+    #[serde(default)]
+    steps: Option<u32>,
+    #[serde(default)]
+    distance_meters: Option<u32>,
+    #[serde(default)]
+    active_calories: Option<u32>,
+}
+
+impl ActivityResponse {
+    fn into_result(self) -> Result<Option<ActivityInfo>, ActivityError> {
+        match self.status {
+            ActivityStatus::Denied => Err(ActivityError::OsPermissionDenied),
+            ActivityStatus::Unsupported => Ok(None),
+            ActivityStatus::Ok => Ok(self.steps.map(|steps| ActivityInfo {
+                timestamp_ms: self
+                    .timestamp_ms
+                    .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+                steps,
+                distance_meters: self.distance_meters,
+                active_calories: self.active_calories,
+            })),
+        }
+    }
+}
+
+impl psys_host::os::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "os.network_status")
+        )
+    )]
+    fn network_status(&mut self) -> wasmtime::Result<psys_host::os::NetworkInfo> {
+        let status = crate::network::current_network();
+        Ok(psys_host::os::NetworkInfo {
+            connected: status.connected,
+            connection_type: match status.connection_type {
+                ConnectionType::Wifi => psys_host::os::ConnectionType::Wifi,
+                ConnectionType::Cellular => psys_host::os::ConnectionType::Cellular,
+                ConnectionType::Ethernet => psys_host::os::ConnectionType::Ethernet,
+                ConnectionType::None => psys_host::os::ConnectionType::None,
+                ConnectionType::Unknown => psys_host::os::ConnectionType::Unknown,
+            },
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "os.power_state")
+        )
+    )]
+    fn power_state(&mut self) -> wasmtime::Result<psys_host::os::PowerInfo> {
+        let state = crate::power::current_power();
+        Ok(psys_host::os::PowerInfo {
+            source: match state.source {
+                PowerSource::Charging => psys_host::os::PowerSource::Charging,
+                PowerSource::Battery => psys_host::os::PowerSource::Battery,
+                PowerSource::Unknown => psys_host::os::PowerSource::Unknown,
+            },
+            power_saver: state.power_saver,
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "os.runtime_mode")
+        )
+    )]
+    fn runtime_mode(&mut self) -> wasmtime::Result<psys_host::os::RuntimeMode> {
+        Ok(if crate::plugin::ENGINE_USES_INTERPRETER {
+            psys_host::os::RuntimeMode::Interpreter
+        } else {
+            psys_host::os::RuntimeMode::Jit
+        })
+    }
+}
 
 impl psys_host::os::HostWithStore for PluginCtx {
     fn arch<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostString>> + Send {
-        make_string_future(accessor, || std::env::consts::ARCH.to_string())
+        make_string_future(accessor, "os.arch", || std::env::consts::ARCH.to_string())
     }
 
     fn hostname<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostString>> + Send {
-        make_string_future(accessor, || {
+        make_string_future(accessor, "os.hostname", || {
             whoami::fallible::hostname().unwrap_or_else(|_| "unknown-host".to_string())
         })
     }
@@ -29,7 +137,7 @@ impl psys_host::os::HostWithStore for PluginCtx {
     fn locale<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostString>> + Send {
-        make_string_future(accessor, || {
+        make_string_future(accessor, "os.locale", || {
             sys_locale::get_locale().unwrap_or_else(|| "en-US".to_string())
         })
     }
@@ -37,28 +145,37 @@ impl psys_host::os::HostWithStore for PluginCtx {
     fn platform<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostString>> + Send {
-        make_string_future(accessor, || os_info::get().os_type().to_string())
+        make_string_future(accessor, "os.platform", || {
+            os_info::get().os_type().to_string()
+        })
     }
 
     fn version<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostString>> + Send {
-        make_string_future(accessor, || os_info::get().version().to_string())
+        make_string_future(accessor, "os.version", || {
+            os_info::get().version().to_string()
+        })
     }
 
     fn astrobox_language<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostString>> + Send {
+        let span = HostCallSpan::new(accessor, "os.astrobox_language");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let future = accessor.with(|mut access| {
             let app_handle = app_handle.clone();
-            FutureReader::new(instance, &mut access, async move {
-                let language: String = invoke_frontend(&app_handle, FRONT_LANGUAGE_METHOD, ())
-                    .await
-                    .context("invoke frontend astrobox_language")?;
-                Ok::<HostString, Error>(language.into())
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let language: String = invoke_frontend(&app_handle, FRONT_LANGUAGE_METHOD, ())
+                        .await
+                        .context("invoke frontend astrobox_language")?;
+                    Ok::<HostString, Error>(language.into())
+                }),
+            )
         });
         async move { future }
     }
@@ -66,16 +183,78 @@ impl psys_host::os::HostWithStore for PluginCtx {
     fn appearance<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<HostString>> + Send {
+        let span = HostCallSpan::new(accessor, "os.appearance");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let future = accessor.with(|mut access| {
             let app_handle = app_handle.clone();
-            FutureReader::new(instance, &mut access, async move {
-                let appearance: String = invoke_frontend(&app_handle, FRONT_APPEARANCE_METHOD, ())
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let appearance: String =
+                        invoke_frontend(&app_handle, FRONT_APPEARANCE_METHOD, ())
+                            .await
+                            .context("invoke frontend appearance")?;
+                    Ok::<HostString, Error>(appearance.into())
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn activity_snapshot<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<
+        Output = FutureReader<core::result::Result<Option<ActivityInfo>, ActivityError>>,
+    > + Send {
+        let span = HostCallSpan::new(accessor, "os.activity_snapshot");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "action": "activity",
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        ACTIVITY_PERMISSION,
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(ActivityError::PermissionDenied));
+                    }
+
+                    let result = match invoke_frontend::<ActivityResponse, _>(
+                        &app_handle,
+                        FRONT_ACTIVITY_METHOD,
+                        (),
+                    )
                     .await
-                    .context("invoke frontend appearance")?;
-                Ok::<HostString, Error>(appearance.into())
-            })
+                    {
+                        Ok(response) => response.into_result(),
+                        Err(err) => {
+                            log::warn!("[plugin:{}] activity_snapshot failed: {err}", plugin_name);
+                            Ok(None)
+                        }
+                    };
+                    if matches!(result, Err(ActivityError::OsPermissionDenied)) {
+                        log::info!(
+                            "[plugin:{}] activity_snapshot: health access denied by the OS",
+                            plugin_name
+                        );
+                    }
+                    Ok::<core::result::Result<Option<ActivityInfo>, ActivityError>, Error>(result)
+                }),
+            )
         });
         async move { future }
     }
@@ -83,12 +262,17 @@ impl psys_host::os::HostWithStore for PluginCtx {
     fn timezone_offset_minutes<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<i32>> + Send {
+        let span = HostCallSpan::new(accessor, "os.timezone_offset_minutes");
         let instance = accessor.instance();
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let offset_seconds = Local::now().offset().local_minus_utc();
-                Ok::<i32, Error>(offset_seconds / 60)
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let offset_seconds = Local::now().offset().local_minus_utc();
+                    Ok::<i32, Error>(offset_seconds / 60)
+                }),
+            )
         });
         async move { future }
     }
@@ -96,16 +280,50 @@ impl psys_host::os::HostWithStore for PluginCtx {
 
 fn make_string_future<T, F>(
     accessor: &Accessor<T, PluginCtx>,
+    operation: &'static str,
     producer: F,
 ) -> impl core::future::Future<Output = FutureReader<HostString>> + Send
 where
     F: FnOnce() -> String + Send + 'static,
 {
+    let span = HostCallSpan::new(accessor, operation);
     let instance = accessor.instance();
     let future = accessor.with(|mut access| {
-        FutureReader::new(instance, &mut access, async move {
-            Ok::<HostString, Error>(producer().into())
-        })
+        FutureReader::new(
+            instance,
+            &mut access,
+            span.instrument(async move { Ok::<HostString, Error>(producer().into()) }),
+        )
     });
     async move { future }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ActivityError, ActivityResponse};
+
+    fn parse(json: &str) -> ActivityResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn activity_response_maps_os_denial_to_error() {
+        let snapshot = parse(r#"{"status":"ok","timestampMs":1000,"steps":4200}"#)
+            .into_result()
+            .unwrap()
+            .unwrap();
+        assert_eq!(snapshot.steps, 4200);
+        assert_eq!(snapshot.distance_meters, None);
+
+        assert!(
+            parse(r#"{"status":"unsupported"}"#)
+                .into_result()
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            parse(r#"{"status":"denied"}"#).into_result(),
+            Err(ActivityError::OsPermissionDenied)
+        ));
+    }
+}
@@ -1,11 +1,20 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
 use anyhow::Error;
 use corelib::device::xiaomi::{XiaomiDevice, components::resource::ResourceComponent};
 use frontbridge::invoke_frontend;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
 use tauri::AppHandle;
 
 const FRONT_PERMISSION_METHOD: &str = "host/register/request_permission";
+const FRONT_PERMISSION_RESET_METHOD: &str = "host/register/reset_permission";
+// 插件根目录下记录各插件实际请求过的权限，跨会话累积
+const PERMISSION_USAGE_FILE: &str = ".permission-usage.json";
 
 #[derive(Serialize)]
 struct PermissionRequestPayload {
@@ -16,27 +25,270 @@ struct PermissionRequestPayload {
 #[derive(Deserialize)]
 struct PermissionResponsePayload {
     granted: bool,
+    // 用户在设备级授权弹窗中选择了“所有设备”
+    #[serde(default, rename = "allDevices")]
+    all_devices: bool,
+}
+
+/// 设备级授权的生效范围；与设备无关的操作不缓存授权，每次都交给前端确认
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum GrantScope {
+    // 用户在请求某个设备时明确选择了“所有设备”
+    AllDevices,
+    Device(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PermissionGrantKey {
+    plugin: String,
+    operation: String,
+    scope: GrantScope,
+}
+
+static PERMISSION_GRANTS: Lazy<Mutex<HashSet<PermissionGrantKey>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 插件名 -> 实际请求过的权限，`path` 未设置时只记录在内存中
+#[derive(Default)]
+struct PermissionUsage {
+    path: Option<PathBuf>,
+    observed: BTreeMap<String, BTreeSet<String>>,
+}
+
+static PERMISSION_USAGE: Lazy<Mutex<PermissionUsage>> =
+    Lazy::new(|| Mutex::new(PermissionUsage::default()));
+
+#[derive(Serialize)]
+struct PermissionResetPayload {
+    plugin: String,
+    operations: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PermissionResetAck {
+    success: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PermissionDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl PermissionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
 }
 
 async fn request_permission(
     app_handle: &AppHandle,
     operation: impl Into<String>,
     params: Value,
-) -> Result<bool, Error> {
+) -> Result<PermissionResponsePayload, Error> {
     let operation = operation.into();
     let payload = PermissionRequestPayload {
         operation: operation.clone(),
         params,
     };
-    let resp: PermissionResponsePayload =
-        invoke_frontend(app_handle, FRONT_PERMISSION_METHOD, payload).await?;
-    Ok(resp.granted)
+    invoke_frontend(app_handle, FRONT_PERMISSION_METHOD, payload).await
 }
 
 fn normalize_permission_name(name: &str) -> String {
     name.trim().to_ascii_lowercase()
 }
 
+fn normalize_device_addr(addr: &str) -> Option<String> {
+    let addr = addr.trim();
+    if addr.is_empty() {
+        None
+    } else {
+        Some(addr.to_ascii_uppercase())
+    }
+}
+
+fn extract_device_addr(params: &Value) -> Option<String> {
+    params
+        .get("addr")
+        .and_then(|value| value.as_str())
+        .and_then(normalize_device_addr)
+}
+
+fn grant_matches(
+    grants: &HashSet<PermissionGrantKey>,
+    plugin: &str,
+    operation: &str,
+    addr: Option<&str>,
+) -> bool {
+    let Some(addr) = addr else {
+        return false;
+    };
+    grants.iter().any(|grant| {
+        grant.plugin == plugin
+            && grant.operation == operation
+            && match &grant.scope {
+                GrantScope::AllDevices => true,
+                GrantScope::Device(granted) => granted == addr,
+            }
+    })
+}
+
+fn is_permission_granted_cached(plugin: &str, operation: &str, addr: Option<&str>) -> bool {
+    let guard = PERMISSION_GRANTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    grant_matches(&guard, plugin, operation, addr)
+}
+
+/// 插件已就该设备获得授权的操作，只查已保存的授权，不会向用户请求
+pub(crate) fn cached_permission_grants<'a>(
+    plugin: &str,
+    addr: &str,
+    operations: &[&'a str],
+) -> Vec<&'a str> {
+    let addr = normalize_device_addr(addr);
+    let guard = PERMISSION_GRANTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    operations
+        .iter()
+        .copied()
+        .filter(|operation| {
+            grant_matches(
+                &guard,
+                plugin,
+                &normalize_permission_name(operation),
+                addr.as_deref(),
+            )
+        })
+        .collect()
+}
+
+fn record_permission_grant(plugin: &str, operation: &str, scope: GrantScope) {
+    let mut guard = PERMISSION_GRANTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    guard.insert(PermissionGrantKey {
+        plugin: plugin.to_string(),
+        operation: operation.to_string(),
+        scope,
+    });
+}
+
+/// 插件被禁用或移除时丢弃其全部授权缓存，重新启用后需要重新授权
+pub(crate) fn forget_all_permission_grants(plugin: &str) {
+    let mut guard = PERMISSION_GRANTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    guard.retain(|grant| grant.plugin != plugin);
+}
+
+pub(crate) fn forget_permission_grants(plugin: &str, operations: &[String]) {
+    let operations = operations
+        .iter()
+        .map(|operation| normalize_permission_name(operation))
+        .collect::<Vec<_>>();
+    let mut guard = PERMISSION_GRANTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    guard.retain(|grant| grant.plugin != plugin || !operations.contains(&grant.operation));
+}
+
+impl PermissionUsage {
+    // 在锁内只做快照，文件写入交给 persist_permission_usage 在锁外完成
+    fn snapshot(&self) -> Option<(PathBuf, BTreeMap<String, BTreeSet<String>>)> {
+        let path = self.path.clone()?;
+        Some((path, self.observed.clone()))
+    }
+}
+
+// 串行化文件写入；先拿到写锁再取快照，保证最后写入的总是最新的记录
+static PERMISSION_USAGE_WRITE: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn persist_permission_usage() {
+    let _write = PERMISSION_USAGE_WRITE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let Some((path, observed)) = PERMISSION_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .snapshot()
+    else {
+        return;
+    };
+    let result = serde_json::to_string_pretty(&observed)
+        .map_err(Error::from)
+        .and_then(|content| fs::write(&path, content).map_err(Error::from));
+    if let Err(err) = result {
+        log::warn!(
+            "[pluginsystem] failed to write permission usage {}: {err}",
+            path.display()
+        );
+    }
+}
+
+/// 加载之前会话记录的权限使用情况，之后的记录写回同一文件
+pub(crate) fn load_permission_usage(plugins_root: &Path) {
+    let path = plugins_root.join(PERMISSION_USAGE_FILE);
+    let observed = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    *PERMISSION_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner()) = PermissionUsage {
+        path: Some(path),
+        observed,
+    };
+}
+
+/// 记录插件实际用到了某个权限，任何按权限放行的调用点都应调用；只在首次观察到时写文件
+pub(crate) fn record_permission_use(plugin: &str, operation: &str) {
+    let inserted = PERMISSION_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .observed
+        .entry(plugin.to_string())
+        .or_default()
+        .insert(normalize_permission_name(operation));
+    if inserted {
+        persist_permission_usage();
+    }
+}
+
+/// 已声明但从未请求过的权限，按声明顺序返回
+pub(crate) fn unused_permissions(plugin: &str, declared: &[String]) -> Vec<String> {
+    let usage = PERMISSION_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let observed = usage.observed.get(plugin);
+    let mut unused: Vec<String> = Vec::new();
+    for permission in declared {
+        let permission = normalize_permission_name(permission);
+        if permission.is_empty()
+            || unused.contains(&permission)
+            || observed.is_some_and(|observed| observed.contains(&permission))
+        {
+            continue;
+        }
+        unused.push(permission);
+    }
+    unused
+}
+
+/// 插件被移除时丢弃其记录
+pub(crate) fn forget_permission_usage(plugin: &str) {
+    let removed = PERMISSION_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .observed
+        .remove(plugin)
+        .is_some();
+    if removed {
+        persist_permission_usage();
+    }
+}
+
 fn extract_plugin_name(params: &Value) -> Option<String> {
     params
         .get("plugin")
@@ -56,32 +308,111 @@ fn extract_plugin_name(params: &Value) -> Option<String> {
         })
 }
 
-pub(crate) fn is_permission_declared(permissions: &[String], required: &str) -> bool {
-    let required = normalize_permission_name(required);
-    if required.is_empty() {
-        return false;
+pub(crate) fn diff_permissions(previous: &[String], next: &[String]) -> PermissionDiff {
+    let previous = previous
+        .iter()
+        .map(|perm| normalize_permission_name(perm))
+        .filter(|perm| !perm.is_empty())
+        .collect::<Vec<_>>();
+    let next = next
+        .iter()
+        .map(|perm| normalize_permission_name(perm))
+        .filter(|perm| !perm.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut diff = PermissionDiff::default();
+    for perm in &next {
+        if !previous.contains(perm) && !diff.added.contains(perm) {
+            diff.added.push(perm.clone());
+        }
     }
-    permissions.iter().any(|perm| perm == &required)
+    for perm in &previous {
+        if !next.contains(perm) && !diff.removed.contains(perm) {
+            diff.removed.push(perm.clone());
+        }
+    }
+    diff
 }
 
-pub(crate) async fn check_permission(
+pub(crate) async fn reset_permission_grants(
     app_handle: &AppHandle,
-    operation: impl Into<String>,
-    params: Value,
+    plugin_name: &str,
+    operations: &[String],
 ) -> bool {
-    let operation = operation.into();
-    match request_permission(app_handle, operation.clone(), params).await {
-        Ok(granted) => granted,
+    if operations.is_empty() {
+        return true;
+    }
+    let payload = PermissionResetPayload {
+        plugin: plugin_name.to_string(),
+        operations: operations.to_vec(),
+    };
+    match invoke_frontend::<PermissionResetAck, _>(
+        app_handle,
+        FRONT_PERMISSION_RESET_METHOD,
+        payload,
+    )
+    .await
+    {
+        Ok(ack) => {
+            if !ack.success {
+                log::warn!(
+                    "[plugin:{}] permission grant reset rejected by frontend",
+                    plugin_name
+                );
+            }
+            ack.success
+        }
         Err(err) => {
             log::warn!(
-                "[pluginsystem] permission request '{}' failed: {err}",
-                operation
+                "[plugin:{}] failed to reset permission grants: {err}",
+                plugin_name
             );
             false
         }
     }
 }
 
+pub(crate) fn is_permission_declared(permissions: &[String], required: &str) -> bool {
+    let required = normalize_permission_name(required);
+    if required.is_empty() {
+        return false;
+    }
+    permissions.iter().any(|perm| perm == &required)
+}
+
+/// 用户明确拒绝授权时向插件派发 `permission-denied` 事件，请求失败等其他错误不会触发
+fn notify_permission_denied(plugin: String, operation: String, addr: Option<String>) {
+    let payload = json!({
+        "operation": operation,
+        "addr": addr,
+    })
+    .to_string();
+    tauri::async_runtime::spawn(async move {
+        let target = plugin.clone();
+        let result = crate::with_plugin_manager_async(move |pm| {
+            let runtime = pm
+                .plugins
+                .get(&target)
+                .filter(|plugin| plugin.state.loaded && !plugin.state.disabled)
+                .map(|plugin| plugin.runtime.clone());
+            Box::pin(async move {
+                match runtime {
+                    Some(runtime) => runtime.dispatch_permission_denied(payload).await,
+                    None => Ok(()),
+                }
+            })
+        })
+        .await;
+
+        if let Err(err) = result.and_then(|result| result) {
+            log::warn!(
+                "[plugin:{}] failed to deliver permission-denied event: {err}",
+                plugin
+            );
+        }
+    });
+}
+
 pub(crate) async fn check_permission_declared(
     app_handle: &AppHandle,
     permissions: &[String],
@@ -104,7 +435,46 @@ pub(crate) async fn check_permission_declared(
         );
         return false;
     }
-    let granted = check_permission(app_handle, operation, params).await;
+    let operation = normalize_permission_name(&operation);
+    // 无论最终是否授权，插件请求过即视为用到了该权限
+    if extract_plugin_name(&params).is_some() {
+        record_permission_use(&plugin, &operation);
+    }
+    let addr = extract_device_addr(&params);
+    if is_permission_granted_cached(&plugin, &operation, addr.as_deref()) {
+        log::info!(
+            "[plugin:{}] permission request done '{}' -> true (cached, addr={:?})",
+            plugin,
+            operation_label,
+            addr
+        );
+        return true;
+    }
+    let granted = match request_permission(app_handle, operation.clone(), params).await {
+        Ok(resp) => {
+            if resp.granted {
+                // 只缓存针对具体设备的授权，无设备地址的授权不能扩大成“所有设备”
+                if let Some(addr) = addr {
+                    let scope = if resp.all_devices {
+                        GrantScope::AllDevices
+                    } else {
+                        GrantScope::Device(addr)
+                    };
+                    record_permission_grant(&plugin, &operation, scope);
+                }
+            } else {
+                notify_permission_denied(plugin.clone(), operation.clone(), addr);
+            }
+            resp.granted
+        }
+        Err(err) => {
+            log::warn!(
+                "[pluginsystem] permission request '{}' failed: {err}",
+                operation
+            );
+            false
+        }
+    };
     log::info!(
         "[plugin:{}] permission request done '{}' -> {}",
         plugin,
@@ -167,3 +537,78 @@ pub(crate) async fn resolve_quick_app_name(device_addr: &str, pkg_name: &str) ->
     })
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{
+        GrantScope, PermissionGrantKey, diff_permissions, forget_permission_usage, grant_matches,
+        load_permission_usage, record_permission_use, unused_permissions,
+    };
+    use crate::test_support::temp_root;
+
+    fn perms(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn permission_diff_reports_added_and_removed() {
+        let diff = diff_permissions(
+            &perms(&["device", "clipboard.read"]),
+            &perms(&["device", "interconnect"]),
+        );
+        assert_eq!(diff.added, perms(&["interconnect"]));
+        assert_eq!(diff.removed, perms(&["clipboard.read"]));
+    }
+
+    #[test]
+    fn permission_diff_ignores_case_and_whitespace() {
+        let diff = diff_permissions(&perms(&[" Device "]), &perms(&["device", ""]));
+        assert!(diff.is_empty());
+    }
+
+    fn grant(scope: GrantScope) -> PermissionGrantKey {
+        PermissionGrantKey {
+            plugin: "demo".to_string(),
+            operation: "request".to_string(),
+            scope,
+        }
+    }
+
+    #[test]
+    fn scoped_grant_only_matches_its_device() {
+        let mut grants = HashSet::new();
+        grants.insert(grant(GrantScope::Device("AA:BB".to_string())));
+        assert!(grant_matches(&grants, "demo", "request", Some("AA:BB")));
+        assert!(!grant_matches(&grants, "demo", "request", Some("CC:DD")));
+        assert!(!grant_matches(&grants, "demo", "request", None));
+
+        grants.insert(grant(GrantScope::AllDevices));
+        assert!(grant_matches(&grants, "demo", "request", Some("CC:DD")));
+        assert!(!grant_matches(&grants, "other", "request", Some("CC:DD")));
+        // 全设备授权也不覆盖与设备无关的请求
+        assert!(!grant_matches(&grants, "demo", "request", None));
+    }
+
+    #[test]
+    fn permission_usage_survives_reload() {
+        let temp = temp_root();
+        let root = temp.path();
+        let declared = perms(&["device", "Clipboard.Read", "interconnect"]);
+
+        load_permission_usage(root);
+        record_permission_use("usage-demo", "device");
+        // 模拟宿主重启后重新加载
+        load_permission_usage(root);
+        record_permission_use("usage-demo", "clipboard.read");
+        assert_eq!(
+            unused_permissions("usage-demo", &declared),
+            perms(&["interconnect"])
+        );
+
+        forget_permission_usage("usage-demo");
+        load_permission_usage(root);
+        assert_eq!(unused_permissions("usage-demo", &declared).len(), 3);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::bindings::astrobox::psys_host;
+use anyhow::Error;
+use serde_json::json;
+use wasmtime::component::{Accessor, FutureReader};
+
+use psys_host::plugins::ManageError;
+
+use super::{HostCallSpan, HostString, HostVec, PluginCtx, permission::check_permission_declared};
+
+// 可读取其他插件信息的特权权限，必须在 manifest 中声明并经用户授权
+const MANAGE_PLUGINS_PERMISSION: &str = "manage_plugins";
+
+async fn list_plugin_summaries() -> Vec<psys_host::plugins::PluginSummary> {
+    let result = crate::with_plugin_manager_async(|pm| {
+        let mut summaries = pm
+            .plugins
+            .values()
+            .map(|plugin| psys_host::plugins::PluginSummary {
+                name: plugin.manifest.name.clone(),
+                version: plugin.manifest.version.clone(),
+                enabled: plugin.state.loaded && !plugin.state.disabled,
+            })
+            .collect::<Vec<_>>();
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        Box::pin(async move { summaries })
+    })
+    .await;
+
+    match result {
+        Ok(summaries) => summaries,
+        Err(err) => {
+            log::warn!("[pluginsystem] plugin list lookup failed: {err}");
+            Vec::new()
+        }
+    }
+}
+
+async fn set_plugin_enabled(name: String, enabled: bool) -> core::result::Result<(), ManageError> {
+    let result = crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move {
+            if !pm.plugins.contains_key(&name) {
+                return Err(ManageError::NotFound);
+            }
+            let success = if enabled {
+                pm.enable(&name).await
+            } else {
+                pm.disable(&name).await
+            };
+            if success {
+                Ok(())
+            } else {
+                Err(ManageError::Failed)
+            }
+        })
+    })
+    .await;
+
+    match result {
+        Ok(result) => result,
+        Err(err) => {
+            log::warn!("[pluginsystem] plugin set_enabled failed: {err}");
+            Err(ManageError::Failed)
+        }
+    }
+}
+
+impl psys_host::plugins::Host for PluginCtx {}
+
+impl psys_host::plugins::HostWithStore for PluginCtx {
+    fn list<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<
+        Output = FutureReader<core::result::Result<HostVec<psys_host::plugins::PluginSummary>, ()>>,
+    > + Send {
+        let span = HostCallSpan::new(accessor, "plugins.list");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                    instance,
+                    &mut access,
+                    span.instrument(async move {
+                        if !check_permission_declared(
+                            &app_handle,
+                            permissions.as_ref(),
+                            MANAGE_PLUGINS_PERMISSION,
+                            json!({ "plugin": plugin_name }),
+                        )
+                        .await
+                        {
+                            return Ok::<
+                                core::result::Result<
+                                    HostVec<psys_host::plugins::PluginSummary>,
+                                    (),
+                                >,
+                                Error,
+                            >(Err(()));
+                        }
+
+                        // 仅返回名称、版本与启用状态，不暴露任何插件的数据或密钥
+                        let summaries = list_plugin_summaries().await;
+                        Ok::<
+                            core::result::Result<HostVec<psys_host::plugins::PluginSummary>, ()>,
+                            Error,
+                        >(Ok(summaries.into_iter().collect()))
+                    }),
+                )
+        });
+        async move { future }
+    }
+
+    fn set_enabled<T>(
+        accessor: &Accessor<T, Self>,
+        name: HostString,
+        enabled: bool,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ManageError>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "plugins.set_enabled");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let target = name.to_string();
+                    // 通过该接口停用自身会导致插件无法再恢复，应使用自身停用接口
+                    if target == plugin_name {
+                        log::warn!(
+                            "[plugin:{}] plugins.set_enabled rejected: cannot target itself",
+                            plugin_name
+                        );
+                        return Ok::<core::result::Result<(), ManageError>, Error>(Err(
+                            ManageError::SelfTarget,
+                        ));
+                    }
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        MANAGE_PLUGINS_PERMISSION,
+                        json!({ "plugin": plugin_name.clone(), "target": target.clone() }),
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), ManageError>, Error>(Err(
+                            ManageError::PermissionDenied,
+                        ));
+                    }
+
+                    log::info!(
+                        "[plugin:{}] set plugin '{}' enabled={}",
+                        plugin_name,
+                        target,
+                        enabled
+                    );
+                    Ok::<core::result::Result<(), ManageError>, Error>(
+                        set_plugin_enabled(target, enabled).await,
+                    )
+                }),
+            )
+        });
+        async move { future }
+    }
+}
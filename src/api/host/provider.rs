@@ -0,0 +1,194 @@
+use crate::bindings::astrobox::psys_host;
+use crate::plugin::{PluginRuntime, ProviderRegistration};
+use crate::provider_action_bridge;
+use anyhow::Error;
+use serde_json::json;
+use std::time::Duration;
+use wasmtime::component::{Accessor, FutureReader};
+
+use super::{HostCallSpan, HostString, PluginCtx, permission::check_permission_declared};
+
+// 派发给提供者插件和等待其返回共用这一时长
+const PROVIDER_RESOLVE_TIMEOUT: Duration = Duration::from_secs(30);
+const PROVIDER_RESOLVE_PERMISSION: &str = "resolve_provider";
+
+/// 挂起的 resolve 请求；插件丢弃 future、超时或派发失败时都在丢弃时取消，提供者之后的响应直接丢弃
+struct PendingResolve(String);
+
+impl Drop for PendingResolve {
+    fn drop(&mut self) {
+        provider_action_bridge::cancel_pending_provider_action(&self.0);
+    }
+}
+
+async fn find_provider(
+    provider_name: String,
+) -> Option<(String, ProviderRegistration, PluginRuntime)> {
+    let result = crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.find_provider(&provider_name).await })
+    })
+    .await;
+
+    match result {
+        Ok(found) => found,
+        Err(err) => {
+            log::warn!("[pluginsystem] provider lookup failed: {err}");
+            None
+        }
+    }
+}
+
+fn to_provider_info(
+    plugin_name: String,
+    registration: ProviderRegistration,
+) -> psys_host::provider::ProviderInfo {
+    psys_host::provider::ProviderInfo {
+        name: registration.name,
+        plugin_name,
+        provider_type: registration.provider_type,
+    }
+}
+
+async fn resolve_provider(
+    caller: &str,
+    provider_name: String,
+    input: String,
+) -> core::result::Result<String, ()> {
+    let Some((owner, registration, runtime)) = find_provider(provider_name.clone()).await else {
+        log::warn!(
+            "[plugin:{}] provider '{}' not found or its plugin is disabled",
+            caller,
+            provider_name
+        );
+        return Err(());
+    };
+
+    if !matches!(
+        registration.provider_type,
+        psys_host::register::ProviderType::Url
+    ) {
+        log::warn!(
+            "[plugin:{}] provider '{}' is not a url provider and cannot be resolved",
+            caller,
+            provider_name
+        );
+        return Err(());
+    }
+
+    if owner == caller {
+        log::warn!(
+            "[plugin:{}] provider '{}' cannot be resolved by its own plugin",
+            caller,
+            provider_name
+        );
+        return Err(());
+    }
+
+    let (request_id, rx) =
+        provider_action_bridge::register_pending_provider_action(&provider_name, "resolve", None);
+    let _pending = PendingResolve(request_id.clone());
+    let payload = json!({
+        "requestId": request_id.clone(),
+        "provider": provider_name.clone(),
+        "action": "resolve",
+        "sourcePlugin": caller,
+        "payload": input,
+    })
+    .to_string();
+
+    // 提供者插件卡在事件处理里时，派发本身也可能不返回
+    let resolved = tokio::time::timeout(PROVIDER_RESOLVE_TIMEOUT, async {
+        if let Err(err) = runtime.dispatch_provider_action(payload).await {
+            log::warn!(
+                "[plugin:{}] failed to dispatch provider '{}' resolve to {}: {err}",
+                caller,
+                provider_name,
+                owner
+            );
+            return Err(());
+        }
+        rx.await.map_err(|_| ())
+    })
+    .await;
+
+    match resolved {
+        Ok(Ok(response)) => Ok(response),
+        Ok(Err(())) => Err(()),
+        Err(_) => {
+            log::warn!(
+                "[plugin:{}] provider '{}' resolve timed out",
+                caller,
+                provider_name
+            );
+            Err(())
+        }
+    }
+}
+
+impl psys_host::provider::Host for PluginCtx {}
+
+impl psys_host::provider::HostWithStore for PluginCtx {
+    fn get<T>(
+        accessor: &Accessor<T, Self>,
+        name: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<Option<psys_host::provider::ProviderInfo>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "provider.get");
+        let instance = accessor.instance();
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let info = find_provider(name.to_string())
+                        .await
+                        .map(|(owner, registration, _)| to_provider_info(owner, registration));
+                    Ok::<Option<psys_host::provider::ProviderInfo>, Error>(info)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn resolve<T>(
+        accessor: &Accessor<T, Self>,
+        name: HostString,
+        input: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<HostString, ()>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "provider.resolve");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "provider": name.to_string(),
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        PROVIDER_RESOLVE_PERMISSION,
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(()));
+                    }
+
+                    let result =
+                        resolve_provider(&plugin_name, name.to_string(), input.to_string())
+                            .await
+                            .map(HostString::from);
+                    Ok::<core::result::Result<HostString, ()>, Error>(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+}
@@ -4,6 +4,14 @@ use crate::provider_action_bridge;
 use super::{HostString, PluginCtx};
 
 impl psys_host::provider_callback::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "provider_callback.resolve_provider_action")
+        )
+    )]
     fn resolve_provider_action(
         &mut self,
         request_id: HostString,
@@ -37,6 +45,14 @@ impl psys_host::provider_callback::Host for PluginCtx {
         Ok(resolved)
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "provider_callback.report_provider_action_progress")
+        )
+    )]
     fn report_provider_action_progress(
         &mut self,
         request_id: HostString,
@@ -7,6 +7,14 @@ use super::{HostString, PluginCtx, permission::check_permission_declared_blockin
 const FRONT_FILE_ADD_TO_QUEUE_METHOD: &str = "host/file/add_to_queue";
 
 impl psys_host::queue::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "queue.add_resource_to_queue")
+        )
+    )]
     fn add_resource_to_queue(
         &mut self,
         res_type: psys_host::queue::ResourceType,
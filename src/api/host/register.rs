@@ -1,17 +1,85 @@
 use crate::bindings::astrobox::psys_host;
+use crate::deeplink::{DeeplinkClaimError, claim_deeplink_prefix};
 use crate::plugin::{
     CardRegistration, InterconnectRecvRegistration, ProviderRegistration, TransportRecvRegistration,
 };
 use anyhow::Error;
+use psys_host::register::DeeplinkError;
 use serde_json::json;
 use wasmtime::component::{Accessor, FutureReader};
 
 use super::{
-    HostString, PluginCtx,
+    HostCallSpan, HostString, HostVec, PluginCtx,
     permission::{check_permission_declared, resolve_device_name, resolve_quick_app_name},
+    transport::CUSTOM_PROTOCOL_PERMISSION,
 };
 
-impl psys_host::register::Host for PluginCtx {}
+// 接收系统分享面板分享给 AstroBox 的文件
+const SHARE_TARGET_PERMISSION: &str = "share_target";
+
+impl psys_host::register::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "register.register_command")
+        )
+    )]
+    fn register_command(
+        &mut self,
+        command: HostString,
+    ) -> wasmtime::Result<core::result::Result<(), ()>> {
+        if crate::plugin_command::register_command(self.plugin_name(), &command) {
+            Ok(Ok(()))
+        } else {
+            log::warn!(
+                "[plugin:{}] register_command '{}' rejected: invalid command name",
+                self.plugin_name(),
+                command
+            );
+            Ok(Err(()))
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "register.register_web_root")
+        )
+    )]
+    fn register_web_root(
+        &mut self,
+        dir: HostString,
+    ) -> wasmtime::Result<core::result::Result<HostString, ()>> {
+        let url = crate::webroot::register_web_root(
+            self.plugin_name(),
+            self.plugin_root(),
+            &self.additional_files,
+            &dir,
+        );
+        match url {
+            Ok(url) => Ok(Ok(HostString::from(url))),
+            Err(crate::webroot::WebRootError::InvalidDir) => {
+                log::warn!(
+                    "[plugin:{}] register_web_root '{}' rejected: path escapes plugin directory",
+                    self.plugin_name(),
+                    dir
+                );
+                Ok(Err(()))
+            }
+            Err(crate::webroot::WebRootError::OriginConflict) => {
+                log::warn!(
+                    "[plugin:{}] register_web_root rejected: origin is used by another plugin",
+                    self.plugin_name()
+                );
+                Ok(Err(()))
+            }
+        }
+    }
+}
 
 impl psys_host::register::HostWithStore for PluginCtx {
     fn register_transport_recv<T>(
@@ -19,51 +87,56 @@ impl psys_host::register::HostWithStore for PluginCtx {
         addr: HostString,
         filter: psys_host::register::TransportRecvFiler,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "register.register_transport_recv");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let register_state = accessor.with(|mut access| access.get().register_state());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let addr = addr.to_string();
-                let psys_host::register::TransportRecvFiler {
-                    xiaomi_vela_v5_channel_id,
-                    xiaomi_vela_v5_protobuf_typeid,
-                } = filter;
-                let device_name = resolve_device_name(&addr).await;
-                let params = json!({
-                    "plugin": plugin_name,
-                    "addr": addr.clone(),
-                    "deviceName": device_name,
-                    "filter": {
-                        "xiaomiVelaV5ChannelId": xiaomi_vela_v5_channel_id,
-                        "xiaomiVelaV5ProtobufTypeid": xiaomi_vela_v5_protobuf_typeid,
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = addr.to_string();
+                    let psys_host::register::TransportRecvFiler {
+                        xiaomi_vela_v5_channel_id,
+                        xiaomi_vela_v5_protobuf_typeid,
+                    } = filter;
+                    let device_name = resolve_device_name(&addr).await;
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": addr.clone(),
+                        "deviceName": device_name,
+                        "filter": {
+                            "xiaomiVelaV5ChannelId": xiaomi_vela_v5_channel_id,
+                            "xiaomiVelaV5ProtobufTypeid": xiaomi_vela_v5_protobuf_typeid,
+                        }
+                    });
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "register_transport_recv",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(()));
                     }
-                });
-
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "register_transport_recv",
-                    params,
-                )
-                .await
-                {
-                    return Ok(Err(()));
-                }
-
-                register_state
-                    .register_transport_recv(TransportRecvRegistration {
-                        addr,
-                        filter: psys_host::register::TransportRecvFiler {
-                            xiaomi_vela_v5_channel_id,
-                            xiaomi_vela_v5_protobuf_typeid,
-                        },
-                    })
-                    .await;
-                Ok::<core::result::Result<(), ()>, Error>(Ok(()))
-            })
+
+                    register_state
+                        .register_transport_recv(TransportRecvRegistration {
+                            addr,
+                            filter: psys_host::register::TransportRecvFiler {
+                                xiaomi_vela_v5_channel_id,
+                                xiaomi_vela_v5_protobuf_typeid,
+                            },
+                        })
+                        .await;
+                    Ok::<core::result::Result<(), ()>, Error>(Ok(()))
+                }),
+            )
         });
         async move { future }
     }
@@ -73,39 +146,44 @@ impl psys_host::register::HostWithStore for PluginCtx {
         addr: HostString,
         pkg_name: HostString,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "register.register_interconnect_recv");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let register_state = accessor.with(|mut access| access.get().register_state());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let addr = addr.to_string();
-                let pkg_name = pkg_name.to_string();
-                let app_name = resolve_quick_app_name(&addr, &pkg_name).await;
-                let params = json!({
-                    "plugin": plugin_name,
-                    "addr": addr.clone(),
-                    "pkgName": pkg_name.clone(),
-                    "appName": app_name,
-                });
-
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "register_interconnect_recv",
-                    params,
-                )
-                .await
-                {
-                    return Ok(Err(()));
-                }
-
-                register_state
-                    .register_interconnect_recv(InterconnectRecvRegistration { addr, pkg_name })
-                    .await;
-                Ok::<core::result::Result<(), ()>, Error>(Ok(()))
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = addr.to_string();
+                    let pkg_name = pkg_name.to_string();
+                    let app_name = resolve_quick_app_name(&addr, &pkg_name).await;
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": addr.clone(),
+                        "pkgName": pkg_name.clone(),
+                        "appName": app_name,
+                    });
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "register_interconnect_recv",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(()));
+                    }
+
+                    register_state
+                        .register_interconnect_recv(InterconnectRecvRegistration { addr, pkg_name })
+                        .await;
+                    Ok::<core::result::Result<(), ()>, Error>(Ok(()))
+                }),
+            )
         });
         async move { future }
     }
@@ -113,35 +191,191 @@ impl psys_host::register::HostWithStore for PluginCtx {
     fn register_deeplink_action<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "register.register_deeplink_action");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let register_state = accessor.with(|mut access| access.get().register_state());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let params = json!({
-                    "plugin": plugin_name,
-                    "action": "deeplink",
-                });
-
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "register_deeplink_action",
-                    params,
-                )
-                .await
-                {
-                    return Ok(Err(()));
-                }
-
-                if register_state.try_register_deeplink().await {
-                    Ok::<core::result::Result<(), ()>, Error>(Ok(()))
-                } else {
-                    Ok::<core::result::Result<(), ()>, Error>(Err(()))
-                }
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "action": "deeplink",
+                    });
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "register_deeplink_action",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(()));
+                    }
+
+                    if register_state.try_register_deeplink().await {
+                        Ok::<core::result::Result<(), ()>, Error>(Ok(()))
+                    } else {
+                        Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                    }
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn register_deeplink_path<T>(
+        accessor: &Accessor<T, Self>,
+        prefix: HostString,
+    ) -> impl core::future::Future<
+        Output = FutureReader<core::result::Result<(), psys_host::register::DeeplinkError>>,
+    > + Send {
+        let span = HostCallSpan::new(accessor, "register.register_deeplink_path");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let prefix = prefix.to_string();
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "action": "deeplink",
+                        "prefix": prefix.clone(),
+                    });
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "register_deeplink_action",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(DeeplinkError::PermissionDenied));
+                    }
+
+                    let result = claim_deeplink_prefix(&plugin_name, &prefix).map_err(|err| {
+                        log::warn!(
+                            "[plugin:{}] register_deeplink_path '{}' rejected: {:?}",
+                            plugin_name,
+                            prefix,
+                            err
+                        );
+                        match err {
+                            DeeplinkClaimError::InvalidPrefix => DeeplinkError::InvalidPrefix,
+                            DeeplinkClaimError::Conflict => DeeplinkError::Conflict,
+                        }
+                    });
+                    Ok::<core::result::Result<(), DeeplinkError>, Error>(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn register_protocol<T>(
+        accessor: &Accessor<T, Self>,
+        name: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "register.register_protocol");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let name = name.to_string();
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "protocol": name.clone(),
+                    });
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        CUSTOM_PROTOCOL_PERMISSION,
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(()));
+                    }
+
+                    let result =
+                        crate::transport_runtime::claim_custom_protocol(&plugin_name, &name)
+                            .map_err(|err| {
+                                log::warn!(
+                                    "[plugin:{}] register_protocol '{}' rejected: {:?}",
+                                    plugin_name,
+                                    name,
+                                    err
+                                );
+                            });
+                    Ok::<core::result::Result<(), ()>, Error>(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn register_share_target<T>(
+        accessor: &Accessor<T, Self>,
+        mime_patterns: HostVec<HostString>,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "register.register_share_target");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let mime_patterns = mime_patterns
+                        .into_iter()
+                        .map(|pattern| pattern.to_string())
+                        .collect::<Vec<_>>();
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "mimePatterns": mime_patterns.clone(),
+                    });
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        SHARE_TARGET_PERMISSION,
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(()));
+                    }
+
+                    let result =
+                        crate::share_target::set_share_target(&plugin_name, &mime_patterns)
+                            .map_err(|err| {
+                                log::warn!(
+                                    "[plugin:{}] register_share_target rejected: {:?}",
+                                    plugin_name,
+                                    err
+                                );
+                            });
+                    Ok::<core::result::Result<(), ()>, Error>(result)
+                }),
+            )
         });
         async move { future }
     }
@@ -151,43 +385,48 @@ impl psys_host::register::HostWithStore for PluginCtx {
         name: HostString,
         provider_type: psys_host::register::ProviderType,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "register.register_provider");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let register_state = accessor.with(|mut access| access.get().register_state());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let name = name.to_string();
-                let provider_label = match &provider_type {
-                    psys_host::register::ProviderType::Url => "url",
-                    psys_host::register::ProviderType::Custom => "custom",
-                };
-                let params = json!({
-                    "plugin": plugin_name,
-                    "name": name.clone(),
-                    "providerType": provider_label,
-                });
-
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "register_provider",
-                    params,
-                )
-                .await
-                {
-                    return Ok(Err(()));
-                }
-
-                register_state
-                    .register_provider(ProviderRegistration {
-                        name,
-                        provider_type,
-                    })
-                    .await;
-                Ok::<core::result::Result<(), ()>, Error>(Ok(()))
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let name = name.to_string();
+                    let provider_label = match &provider_type {
+                        psys_host::register::ProviderType::Url => "url",
+                        psys_host::register::ProviderType::Custom => "custom",
+                    };
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "name": name.clone(),
+                        "providerType": provider_label,
+                    });
+
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "register_provider",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok(Err(()));
+                    }
+
+                    register_state
+                        .register_provider(ProviderRegistration {
+                            name,
+                            provider_type,
+                        })
+                        .await;
+                    Ok::<core::result::Result<(), ()>, Error>(Ok(()))
+                }),
+            )
         });
         async move { future }
     }
@@ -198,22 +437,53 @@ impl psys_host::register::HostWithStore for PluginCtx {
         id: HostString,
         name: HostString,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "register.register_card");
         let instance = accessor.instance();
         let register_state = accessor.with(|mut access| access.get().register_state());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let id = id.to_string();
-                let name = name.to_string();
-
-                register_state
-                    .register_card(CardRegistration {
-                        card_type,
-                        id,
-                        name,
-                    })
-                    .await;
-                Ok::<core::result::Result<(), ()>, Error>(Ok(()))
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let id = id.to_string();
+                    let name = name.to_string();
+
+                    register_state
+                        .register_card(CardRegistration {
+                            card_type,
+                            id,
+                            name,
+                        })
+                        .await;
+                    Ok::<core::result::Result<(), ()>, Error>(Ok(()))
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn unregister_card<T>(
+        accessor: &Accessor<T, Self>,
+        id: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "register.unregister_card");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let register_state = accessor.with(|mut access| access.get().register_state());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let id = id.to_string();
+                    if !register_state.unregister_card(&id).await {
+                        return Ok(Err(()));
+                    }
+                    super::ui::clear_card_content(&app_handle, &plugin_name, &id);
+                    Ok::<core::result::Result<(), ()>, Error>(Ok(()))
+                }),
+            )
         });
         async move { future }
     }
@@ -0,0 +1,238 @@
+use crate::bindings::astrobox::psys_host;
+use anyhow::Error;
+use psys_host::secrets::SecretError;
+use serde_json::json;
+use wasmtime::component::{Accessor, FutureReader};
+
+use super::{HostCallSpan, HostString, PluginCtx, permission::check_permission_declared};
+
+// 系统钥匙串中的服务名前缀，按插件隔离
+const SECRET_SERVICE_PREFIX: &str = "astrobox.plugin";
+const SECRET_KEY_MAX_LEN: usize = 256;
+const SECRET_VALUE_MAX_LEN: usize = 8 * 1024;
+
+fn secret_service(plugin_name: &str) -> String {
+    format!("{SECRET_SERVICE_PREFIX}.{plugin_name}")
+}
+
+fn is_valid_secret_key(key: &str) -> bool {
+    !key.trim().is_empty() && key.len() <= SECRET_KEY_MAX_LEN
+}
+
+fn map_keyring_error(plugin_name: &str, operation: &str, err: keyring::Error) -> SecretError {
+    match err {
+        keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_) => {
+            log::warn!(
+                "[plugin:{}] secrets.{} failed: keychain unavailable: {err}",
+                plugin_name,
+                operation
+            );
+            SecretError::Unavailable
+        }
+        other => {
+            log::warn!(
+                "[plugin:{}] secrets.{} failed: {other}",
+                plugin_name,
+                operation
+            );
+            SecretError::Failed
+        }
+    }
+}
+
+async fn with_secret_entry<R, F>(
+    plugin_name: String,
+    key: String,
+    operation: &'static str,
+    action: F,
+) -> core::result::Result<R, SecretError>
+where
+    R: Send + 'static,
+    F: FnOnce(&keyring::Entry) -> keyring::Result<R> + Send + 'static,
+{
+    // 钥匙串访问是阻塞调用，放到 blocking 线程池执行
+    let result = tokio::task::spawn_blocking(move || {
+        let entry = keyring::Entry::new(&secret_service(&plugin_name), &key)
+            .map_err(|err| map_keyring_error(&plugin_name, operation, err))?;
+        action(&entry).map_err(|err| map_keyring_error(&plugin_name, operation, err))
+    })
+    .await;
+
+    match result {
+        Ok(result) => result,
+        Err(err) => {
+            log::error!("[pluginsystem] secrets.{} task failed: {err}", operation);
+            Err(SecretError::Failed)
+        }
+    }
+}
+
+async fn ensure_secrets_permission(
+    app_handle: &tauri::AppHandle,
+    permissions: &[String],
+    plugin_name: &str,
+    key: &str,
+) -> core::result::Result<(), SecretError> {
+    if !is_valid_secret_key(key) {
+        log::warn!(
+            "[plugin:{}] secrets rejected: invalid key (len={})",
+            plugin_name,
+            key.len()
+        );
+        return Err(SecretError::InvalidKey);
+    }
+
+    if !check_permission_declared(
+        app_handle,
+        permissions,
+        "secrets",
+        json!({ "plugin": plugin_name }),
+    )
+    .await
+    {
+        return Err(SecretError::PermissionDenied);
+    }
+
+    Ok(())
+}
+
+impl psys_host::secrets::Host for PluginCtx {}
+
+impl psys_host::secrets::HostWithStore for PluginCtx {
+    fn get<T>(
+        accessor: &Accessor<T, Self>,
+        key: HostString,
+    ) -> impl core::future::Future<
+        Output = FutureReader<core::result::Result<Option<HostString>, SecretError>>,
+    > + Send {
+        let span = HostCallSpan::new(accessor, "secrets.get");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let key = key.to_string();
+                    if let Err(err) = ensure_secrets_permission(
+                        &app_handle,
+                        permissions.as_ref(),
+                        &plugin_name,
+                        &key,
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<Option<HostString>, SecretError>, Error>(
+                            Err(err),
+                        );
+                    }
+
+                    let result = with_secret_entry(plugin_name, key, "get", |entry| {
+                        match entry.get_password() {
+                            Ok(value) => Ok(Some(value)),
+                            Err(keyring::Error::NoEntry) => Ok(None),
+                            Err(err) => Err(err),
+                        }
+                    })
+                    .await
+                    .map(|value| value.map(HostString::from));
+                    Ok(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn set<T>(
+        accessor: &Accessor<T, Self>,
+        key: HostString,
+        value: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), SecretError>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "secrets.set");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let key = key.to_string();
+                    if let Err(err) = ensure_secrets_permission(
+                        &app_handle,
+                        permissions.as_ref(),
+                        &plugin_name,
+                        &key,
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), SecretError>, Error>(Err(err));
+                    }
+
+                    let value = value.to_string();
+                    if value.len() > SECRET_VALUE_MAX_LEN {
+                        log::warn!(
+                            "[plugin:{}] secrets.set rejected: value too large ({} bytes)",
+                            plugin_name,
+                            value.len()
+                        );
+                        return Ok(Err(SecretError::Failed));
+                    }
+
+                    let result = with_secret_entry(plugin_name, key, "set", move |entry| {
+                        entry.set_password(&value)
+                    })
+                    .await;
+                    Ok(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn delete<T>(
+        accessor: &Accessor<T, Self>,
+        key: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), SecretError>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "secrets.delete");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let key = key.to_string();
+                    if let Err(err) = ensure_secrets_permission(
+                        &app_handle,
+                        permissions.as_ref(),
+                        &plugin_name,
+                        &key,
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), SecretError>, Error>(Err(err));
+                    }
+
+                    // 删除不存在的键视为成功
+                    let result = with_secret_entry(plugin_name, key, "delete", |entry| match entry
+                        .delete_credential()
+                    {
+                        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                        Err(err) => Err(err),
+                    })
+                    .await;
+                    Ok(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+}
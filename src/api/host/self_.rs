@@ -0,0 +1,266 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Error, Result};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use serde_json::json;
+use wasmtime::component::{Accessor, FutureReader};
+
+use crate::bindings::astrobox::psys_host;
+
+use super::{HostCallSpan, HostString, PluginCtx, permission::check_permission_declared};
+
+// 插件在沙箱内看到的目录：安装目录即数据目录，均以 `.` 预打开
+const GUEST_PLUGIN_DIR: &str = ".";
+// 宿主绝对路径会暴露宿主文件系统结构，需要声明该权限才返回
+const HOST_PATHS_PERMISSION: &str = "host_paths";
+// 插件根目录下保存安装标识的目录；插件更新会替换插件目录，因此不能放在插件目录内
+const INSTALL_IDS_DIR: &str = ".install-ids";
+// 致命错误信息的最大长度（字符数），超出部分截断
+const FATAL_MESSAGE_MAX_CHARS: usize = 1024;
+
+// 串行化安装标识的生成，避免并发调用生成两个不同的标识
+static INSTALL_ID_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn install_id_path(plugins_root: &Path, plugin_name: &str) -> PathBuf {
+    plugins_root.join(INSTALL_IDS_DIR).join(plugin_name)
+}
+
+// 随机生成 UUID v4
+fn generate_install_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn is_valid_install_id(id: &str) -> bool {
+    id.len() == 36
+        && id.char_indices().all(|(idx, c)| match idx {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// 读取插件的安装标识，不存在时生成并先写临时文件再重命名，保证落盘是原子的
+fn load_or_create_install_id(path: &Path) -> Result<String> {
+    let _guard = INSTALL_ID_LOCK
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if let Ok(content) = fs::read_to_string(path) {
+        let id = content.trim();
+        if is_valid_install_id(id) {
+            return Ok(id.to_string());
+        }
+        log::warn!(
+            "[pluginsystem] invalid install id in {}, regenerating",
+            path.display()
+        );
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let id = generate_install_id();
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, &id)
+        .with_context(|| format!("failed to write install id {}", tmp.display()))?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("failed to persist install id {}", path.display()))?;
+    Ok(id)
+}
+
+/// 卸载插件时删除安装标识，重新安装后会得到新的标识
+pub(crate) fn forget_install_id(plugins_root: &Path, plugin_name: &str) {
+    let path = install_id_path(plugins_root, plugin_name);
+    if let Err(err) = fs::remove_file(&path) {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            log::warn!(
+                "[plugin:{}] failed to remove install id: {err}",
+                plugin_name
+            );
+        }
+    }
+}
+
+impl psys_host::self_::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "self.resource_usage")
+        )
+    )]
+    fn resource_usage(&mut self) -> wasmtime::Result<psys_host::self_::UsageInfo> {
+        let limiter = self.limiter();
+        Ok(psys_host::self_::UsageInfo {
+            memory_bytes: limiter.memory_bytes() as u64,
+            memory_limit_bytes: limiter.memory_limit() as u64,
+            // 燃料在每次进入插件前重置，宿主调用中无法读取 store 的剩余额度
+            fuel_remaining: None,
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "self.install_id")
+        )
+    )]
+    fn install_id(&mut self) -> wasmtime::Result<HostString> {
+        let plugins_root = self
+            .plugin_root()
+            .parent()
+            .unwrap_or(self.plugin_root().as_path());
+        let id = load_or_create_install_id(&install_id_path(plugins_root, self.plugin_name()))?;
+        Ok(HostString::from(id))
+    }
+}
+
+impl psys_host::self_::HostWithStore for PluginCtx {
+    fn paths<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<Output = FutureReader<psys_host::self_::PathInfo>> + Send {
+        let span = HostCallSpan::new(accessor, "self.paths");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let plugin_root = accessor.with(|mut access| access.get().plugin_root().clone());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let granted = check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        HOST_PATHS_PERMISSION,
+                        json!({ "plugin": plugin_name }),
+                    )
+                    .await;
+                    let host_dir = granted.then(|| plugin_root.to_string_lossy().to_string());
+                    Ok::<psys_host::self_::PathInfo, Error>(psys_host::self_::PathInfo {
+                        install_dir: GUEST_PLUGIN_DIR.to_string(),
+                        data_dir: GUEST_PLUGIN_DIR.to_string(),
+                        host_install_dir: host_dir.clone(),
+                        host_data_dir: host_dir,
+                    })
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn report_fatal<T>(
+        accessor: &Accessor<T, Self>,
+        code: HostString,
+        message: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<()>> + Send {
+        let span = HostCallSpan::new(accessor, "self.report_fatal");
+        let instance = accessor.instance();
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let code = code.to_string();
+                    let message = message
+                        .chars()
+                        .take(FATAL_MESSAGE_MAX_CHARS)
+                        .collect::<String>();
+                    // 停止插件需要等待当前调用返回，放到独立任务中执行，避免在插件自身的调用里等待自己
+                    tokio::spawn(async move {
+                        let result = crate::with_plugin_manager_async(move |pm| {
+                            Box::pin(
+                                async move { pm.report_fatal(&plugin_name, code, message).await },
+                            )
+                        })
+                        .await;
+                        if let Err(err) = result {
+                            log::error!("[pluginsystem] report_fatal failed: {err}");
+                        }
+                    });
+                    Ok::<(), Error>(())
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn restart_info<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<Output = FutureReader<psys_host::self_::RestartInfo>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "self.restart_info");
+        let instance = accessor.instance();
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let lookup_name = plugin_name.clone();
+                    let result = crate::with_plugin_manager_async(move |pm| {
+                        let info = pm.restart_info(&lookup_name);
+                        Box::pin(async move { info })
+                    })
+                    .await;
+                    let (restart_count, last_failure) = match result {
+                        Ok(info) => info.unwrap_or_default(),
+                        Err(err) => {
+                            log::warn!(
+                                "[plugin:{}] restart_info lookup failed: {err}",
+                                plugin_name
+                            );
+                            (0, None)
+                        }
+                    };
+                    Ok::<psys_host::self_::RestartInfo, Error>(psys_host::self_::RestartInfo {
+                        restart_count,
+                        last_failure,
+                    })
+                }),
+            )
+        });
+        async move { future }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        forget_install_id, install_id_path, is_valid_install_id, load_or_create_install_id,
+    };
+    use crate::test_support::temp_root;
+
+    #[test]
+    fn install_id_is_stable_until_forgotten() {
+        let temp = temp_root();
+        let root = temp.path();
+        let path = install_id_path(root, "demo");
+
+        let first = load_or_create_install_id(&path).unwrap();
+        assert!(is_valid_install_id(&first));
+        assert_eq!(load_or_create_install_id(&path).unwrap(), first);
+
+        forget_install_id(root, "demo");
+        let second = load_or_create_install_id(&path).unwrap();
+        assert!(is_valid_install_id(&second));
+        assert_ne!(second, first);
+    }
+}
@@ -0,0 +1,102 @@
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::str::FromStr;
+
+use super::http::IpRule;
+
+// manifest 中声明后才会开放 wasi:sockets 的权限名
+pub(crate) const SOCKETS_PERMISSION: &str = "sockets";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SocketHost {
+    Ip(IpRule),
+    Name(String),
+}
+
+/// manifest `sockets` 中的一条 `host:port` 规则；host 可以是 IP、CIDR、`[IPv6]` 或域名，port 可以是 `*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SocketRule {
+    host: SocketHost,
+    port: Option<u16>,
+}
+
+impl SocketRule {
+    pub(crate) fn parse(rule: &str) -> Option<Self> {
+        let (host, port) = rule.trim().rsplit_once(':')?;
+        let port = match port {
+            "*" => None,
+            port => Some(port.parse::<u16>().ok()?),
+        };
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+        if host.is_empty() {
+            return None;
+        }
+        let host = match IpRule::parse(host) {
+            Some(rule) => SocketHost::Ip(rule),
+            None if IpAddr::from_str(host).is_err() && !host.contains(['/', ':', '*']) => {
+                SocketHost::Name(host.to_ascii_lowercase())
+            }
+            None => return None,
+        };
+        Some(Self { host, port })
+    }
+
+    fn port_matches(&self, port: u16) -> bool {
+        self.port.is_none_or(|allowed| allowed == port)
+    }
+}
+
+/// 判断插件能否绑定或连接到该地址；域名规则在检查时解析，只放行解析结果中的地址
+pub(crate) async fn socket_addr_allowed(rules: &[SocketRule], addr: SocketAddr) -> bool {
+    let ip = addr.ip().to_canonical();
+    for rule in rules.iter().filter(|rule| rule.port_matches(addr.port())) {
+        let allowed = match &rule.host {
+            SocketHost::Ip(ip_rule) => ip_rule.contains(ip),
+            SocketHost::Name(name) => {
+                let target = (name.clone(), addr.port());
+                tokio::task::spawn_blocking(move || target.to_socket_addrs())
+                    .await
+                    .ok()
+                    .and_then(Result::ok)
+                    .is_some_and(|mut resolved| {
+                        resolved.any(|candidate| candidate.ip().to_canonical() == ip)
+                    })
+            }
+        };
+        if allowed {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SocketRule, socket_addr_allowed};
+
+    #[tokio::test]
+    async fn allowlist_matches_host_and_port() {
+        let rules = ["192.168.1.0/24:5353", "[::1]:*", "127.0.0.1:8080"]
+            .iter()
+            .map(|rule| SocketRule::parse(rule).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(socket_addr_allowed(&rules, "192.168.1.20:5353".parse().unwrap()).await);
+        assert!(!socket_addr_allowed(&rules, "192.168.1.20:80".parse().unwrap()).await);
+        assert!(socket_addr_allowed(&rules, "[::1]:9000".parse().unwrap()).await);
+        assert!(socket_addr_allowed(&rules, "127.0.0.1:8080".parse().unwrap()).await);
+        assert!(!socket_addr_allowed(&rules, "10.0.0.1:8080".parse().unwrap()).await);
+        assert!(!socket_addr_allowed(&[], "127.0.0.1:8080".parse().unwrap()).await);
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert!(SocketRule::parse("example.local:443").is_some());
+        assert!(SocketRule::parse("example.local").is_none());
+        assert!(SocketRule::parse(":80").is_none());
+        assert!(SocketRule::parse("10.0.0.0/40:80").is_none());
+        assert!(SocketRule::parse("127.0.0.1:99999").is_none());
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::bindings::astrobox::psys_host;
+use crate::manifest::PluginManifest;
+use anyhow::Error;
+use once_cell::sync::Lazy;
+use wasmtime::component::{Accessor, FutureReader};
+
+use super::{HostCallSpan, PluginCtx};
+
+/// 每个插件目录的默认磁盘配额
+pub const DEFAULT_PLUGIN_STORAGE_QUOTA_BYTES: u64 = 50 * 1024 * 1024;
+// 超出配额前重新统计时，两次统计至少间隔这么久
+const STORAGE_RESCAN_MIN_INTERVAL: Duration = Duration::from_secs(10);
+
+static STORAGE_QUOTA_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_PLUGIN_STORAGE_QUOTA_BYTES);
+
+struct StorageUsage {
+    used_bytes: u64,
+    // `None` 表示宿主改动过插件目录，下一次写入前重新统计
+    scanned_at: Option<Instant>,
+    // 随插件安装的文件，统计时跳过
+    payload: Arc<HashSet<PathBuf>>,
+}
+
+// 插件目录 -> 用量，插件启动时在后台统计，之后随 WASI 写入递增
+static STORAGE_USAGE: Lazy<Mutex<HashMap<PathBuf, StorageUsage>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 设置每个插件目录的磁盘配额
+pub fn set_storage_quota_bytes(bytes: u64) {
+    STORAGE_QUOTA_BYTES.store(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn storage_quota_bytes() -> u64 {
+    STORAGE_QUOTA_BYTES.load(Ordering::Relaxed)
+}
+
+/// 随插件安装的文件：清单、入口和附加组件的 wasm、图标以及 `additional_files`，
+/// 声明为目录时包含其下所有文件
+pub(crate) fn install_payload(manifest: &PluginManifest) -> Vec<String> {
+    let mut payload = vec!["manifest.json".to_string(), manifest.entry.clone()];
+    payload.extend(
+        manifest
+            .components
+            .iter()
+            .map(|component| component.entry.clone()),
+    );
+    if !manifest.icon.trim().is_empty() {
+        payload.push(manifest.icon.clone());
+    }
+    payload.extend(manifest.additional_files.iter().cloned());
+    payload
+}
+
+fn resolve_payload(dir: &Path, payload: &[String]) -> HashSet<PathBuf> {
+    let mut files = HashSet::new();
+    let mut pending = payload
+        .iter()
+        .filter_map(|entry| super::assets::sanitize_declared(entry))
+        .map(|entry| dir.join(entry))
+        .collect::<Vec<_>>();
+    while let Some(current) = pending.pop() {
+        let Ok(metadata) = fs::symlink_metadata(&current) else {
+            continue;
+        };
+        if metadata.is_file() {
+            files.insert(current);
+        } else if metadata.is_dir() {
+            if let Ok(entries) = fs::read_dir(&current) {
+                pending.extend(entries.flatten().map(|entry| entry.path()));
+            }
+        }
+    }
+    files
+}
+
+fn dir_usage_bytes(dir: &Path, payload: &HashSet<PathBuf>) -> u64 {
+    let mut total = 0u64;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() && !payload.contains(&entry.path()) {
+                if let Ok(metadata) = entry.metadata() {
+                    total = total.saturating_add(metadata.len());
+                }
+            }
+        }
+    }
+    total
+}
+
+/// 插件启动时统计用量，目录遍历放在阻塞线程中，之后的写入只做计数
+pub(crate) async fn seed_storage_usage(dir: PathBuf, payload: Vec<String>) {
+    if dir.as_os_str().is_empty() {
+        return;
+    }
+    let walk_dir = dir.clone();
+    let scanned = tokio::task::spawn_blocking(move || {
+        let payload = resolve_payload(&walk_dir, &payload);
+        let used_bytes = dir_usage_bytes(&walk_dir, &payload);
+        (used_bytes, payload)
+    })
+    .await;
+    let (used_bytes, payload) = match scanned {
+        Ok(scanned) => scanned,
+        Err(err) => {
+            log::error!("[pluginsystem] storage usage task failed: {err}");
+            return;
+        }
+    };
+    STORAGE_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .insert(
+            dir,
+            StorageUsage {
+                used_bytes,
+                scanned_at: Some(Instant::now()),
+                payload: Arc::new(payload),
+            },
+        );
+}
+
+enum UsageLookup {
+    Current(u64),
+    // 需要重新统计，附带要跳过的安装文件
+    Rescan(Arc<HashSet<PathBuf>>),
+}
+
+/// 读取缓存的用量；缓存过期时标记为正在统计，避免多个写入同时遍历目录
+fn lookup_usage(dir: &Path, needs_rescan: impl Fn(&StorageUsage) -> bool) -> UsageLookup {
+    let mut guard = STORAGE_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    match guard.get_mut(dir) {
+        Some(usage) if !needs_rescan(&*usage) => UsageLookup::Current(usage.used_bytes),
+        Some(usage) => {
+            usage.scanned_at = Some(Instant::now());
+            UsageLookup::Rescan(Arc::clone(&usage.payload))
+        }
+        // 未在启动时统计过的目录（例如插件尚未启动）无法区分安装文件
+        None => UsageLookup::Rescan(Arc::new(HashSet::new())),
+    }
+}
+
+/// 在锁外统计目录用量后写回缓存
+fn rescan_usage(dir: &Path, payload: Arc<HashSet<PathBuf>>) -> u64 {
+    let used_bytes = dir_usage_bytes(dir, &payload);
+    STORAGE_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .insert(
+            dir.to_path_buf(),
+            StorageUsage {
+                used_bytes,
+                scanned_at: Some(Instant::now()),
+                payload,
+            },
+        );
+    used_bytes
+}
+
+async fn storage_usage_bytes(dir: PathBuf) -> u64 {
+    let payload = match lookup_usage(&dir, |usage| usage.scanned_at.is_none()) {
+        UsageLookup::Current(used_bytes) => return used_bytes,
+        UsageLookup::Rescan(payload) => payload,
+    };
+    match tokio::task::spawn_blocking(move || rescan_usage(&dir, payload)).await {
+        Ok(used_bytes) => used_bytes,
+        Err(err) => {
+            log::error!("[pluginsystem] storage usage task failed: {err}");
+            0
+        }
+    }
+}
+
+/// 插件通过 WASI 写入前计入用量，超出配额时返回 `false` 且不计入。
+/// 目录遍历只在缓存失效或即将超出配额时进行，且不持有全局锁
+pub(crate) fn charge_storage_write(dir: &Path, bytes: u64) -> bool {
+    // 从内存加载的测试插件没有插件目录
+    if bytes == 0 || dir.as_os_str().is_empty() {
+        return true;
+    }
+    let quota = storage_quota_bytes();
+    let lookup = lookup_usage(dir, |usage| match usage.scanned_at {
+        None => true,
+        // 按写入字节累加的用量会高估覆盖写，超出配额前重新统计一次
+        Some(scanned_at) => {
+            usage.used_bytes.saturating_add(bytes) > quota
+                && scanned_at.elapsed() >= STORAGE_RESCAN_MIN_INTERVAL
+        }
+    });
+    if let UsageLookup::Rescan(payload) = lookup {
+        rescan_usage(dir, payload);
+    }
+
+    let mut guard = STORAGE_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let Some(usage) = guard.get_mut(dir) else {
+        return true;
+    };
+    if usage.used_bytes.saturating_add(bytes) > quota {
+        return false;
+    }
+    usage.used_bytes += bytes;
+    true
+}
+
+/// 宿主侧写入或替换插件目录后调用，下一次使用时重新统计用量
+pub(crate) fn invalidate_storage_usage(dir: &Path) {
+    let mut guard = STORAGE_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if let Some(usage) = guard.get_mut(dir) {
+        usage.scanned_at = None;
+    }
+}
+
+/// 插件被移除后丢弃其用量记录
+pub(crate) fn forget_storage_usage(dir: &Path) {
+    let mut guard = STORAGE_USAGE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    guard.remove(dir);
+}
+
+impl psys_host::storage::Host for PluginCtx {}
+
+impl psys_host::storage::HostWithStore for PluginCtx {
+    fn quota<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<Output = FutureReader<psys_host::storage::QuotaInfo>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "storage.quota");
+        let instance = accessor.instance();
+        let plugin_root = accessor.with(|mut access| access.get().plugin_root().clone());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let used_bytes = storage_usage_bytes(plugin_root).await;
+                    Ok::<psys_host::storage::QuotaInfo, Error>(psys_host::storage::QuotaInfo {
+                        limit_bytes: storage_quota_bytes(),
+                        used_bytes,
+                    })
+                }),
+            )
+        });
+        async move { future }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{
+        charge_storage_write, forget_storage_usage, invalidate_storage_usage, seed_storage_usage,
+        storage_quota_bytes, storage_usage_bytes,
+    };
+    use crate::test_support::temp_root;
+
+    #[test]
+    fn writes_are_charged_against_the_quota() {
+        let temp = temp_root();
+        let root = temp.path().to_path_buf();
+        fs::write(root.join("existing.bin"), [0u8; 16]).unwrap();
+
+        let remaining = storage_quota_bytes() - 16;
+        assert!(charge_storage_write(&root, remaining));
+        assert!(!charge_storage_write(&root, 1));
+        assert!(charge_storage_write(&root, 0));
+
+        // 宿主侧删除文件后失效缓存，下一次写入按磁盘上的实际用量计算
+        fs::remove_file(root.join("existing.bin")).unwrap();
+        invalidate_storage_usage(&root);
+        assert!(charge_storage_write(&root, 16));
+
+        invalidate_storage_usage(&root);
+    }
+
+    #[tokio::test]
+    async fn install_payload_is_not_counted() {
+        let temp = temp_root();
+        let root = temp.path().to_path_buf();
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::write(root.join("manifest.json"), [0u8; 8]).unwrap();
+        fs::write(root.join("plugin.wasm"), [0u8; 64]).unwrap();
+        fs::write(root.join("assets").join("icon.png"), [0u8; 32]).unwrap();
+        fs::write(root.join("data.bin"), [0u8; 4]).unwrap();
+
+        let payload = vec![
+            "manifest.json".to_string(),
+            "./plugin.wasm".to_string(),
+            "assets".to_string(),
+        ];
+        seed_storage_usage(root.clone(), payload).await;
+        assert_eq!(storage_usage_bytes(root.clone()).await, 4);
+
+        // 宿主改动目录后重新统计，仍然跳过安装文件
+        fs::write(root.join("copied.bin"), [0u8; 6]).unwrap();
+        invalidate_storage_usage(&root);
+        assert_eq!(storage_usage_bytes(root.clone()).await, 10);
+
+        forget_storage_usage(&root);
+    }
+}
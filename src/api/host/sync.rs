@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc, Mutex as StdMutex,
+    atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::Error;
+use once_cell::sync::Lazy;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use wasmtime::component::{Accessor, FutureReader, Resource};
+
+use crate::bindings::astrobox::psys_host;
+
+use super::{HostCallSpan, HostString, PluginCtx};
+
+/// 插件持有的跨插件锁句柄，句柄被丢弃时释放锁
+pub struct LockHandle {
+    id: u64,
+}
+
+struct HeldLock {
+    owner: String,
+    name: String,
+    _guard: OwnedMutexGuard<()>,
+}
+
+static NEXT_LOCK_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
+// 锁名 -> 互斥量，所有插件共享
+static LOCKS: Lazy<StdMutex<HashMap<String, Arc<Mutex<()>>>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+// 句柄 id -> 已持有的锁；守卫放在宿主侧，插件停用时可以强制释放
+static HELD_LOCKS: Lazy<StdMutex<HashMap<u64, HeldLock>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+fn named_lock(name: &str) -> Arc<Mutex<()>> {
+    let mut locks = LOCKS.lock().unwrap_or_else(|poison| poison.into_inner());
+    Arc::clone(locks.entry(name.to_string()).or_default())
+}
+
+// 没有持有者和等待者的锁只剩注册表中的引用，可以移除
+fn prune_idle_locks() {
+    LOCKS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .retain(|_, lock| Arc::strong_count(lock) > 1);
+}
+
+fn release_handle(id: u64) {
+    let released = HELD_LOCKS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(&id);
+    if released.is_some() {
+        drop(released);
+        prune_idle_locks();
+    }
+}
+
+/// 插件停用、卸载或重启时释放它持有的全部锁，避免其他插件永久等待
+pub(crate) fn release_plugin_locks(owner: &str) {
+    let released = {
+        let mut held = HELD_LOCKS
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        let ids = held
+            .iter()
+            .filter(|(_, lock)| lock.owner == owner)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        ids.into_iter()
+            .filter_map(|id| held.remove(&id))
+            .collect::<Vec<_>>()
+    };
+    if released.is_empty() {
+        return;
+    }
+    for lock in &released {
+        log::info!(
+            "[plugin:{}] released lock '{}' on teardown",
+            lock.owner,
+            lock.name
+        );
+    }
+    drop(released);
+    prune_idle_locks();
+}
+
+async fn acquire_lock(owner: String, name: String, id: u64) {
+    let guard = named_lock(&name).lock_owned().await;
+    HELD_LOCKS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .insert(
+            id,
+            HeldLock {
+                owner,
+                name,
+                _guard: guard,
+            },
+        );
+}
+
+impl psys_host::sync::Host for PluginCtx {}
+
+impl psys_host::sync::HostWithStore for PluginCtx {
+    fn acquire<T>(
+        accessor: &Accessor<T, Self>,
+        lock_name: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<Resource<LockHandle>>> + Send {
+        let span = HostCallSpan::new(accessor, "sync.acquire");
+        let instance = accessor.instance();
+        let future = accessor.with(|mut access| {
+            let id = NEXT_LOCK_HANDLE_ID.fetch_add(1, Ordering::Relaxed);
+            let (plugin_name, handle) = {
+                let ctx = access.get();
+                (
+                    ctx.plugin_name().to_string(),
+                    ctx.table.push(LockHandle { id }),
+                )
+            };
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let handle = handle?;
+                    acquire_lock(plugin_name, lock_name.to_string(), id).await;
+                    Ok::<Resource<LockHandle>, Error>(handle)
+                }),
+            )
+        });
+        async move { future }
+    }
+}
+
+impl psys_host::sync::HostLockHandle for PluginCtx {
+    fn drop(&mut self, rep: Resource<LockHandle>) -> wasmtime::Result<()> {
+        let handle = self.table.delete(rep)?;
+        release_handle(handle.id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{acquire_lock, release_handle, release_plugin_locks};
+
+    #[tokio::test]
+    async fn lock_is_exclusive_until_released() {
+        acquire_lock(
+            "first".to_string(),
+            "sync-test/exclusive".to_string(),
+            1_001,
+        )
+        .await;
+
+        let waiting = tokio::spawn(acquire_lock(
+            "second".to_string(),
+            "sync-test/exclusive".to_string(),
+            1_002,
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        release_handle(1_001);
+        tokio::time::timeout(Duration::from_millis(100), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        release_handle(1_002);
+    }
+
+    #[tokio::test]
+    async fn teardown_releases_owner_locks() {
+        acquire_lock(
+            "crashed".to_string(),
+            "sync-test/teardown".to_string(),
+            2_001,
+        )
+        .await;
+
+        let waiting = tokio::spawn(acquire_lock(
+            "survivor".to_string(),
+            "sync-test/teardown".to_string(),
+            2_002,
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        release_plugin_locks("crashed");
+        tokio::time::timeout(Duration::from_millis(100), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        release_plugin_locks("survivor");
+    }
+}
@@ -8,7 +8,7 @@ use log::error;
 use serde_json::json;
 use wasmtime::component::{Accessor, FutureReader};
 
-use super::{HostString, HostVec, PluginCtx, permission::check_permission_declared};
+use super::{HostCallSpan, HostString, HostVec, PluginCtx, permission::check_permission_declared};
 
 impl psys_host::thirdpartyapp::Host for PluginCtx {}
 
@@ -19,40 +19,45 @@ impl psys_host::thirdpartyapp::HostWithStore for PluginCtx {
         app_info: psys_host::thirdpartyapp::AppInfo,
         page_name: HostString,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "thirdpartyapp.launch_qa");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let addr = addr.to_string();
-                let page_name = page_name.to_string();
-                let package_name = app_info.package_name.clone().to_string();
-
-                let params = json!({
-                    "plugin": plugin_name,
-                    "addr": addr.clone(),
-                    "pkgName": package_name,
-                });
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "thirdpartyapp",
-                    params,
-                )
-                .await
-                {
-                    return Ok::<core::result::Result<(), ()>, Error>(Err(()));
-                }
-
-                match launch_qa_impl(addr, app_info, page_name).await {
-                    Ok(()) => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
-                    Err(err) => {
-                        error!("Failed to launch third-party app: {err:?}");
-                        Ok::<core::result::Result<(), ()>, Error>(Err(()))
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = addr.to_string();
+                    let page_name = page_name.to_string();
+                    let package_name = app_info.package_name.clone().to_string();
+
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": addr.clone(),
+                        "pkgName": package_name,
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "thirdpartyapp",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
                     }
-                }
-            })
+
+                    match launch_qa_impl(addr, app_info, page_name).await {
+                        Ok(()) => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
+                        Err(err) => {
+                            error!("Failed to launch third-party app: {err:?}");
+                            Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                        }
+                    }
+                }),
+            )
         });
         async move { future }
     }
@@ -63,44 +68,100 @@ impl psys_host::thirdpartyapp::HostWithStore for PluginCtx {
     ) -> impl core::future::Future<
         Output = FutureReader<core::result::Result<HostVec<psys_host::thirdpartyapp::AppInfo>, ()>>,
     > + Send {
+        let span = HostCallSpan::new(accessor, "thirdpartyapp.get_thirdparty_app_list");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let addr = addr.to_string();
-                let params = json!({
-                    "plugin": plugin_name,
-                    "addr": addr.clone(),
-                });
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "thirdpartyapp",
-                    params,
-                )
-                .await
-                {
-                    return Ok::<
-                        core::result::Result<HostVec<psys_host::thirdpartyapp::AppInfo>, ()>,
-                        Error,
-                    >(Err(()));
-                }
-                match get_thirdparty_app_list_impl(addr).await {
-                    Ok(list) => Ok::<
-                        core::result::Result<HostVec<psys_host::thirdpartyapp::AppInfo>, ()>,
-                        Error,
-                    >(Ok(list)),
-                    Err(err) => {
-                        error!("Failed to fetch third-party app list: {err:?}");
-                        Ok::<
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = addr.to_string();
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": addr.clone(),
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "thirdpartyapp",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok::<
                             core::result::Result<HostVec<psys_host::thirdpartyapp::AppInfo>, ()>,
                             Error,
-                        >(Err(()))
+                        >(Err(()));
                     }
-                }
-            })
+                    match get_thirdparty_app_list_impl(addr).await {
+                        Ok(list) => Ok::<
+                            core::result::Result<HostVec<psys_host::thirdpartyapp::AppInfo>, ()>,
+                            Error,
+                        >(Ok(list)),
+                        Err(err) => {
+                            error!("Failed to fetch third-party app list: {err:?}");
+                            Ok::<
+                                core::result::Result<
+                                    HostVec<psys_host::thirdpartyapp::AppInfo>,
+                                    (),
+                                >,
+                                Error,
+                            >(Err(()))
+                        }
+                    }
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn is_installed<T>(
+        accessor: &Accessor<T, Self>,
+        addr: HostString,
+        pkg_name: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<bool>> + Send {
+        let span = HostCallSpan::new(accessor, "thirdpartyapp.is_installed");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = addr.to_string();
+                    let pkg_name = pkg_name.to_string();
+                    if pkg_name.is_empty() {
+                        return Ok::<bool, Error>(false);
+                    }
+
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": addr.clone(),
+                        "pkgName": pkg_name.clone(),
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "thirdpartyapp",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok::<bool, Error>(false);
+                    }
+
+                    // 设备离线或应用不存在都视为未安装
+                    let installed = resolve_app_info_from_component(&addr, &pkg_name)
+                        .await
+                        .is_ok();
+                    Ok::<bool, Error>(installed)
+                }),
+            )
         });
         async move { future }
     }
@@ -1,14 +1,21 @@
 use crate::bindings::{astrobox::psys_host, exports::astrobox::psys_plugin};
+use crate::plugin::PluginRegisterState;
 use anyhow::Error;
+use chrono::Utc;
 use serde_json::json;
+use std::sync::Arc;
 use std::time::Duration;
 use wasmtime::component::{Accessor, FutureReader};
 
-use super::{HostString, PluginCtx};
+use super::{HostCallSpan, HostString, PluginCtx};
+
+// 宿主没有空闲回调，以约一帧的时长近似下一个空闲时刻
+const IDLE_TICK: Duration = Duration::from_millis(16);
 
 enum TimerKind {
     Timeout,
     Interval,
+    Alarm,
 }
 
 impl TimerKind {
@@ -16,6 +23,7 @@ impl TimerKind {
         match self {
             Self::Timeout => "timeout",
             Self::Interval => "interval",
+            Self::Alarm => "alarm",
         }
     }
 }
@@ -79,6 +87,34 @@ async fn dispatch_timer_event(plugin_name: String, timer_id: u64, payload: Strin
     }
 }
 
+fn spawn_one_shot_timer(
+    register_state: Arc<PluginRegisterState>,
+    plugin_name: String,
+    delay_ms: u64,
+    kind: TimerKind,
+    payload: String,
+) -> u64 {
+    let timer_id = register_state.next_timer_id();
+    let timer_state = register_state.clone();
+    let handle = tokio::spawn(async move {
+        tokio::task::yield_now().await;
+        let delay_ms = delay_ms.max(1);
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        crate::suspension::wait_until_plugin_resumed(&plugin_name).await;
+        let timer_payload = build_timer_payload(timer_id, kind, payload);
+        dispatch_timer_event(plugin_name, timer_id, timer_payload).await;
+        timer_state.remove_timer(timer_id);
+    });
+    register_state.insert_timer(timer_id, handle);
+    timer_id
+}
+
+/// 计算从当前时间到目标时间（Unix 毫秒时间戳）的延迟，目标已过去时返回 `None`
+fn alarm_delay_ms(epoch_ms: u64, now_ms: i64) -> Option<u64> {
+    let now_ms = u64::try_from(now_ms).ok()?;
+    epoch_ms.checked_sub(now_ms).filter(|delay| *delay > 0)
+}
+
 impl psys_host::timer::Host for PluginCtx {}
 
 impl psys_host::timer::HostWithStore for PluginCtx {
@@ -87,26 +123,25 @@ impl psys_host::timer::HostWithStore for PluginCtx {
         delay_ms: u64,
         payload: HostString,
     ) -> impl core::future::Future<Output = FutureReader<u64>> + Send {
+        let span = HostCallSpan::new(accessor, "timer.set_timeout");
         let instance = accessor.instance();
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let register_state = accessor.with(|mut access| access.get().register_state());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let timer_id = register_state.next_timer_id();
-                let payload = payload.to_string();
-                let timer_state = register_state.clone();
-                let plugin_name = plugin_name.clone();
-                let handle = tokio::spawn(async move {
-                    tokio::task::yield_now().await;
-                    let delay_ms = delay_ms.max(1);
-                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-                    let timer_payload = build_timer_payload(timer_id, TimerKind::Timeout, payload);
-                    dispatch_timer_event(plugin_name, timer_id, timer_payload).await;
-                    timer_state.remove_timer(timer_id);
-                });
-                register_state.insert_timer(timer_id, handle);
-                Ok::<u64, Error>(timer_id)
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let timer_id = spawn_one_shot_timer(
+                        register_state,
+                        plugin_name,
+                        delay_ms,
+                        TimerKind::Timeout,
+                        payload.to_string(),
+                    );
+                    Ok::<u64, Error>(timer_id)
+                }),
+            )
         });
         async move { future }
     }
@@ -116,29 +151,76 @@ impl psys_host::timer::HostWithStore for PluginCtx {
         interval_ms: u64,
         payload: HostString,
     ) -> impl core::future::Future<Output = FutureReader<u64>> + Send {
+        let span = HostCallSpan::new(accessor, "timer.set_interval");
         let instance = accessor.instance();
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let register_state = accessor.with(|mut access| access.get().register_state());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let timer_id = register_state.next_timer_id();
-                let payload = payload.to_string();
-                let plugin_name = plugin_name.clone();
-                let handle = tokio::spawn(async move {
-                    tokio::task::yield_now().await;
-                    let interval_ms = interval_ms.max(1);
-                    let mut ticker = tokio::time::interval(Duration::from_millis(interval_ms));
-                    ticker.tick().await;
-                    loop {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let timer_id = register_state.next_timer_id();
+                    let payload = payload.to_string();
+                    let plugin_name = plugin_name.clone();
+                    let handle = tokio::spawn(async move {
+                        tokio::task::yield_now().await;
+                        let interval_ms = interval_ms.max(1);
+                        let mut ticker = tokio::time::interval(Duration::from_millis(interval_ms));
                         ticker.tick().await;
-                        let timer_payload =
-                            build_timer_payload(timer_id, TimerKind::Interval, payload.clone());
-                        dispatch_timer_event(plugin_name.clone(), timer_id, timer_payload).await;
-                    }
-                });
-                register_state.insert_timer(timer_id, handle);
-                Ok::<u64, Error>(timer_id)
-            })
+                        loop {
+                            ticker.tick().await;
+                            if crate::suspension::is_plugin_suspended(&plugin_name) {
+                                continue;
+                            }
+                            let timer_payload =
+                                build_timer_payload(timer_id, TimerKind::Interval, payload.clone());
+                            dispatch_timer_event(plugin_name.clone(), timer_id, timer_payload)
+                                .await;
+                        }
+                    });
+                    register_state.insert_timer(timer_id, handle);
+                    Ok::<u64, Error>(timer_id)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn set_alarm<T>(
+        accessor: &Accessor<T, Self>,
+        epoch_ms: u64,
+        payload: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<u64, ()>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "timer.set_alarm");
+        let instance = accessor.instance();
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let register_state = accessor.with(|mut access| access.get().register_state());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let Some(delay_ms) = alarm_delay_ms(epoch_ms, Utc::now().timestamp_millis())
+                    else {
+                        log::warn!(
+                            "[plugin:{}] set_alarm rejected: target time {} is in the past",
+                            plugin_name,
+                            epoch_ms
+                        );
+                        return Ok::<core::result::Result<u64, ()>, Error>(Err(()));
+                    };
+                    let timer_id = spawn_one_shot_timer(
+                        register_state,
+                        plugin_name,
+                        delay_ms,
+                        TimerKind::Alarm,
+                        payload.to_string(),
+                    );
+                    Ok::<core::result::Result<u64, ()>, Error>(Ok(timer_id))
+                }),
+            )
         });
         async move { future }
     }
@@ -147,13 +229,43 @@ impl psys_host::timer::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
         timer_id: u64,
     ) -> impl core::future::Future<Output = FutureReader<()>> + Send {
+        let span = HostCallSpan::new(accessor, "timer.clear_timer");
         let instance = accessor.instance();
         let register_state = accessor.with(|mut access| access.get().register_state());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                register_state.clear_timer(timer_id);
-                Ok::<(), Error>(())
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    register_state.clear_timer(timer_id);
+                    Ok::<(), Error>(())
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn next_idle<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<Output = FutureReader<()>> + Send {
+        let span = HostCallSpan::new(accessor, "timer.next_idle");
+        let instance = accessor.instance();
+        let register_state = accessor.with(|mut access| access.get().register_state());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    // 插件停止时立即返回，不让分片处理的插件卡在等待中
+                    let mut cancel = register_state.idle_cancel_signal();
+                    tokio::task::yield_now().await;
+                    tokio::select! {
+                        _ = tokio::time::sleep(IDLE_TICK) => {}
+                        _ = cancel.wait_for(|cancelled| *cancelled) => {}
+                    }
+                    Ok::<(), Error>(())
+                }),
+            )
         });
         async move { future }
     }
@@ -1,22 +1,50 @@
 use crate::bindings::astrobox::psys_host;
 use crate::transport_runtime;
 use anyhow::Error;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
 use corelib::device::xiaomi::{
     XiaomiDevice,
     packet::{cipher, v2::layer2::L2Channel},
 };
+use frontbridge::invoke_frontend;
 use pb::xiaomi::protocol::WearPacket;
 use prost::Message;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use wasmtime::component::{Accessor, FutureReader};
 
 use super::{
-    HostString, HostVec, PluginCtx,
+    HostCallSpan, HostString, HostVec, PluginCtx,
     permission::{check_permission_declared, resolve_device_name},
 };
 
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+// 协议中没有回显包，ping 借用系统服务的电量查询（type=2 / id=1）：设备总会以相同 type/id
+// 应答且负载很小。等待方标记为宿主所有，插件的同类请求进行中时不测量，应答也不转发给插件
+const PING_PACKET_TYPE: i32 = 2;
+const PING_PACKET_ID: u32 = 1;
+
+/// 注册自定义传输协议并收发原始帧的特权权限
+pub(crate) const CUSTOM_PROTOCOL_PERMISSION: &str = "transport.custom_protocol";
+// 自定义协议的链路由前端维护，宿主只转发帧
+const FRONT_TRANSPORT_SEND_RAW_METHOD: &str = "host/transport/send_raw";
+const RAW_FRAME_MAX_LEN: usize = 64 * 1024;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SendRawPayload {
+    plugin: String,
+    addr: String,
+    protocol: String,
+    payload_base64: String,
+}
+
+#[derive(Deserialize)]
+struct SendRawAck {
+    success: bool,
+}
 
 fn decode_pb_packet(data: &[u8]) -> Result<WearPacket, ()> {
     WearPacket::decode(data).map_err(|err| {
@@ -50,7 +78,45 @@ async fn send_xiaomi_pb_packet(device_addr: &str, packet: WearPacket) -> Result<
     .await
 }
 
+/// 发送数据包并等待 type/id 相同的应答包，超时或发送失败返回 `Err`
+fn packet_waiter_key(packet: &WearPacket) -> (u32, Option<u32>, Option<u32>) {
+    (
+        L2Channel::Pb as u32,
+        u32::try_from(packet.r#type).ok(),
+        Some(packet.id),
+    )
+}
+
+async fn request_xiaomi_pb_packet(
+    device_addr: &str,
+    packet: WearPacket,
+    rx: transport_runtime::RequestWaiter,
+    timeout: Duration,
+) -> Result<Vec<u8>, ()> {
+    send_xiaomi_pb_packet(device_addr, packet).await?;
+
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(payload)) => Ok(payload),
+        Ok(Err(_)) => Err(()),
+        Err(_) => {
+            log::warn!(
+                "[pluginsystem] transport request timed out for {}",
+                device_addr
+            );
+            Err(())
+        }
+    }
+}
+
 impl psys_host::transport::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "transport.to_json")
+        )
+    )]
     fn to_json(
         &mut self,
         protocol: psys_host::transport::Protocol,
@@ -71,6 +137,14 @@ impl psys_host::transport::Host for PluginCtx {
         }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "transport.from_json")
+        )
+    )]
     fn from_json(
         &mut self,
         protocol: psys_host::transport::Protocol,
@@ -96,14 +170,22 @@ impl psys_host::transport::HostWithStore for PluginCtx {
         accessor: &Accessor<T, Self>,
         device_addr: HostString,
         data: HostVec<u8>,
-    ) -> impl core::future::Future<Output = FutureReader<()>> + Send {
+    ) -> impl core::future::Future<Output = FutureReader<Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "transport.send");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
+            FutureReader::new(instance, &mut access, span.instrument(async move {
                 let device_addr = device_addr.to_string();
+                if crate::suspension::is_plugin_suspended(&plugin_name) {
+                    log::warn!(
+                        "[plugin:{}] transport.send rejected: plugin is suspended",
+                        plugin_name
+                    );
+                    return Ok::<Result<(), ()>, Error>(Err(()));
+                }
                 let data = data.as_slice().to_vec();
                 let device_name = resolve_device_name(&device_addr).await;
                 let params = json!({
@@ -114,22 +196,21 @@ impl psys_host::transport::HostWithStore for PluginCtx {
                 if !check_permission_declared(&app_handle, permissions.as_ref(), "request", params)
                     .await
                 {
-                    return Ok::<(), Error>(());
+                    return Ok::<Result<(), ()>, Error>(Err(()));
                 }
                 if !transport_protocol_supported(&device_addr).await {
                     log::warn!(
                         "[pluginsystem] transport.send only supports Xiaomi SARv2 devices for now: {}",
                         device_addr
                     );
-                    return Ok::<(), Error>(());
+                    return Ok::<Result<(), ()>, Error>(Err(()));
                 }
                 let packet = match decode_pb_packet(&data) {
                     Ok(packet) => packet,
-                    Err(()) => return Ok::<(), Error>(()),
+                    Err(()) => return Ok::<Result<(), ()>, Error>(Err(())),
                 };
-                let _ = send_xiaomi_pb_packet(&device_addr, packet).await;
-                Ok::<(), Error>(())
-            })
+                Ok::<Result<(), ()>, Error>(send_xiaomi_pb_packet(&device_addr, packet).await)
+            }))
         });
         async move { future }
     }
@@ -140,13 +221,21 @@ impl psys_host::transport::HostWithStore for PluginCtx {
         data: HostVec<u8>,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<HostVec<u8>, ()>>> + Send
     {
+        let span = HostCallSpan::new(accessor, "transport.request");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
+            FutureReader::new(instance, &mut access, span.instrument(async move {
                 let device_addr = device_addr.to_string();
+                if crate::suspension::is_plugin_suspended(&plugin_name) {
+                    log::warn!(
+                        "[plugin:{}] transport.request rejected: plugin is suspended",
+                        plugin_name
+                    );
+                    return Ok::<core::result::Result<HostVec<u8>, ()>, Error>(Err(()));
+                }
                 let data = data.as_slice().to_vec();
                 let device_name = resolve_device_name(&device_addr).await;
                 let params = json!({
@@ -171,33 +260,187 @@ impl psys_host::transport::HostWithStore for PluginCtx {
                     Ok(packet) => packet,
                     Err(()) => return Ok::<core::result::Result<HostVec<u8>, ()>, Error>(Err(())),
                 };
-                let protobuf_type_id = u32::try_from(packet.r#type).ok();
-                let protobuf_packet_id = Some(packet.id);
+                let (channel_id, protobuf_type_id, protobuf_packet_id) =
+                    packet_waiter_key(&packet);
                 let rx = transport_runtime::register_request_waiter(
                     device_addr.clone(),
-                    L2Channel::Pb as u32,
+                    channel_id,
                     protobuf_type_id,
                     protobuf_packet_id,
                 );
+                let response = request_xiaomi_pb_packet(&device_addr, packet, rx, REQUEST_TIMEOUT)
+                    .await
+                    .map(HostVec::from);
+                Ok::<core::result::Result<HostVec<u8>, ()>, Error>(response)
+            }))
+        });
+        async move { future }
+    }
 
-                if send_xiaomi_pb_packet(&device_addr, packet).await.is_err() {
-                    return Ok::<core::result::Result<HostVec<u8>, ()>, Error>(Err(()));
-                }
-
-                let response = match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
-                    Ok(Ok(payload)) => payload,
-                    Ok(Err(_)) => return Ok::<core::result::Result<HostVec<u8>, ()>, Error>(Err(())),
-                    Err(_) => {
+    fn ping<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+    ) -> impl core::future::Future<Output = FutureReader<Option<u32>>> + Send {
+        let span = HostCallSpan::new(accessor, "transport.ping");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let device_addr = device_addr.to_string();
+                    if crate::suspension::is_plugin_suspended(&plugin_name) {
                         log::warn!(
-                            "[pluginsystem] transport.request timed out for {}",
+                            "[plugin:{}] transport.ping rejected: plugin is suspended",
+                            plugin_name
+                        );
+                        return Ok::<Option<u32>, Error>(None);
+                    }
+                    let device_name = resolve_device_name(&device_addr).await;
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": device_addr.clone(),
+                        "deviceName": device_name,
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "request",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok::<Option<u32>, Error>(None);
+                    }
+                    // 设备离线或不是 SARv2 设备时无法测量
+                    if !transport_protocol_supported(&device_addr).await {
+                        return Ok::<Option<u32>, Error>(None);
+                    }
+
+                    let packet = WearPacket {
+                        r#type: PING_PACKET_TYPE,
+                        id: PING_PACKET_ID,
+                        ..Default::default()
+                    };
+                    let (channel_id, protobuf_type_id, protobuf_packet_id) =
+                        packet_waiter_key(&packet);
+                    let Some(rx) = transport_runtime::register_host_request_waiter(
+                        device_addr.clone(),
+                        channel_id,
+                        protobuf_type_id,
+                        protobuf_packet_id,
+                    ) else {
+                        log::debug!(
+                            "[plugin:{}] transport.ping skipped: a plugin request with the same type/id is pending on {}",
+                            plugin_name,
                             device_addr
                         );
-                        return Ok::<core::result::Result<HostVec<u8>, ()>, Error>(Err(()));
+                        return Ok::<Option<u32>, Error>(None);
+                    };
+                    let started = Instant::now();
+                    let rtt = request_xiaomi_pb_packet(&device_addr, packet, rx, PING_TIMEOUT)
+                        .await
+                        .ok()
+                        .map(|_| u32::try_from(started.elapsed().as_millis()).unwrap_or(u32::MAX));
+                    Ok::<Option<u32>, Error>(rtt)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn send_raw<T>(
+        accessor: &Accessor<T, Self>,
+        device_addr: HostString,
+        protocol_name: HostString,
+        data: HostVec<u8>,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "transport.send_raw");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let device_addr = device_addr.to_string();
+                    let protocol = protocol_name.to_string();
+                    if crate::suspension::is_plugin_suspended(&plugin_name) {
+                        log::warn!(
+                            "[plugin:{}] transport.send_raw rejected: plugin is suspended",
+                            plugin_name
+                        );
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
+                    // 只能发送自己注册的协议
+                    if transport_runtime::custom_protocol_owner(&protocol).as_deref()
+                        != Some(plugin_name.as_str())
+                    {
+                        log::warn!(
+                            "[plugin:{}] transport.send_raw rejected: protocol '{}' is not registered by this plugin",
+                            plugin_name,
+                            protocol
+                        );
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
+                    if data.len() > RAW_FRAME_MAX_LEN {
+                        log::warn!(
+                            "[plugin:{}] transport.send_raw rejected: frame exceeds {} bytes",
+                            plugin_name,
+                            RAW_FRAME_MAX_LEN
+                        );
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": device_addr.clone(),
+                        "protocol": protocol.clone(),
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        CUSTOM_PROTOCOL_PERMISSION,
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
                     }
-                };
 
-                Ok::<core::result::Result<HostVec<u8>, ()>, Error>(Ok(HostVec::from(response)))
-            })
+                    let payload = SendRawPayload {
+                        plugin: plugin_name.clone(),
+                        addr: device_addr.clone(),
+                        protocol,
+                        payload_base64: BASE64_STANDARD.encode(data.as_slice()),
+                    };
+                    match invoke_frontend::<SendRawAck, _>(
+                        &app_handle,
+                        FRONT_TRANSPORT_SEND_RAW_METHOD,
+                        payload,
+                    )
+                    .await
+                    {
+                        Ok(ack) if ack.success => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
+                        Ok(_) => {
+                            log::warn!(
+                                "[plugin:{}] transport.send_raw: no link for device {}",
+                                plugin_name,
+                                device_addr
+                            );
+                            Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                        }
+                        Err(err) => {
+                            log::warn!("[plugin:{}] transport.send_raw failed: {err}", plugin_name);
+                            Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                        }
+                    }
+                }),
+            )
         });
         async move { future }
     }
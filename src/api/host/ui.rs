@@ -1,14 +1,375 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex as StdMutex;
 
+use anyhow::Error;
+use once_cell::sync::Lazy;
 use rand::Rng;
 use rand::distr::Alphanumeric;
 use serde::Serialize;
-use tauri::Emitter;
-use wasmtime::component::Resource;
+use tauri::{AppHandle, Emitter, Manager};
+use wasmtime::component::{Accessor, FutureReader, Resource};
 
 use crate::bindings::astrobox::psys_host;
+use crate::plugin::UiRenderThrottleDecision;
 
-use super::PluginCtx;
+use super::{HostCallSpan, PluginCtx, permission::check_permission_declared};
+
+const PLUGIN_UI_RENDER_EVENT: &str = "plugin-ui-render";
+const PLUGIN_UI_RENDER_BATCH_EVENT: &str = "plugin-ui-render-batch";
+const PLUGIN_TASK_PROGRESS_EVENT: &str = "plugin-task-progress";
+const PLUGIN_REQUEST_ATTENTION_EVENT: &str = "plugin-request-attention";
+const PLUGIN_CARD_UPDATE_EVENT: &str = "plugin-card-update";
+const MAIN_WINDOW_LABEL: &str = "main";
+
+// 跨重载保留的界面状态：最近一次渲染的元素树，以及前端保存的输入内容、滚动位置等
+#[derive(Default)]
+struct PreservedUiState {
+    renders: BTreeMap<String, String>,
+    values: Option<String>,
+}
+
+static PRESERVED_UI_STATE: Lazy<StdMutex<HashMap<String, PreservedUiState>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+fn remember_render(plugin_name: &str, id: &str, ui: &str) {
+    let mut guard = PRESERVED_UI_STATE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    guard
+        .entry(plugin_name.to_string())
+        .or_default()
+        .renders
+        .insert(id.to_string(), ui.to_string());
+}
+
+/// 最近一次渲染的元素树中所有元素的 id（即 `Element::new` 生成的 id），尚未渲染时为空
+fn active_element_ids(plugin_name: &str) -> Vec<String> {
+    let guard = PRESERVED_UI_STATE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let Some(state) = guard.get(plugin_name) else {
+        return Vec::new();
+    };
+    let mut ids = BTreeSet::new();
+    for ui in state.renders.values() {
+        if let Ok(tree) = serde_json::from_str::<serde_json::Value>(ui) {
+            collect_element_ids(&tree, &mut ids);
+        }
+    }
+    ids.into_iter().collect()
+}
+
+fn collect_element_ids(element: &serde_json::Value, ids: &mut BTreeSet<String>) {
+    if let Some(id) = element.get("id").and_then(serde_json::Value::as_str) {
+        ids.insert(id.to_string());
+    }
+    for child in element
+        .get("children")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        collect_element_ids(child, ids);
+    }
+}
+
+/// 卡片内容：`Element` 类型卡片为元素树 JSON，`Text` 类型卡片为文本
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum CardContent {
+    Element(String),
+    Text(String),
+}
+
+/// `plugin-card-update` 事件负载，`content` 为空表示卡片内容已清除
+#[derive(Debug, Clone, Serialize)]
+pub struct CardUpdate {
+    pub name: String,
+    pub id: String,
+    pub content: Option<CardContent>,
+}
+
+// 插件名 -> 卡片 id -> 最近一次推送的内容，前端挂载卡片区域时可一次取回
+static CARD_CONTENTS: Lazy<StdMutex<HashMap<String, BTreeMap<String, CardContent>>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+fn emit_card_update(
+    app_handle: &AppHandle,
+    plugin_name: &str,
+    id: &str,
+    content: Option<CardContent>,
+) {
+    let update = CardUpdate {
+        name: plugin_name.to_string(),
+        id: id.to_string(),
+        content,
+    };
+    if let Err(err) = app_handle.emit(PLUGIN_CARD_UPDATE_EVENT, &update) {
+        log::error!("[plugin:{}] failed to emit card update: {err}", plugin_name);
+    }
+}
+
+fn set_card_content(app_handle: &AppHandle, plugin_name: &str, id: &str, content: CardContent) {
+    CARD_CONTENTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .entry(plugin_name.to_string())
+        .or_default()
+        .insert(id.to_string(), content.clone());
+    emit_card_update(app_handle, plugin_name, id, Some(content));
+}
+
+/// 卡片被注销时清除其内容
+pub(crate) fn clear_card_content(app_handle: &AppHandle, plugin_name: &str, id: &str) {
+    let removed = CARD_CONTENTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .get_mut(plugin_name)
+        .and_then(|cards| cards.remove(id));
+    if removed.is_some() {
+        emit_card_update(app_handle, plugin_name, id, None);
+    }
+}
+
+/// 插件停止或重新加载时清除它的全部卡片内容
+pub(crate) fn release_card_contents(app_handle: &AppHandle, plugin_name: &str) {
+    let removed = CARD_CONTENTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(plugin_name);
+    for id in removed.into_iter().flat_map(|cards| cards.into_keys()) {
+        emit_card_update(app_handle, plugin_name, &id, None);
+    }
+}
+
+/// 所有插件卡片的当前内容，按插件名和卡片 id 排序
+pub fn card_contents() -> Vec<CardUpdate> {
+    let guard = CARD_CONTENTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let mut updates = guard
+        .iter()
+        .flat_map(|(name, cards)| {
+            cards.iter().map(|(id, content)| CardUpdate {
+                name: name.clone(),
+                id: id.clone(),
+                content: Some(content.clone()),
+            })
+        })
+        .collect::<Vec<_>>();
+    updates.sort_by(|left, right| (&left.name, &left.id).cmp(&(&right.name, &right.id)));
+    updates
+}
+
+/// 前端在插件重载前保存的界面值（JSON），由插件在 `on_load` 中通过 `restore_state` 取回
+pub(crate) fn save_frontend_ui_state(plugin_name: &str, values: String) {
+    let mut guard = PRESERVED_UI_STATE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    guard.entry(plugin_name.to_string()).or_default().values = Some(values);
+}
+
+// 前端保存的值只交付一次，渲染树保留到下一次渲染覆盖
+fn take_ui_state(plugin_name: &str) -> Option<psys_host::ui::UiState> {
+    let mut guard = PRESERVED_UI_STATE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let state = guard.get_mut(plugin_name)?;
+    if state.renders.is_empty() && state.values.is_none() {
+        return None;
+    }
+    Some(psys_host::ui::UiState {
+        renders: state
+            .renders
+            .iter()
+            .map(|(id, ui)| psys_host::ui::RenderedElement {
+                id: id.clone(),
+                ui: ui.clone(),
+            })
+            .collect(),
+        values: state.values.take(),
+    })
+}
+
+pub(crate) fn restore_ui_state(ctx: &PluginCtx) -> Option<psys_host::ui::UiState> {
+    take_ui_state(ctx.plugin_name())
+}
+
+/// 插件被移除时丢弃保留的界面状态
+pub(crate) fn forget_ui_state(plugin_name: &str) {
+    let mut guard = PRESERVED_UI_STATE
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    guard.remove(plugin_name);
+}
+
+/// 发送一次界面渲染；处于 `begin_batch` 与 `end_batch` 之间时先缓存，由批量事件统一发送；
+/// 插件设置了渲染帧率时，帧间隔内的渲染合并后在帧末以批量事件发送
+pub(crate) fn emit_ui_render(ctx: &PluginCtx, id: String, ui: String) {
+    remember_render(ctx.plugin_name(), &id, &ui);
+    let render = serde_json::json!({
+        "name": ctx.plugin_name(),
+        "id": id,
+        "ui": ui
+    });
+    let register_state = ctx.register_state();
+    let Some(render) = register_state.push_ui_render(render) else {
+        return;
+    };
+    match register_state.throttle_ui_render(&id, render) {
+        UiRenderThrottleDecision::EmitNow(render) => {
+            let _ = ctx.app_handle.emit(PLUGIN_UI_RENDER_EVENT, render);
+        }
+        UiRenderThrottleDecision::Deferred(delay) => {
+            let app_handle = ctx.app_handle();
+            let plugin_name = ctx.plugin_name().to_string();
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                let renders = register_state.take_pending_ui_renders();
+                emit_ui_render_batch(&app_handle, &plugin_name, renders);
+            });
+        }
+        UiRenderThrottleDecision::Coalesced => {}
+    }
+}
+
+pub(crate) fn emit_ui_render_batch(
+    app_handle: &AppHandle,
+    plugin_name: &str,
+    renders: Vec<serde_json::Value>,
+) {
+    if renders.is_empty() {
+        return;
+    }
+    let _ = app_handle.emit(
+        PLUGIN_UI_RENDER_BATCH_EVENT,
+        serde_json::json!({
+            "name": plugin_name,
+            "renders": renders
+        }),
+    );
+}
+
+/// 插件当前的后台任务进度，`percent` 为空表示不确定进度
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskProgress {
+    pub label: String,
+    pub percent: Option<u8>,
+}
+
+/// 上报插件后台任务进度：`percent` 为空表示不确定进度，达到 100 时视为完成并移除
+pub(crate) fn emit_task_progress(ctx: &PluginCtx, label: String, percent: Option<u8>) {
+    let percent = percent.map(|value| value.min(100));
+    if percent == Some(100) {
+        clear_task_progress(ctx);
+        return;
+    }
+    // 宿主按插件汇总进度，前端重新打开时可通过 plugin_task_progress 取回当前状态
+    ctx.register_state().set_task_progress(Some(TaskProgress {
+        label: label.clone(),
+        percent,
+    }));
+    let _ = ctx.app_handle.emit(
+        PLUGIN_TASK_PROGRESS_EVENT,
+        serde_json::json!({
+            "name": ctx.plugin_name(),
+            "label": label,
+            "percent": percent,
+            "cleared": false
+        }),
+    );
+}
+
+pub(crate) fn clear_task_progress(ctx: &PluginCtx) {
+    ctx.register_state().set_task_progress(None);
+    emit_task_progress_cleared(&ctx.app_handle, ctx.plugin_name());
+}
+
+pub(crate) fn emit_task_progress_cleared(app_handle: &AppHandle, plugin_name: &str) {
+    let _ = app_handle.emit(
+        PLUGIN_TASK_PROGRESS_EVENT,
+        serde_json::json!({
+            "name": plugin_name,
+            "cleared": true
+        }),
+    );
+}
+
+pub(crate) fn begin_ui_batch(ctx: &PluginCtx) {
+    ctx.register_state().begin_ui_render_batch();
+}
+
+pub(crate) fn end_ui_batch(ctx: &PluginCtx) {
+    if let Some(renders) = ctx.register_state().take_ui_render_batch() {
+        emit_ui_render_batch(&ctx.app_handle, ctx.plugin_name(), renders);
+    }
+}
+
+/// 宿主当前的明暗主题与强调色，供插件选择合适的 `bg`/`text_color`
+pub(crate) fn host_theme_info(app_handle: &AppHandle) -> psys_host::ui::ThemeInfo {
+    let theme = crate::theme::current_theme(app_handle);
+    psys_host::ui::ThemeInfo {
+        dark: theme.dark,
+        accent_color: theme.accent_color,
+    }
+}
+
+#[cfg(desktop)]
+fn focus_main_window(app_handle: &AppHandle) -> bool {
+    let window = app_handle
+        .get_webview_window(MAIN_WINDOW_LABEL)
+        .or_else(|| app_handle.webview_windows().into_values().next());
+    let Some(window) = window else {
+        return false;
+    };
+
+    let focused =
+        window.unminimize().is_ok() && window.show().is_ok() && window.set_focus().is_ok();
+    if !focused {
+        // 系统不允许抢占焦点时退化为任务栏/Dock 闪烁
+        let _ = window.request_user_attention(Some(tauri::UserAttentionType::Critical));
+    }
+    focused
+}
+
+#[cfg(not(desktop))]
+fn focus_main_window(_app_handle: &AppHandle) -> bool {
+    false
+}
+
+/// 将主窗口切到前台；无法切换时通知前端以应用内提示的方式提醒用户
+pub(crate) async fn request_foreground(
+    app_handle: &AppHandle,
+    permissions: &[String],
+    plugin_name: &str,
+) -> core::result::Result<(), ()> {
+    if !check_permission_declared(
+        app_handle,
+        permissions,
+        "foreground",
+        serde_json::json!({ "plugin": plugin_name }),
+    )
+    .await
+    {
+        return Err(());
+    }
+
+    let focused = focus_main_window(app_handle);
+    if !focused {
+        log::info!(
+            "[plugin:{}] request_foreground: focus unavailable, falling back to attention notice",
+            plugin_name
+        );
+    }
+    let _ = app_handle.emit(
+        PLUGIN_REQUEST_ATTENTION_EVENT,
+        serde_json::json!({
+            "name": plugin_name,
+            "focused": focused
+        }),
+    );
+    Ok(())
+}
 
 #[derive(Clone, Serialize)]
 pub struct Element {
@@ -138,6 +499,14 @@ fn return_owned_element(
 }
 
 impl psys_host::ui::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.render")
+        )
+    )]
     fn render(&mut self, id: String, element: Resource<Element>) -> wasmtime::Result<()> {
         let el = take_or_clone_element(self, element)?;
         let json = match serde_json::to_string(&el) {
@@ -151,18 +520,120 @@ impl psys_host::ui::Host for PluginCtx {
             }
         };
 
-        let _ = self.app_handle.emit(
-            "plugin-ui-render",
-            serde_json::json!({
-                "name": self.plugin_name(),
-                "id": id,
-                "ui": json
-            }),
-        );
+        emit_ui_render(self, id, json);
 
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.begin_batch")
+        )
+    )]
+    fn begin_batch(&mut self) -> wasmtime::Result<()> {
+        begin_ui_batch(self);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.end_batch")
+        )
+    )]
+    fn end_batch(&mut self) -> wasmtime::Result<()> {
+        end_ui_batch(self);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.set_render_fps")
+        )
+    )]
+    fn set_render_fps(&mut self, fps: u32) -> wasmtime::Result<()> {
+        self.register_state().set_ui_render_fps(fps);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.set_task_progress")
+        )
+    )]
+    fn set_task_progress(&mut self, label: String, percent: Option<u8>) -> wasmtime::Result<()> {
+        emit_task_progress(self, label, percent);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.clear_task_progress")
+        )
+    )]
+    fn clear_task_progress(&mut self) -> wasmtime::Result<()> {
+        clear_task_progress(self);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.get_theme")
+        )
+    )]
+    fn get_theme(&mut self) -> wasmtime::Result<psys_host::ui::ThemeInfo> {
+        Ok(host_theme_info(&self.app_handle))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.restore_state")
+        )
+    )]
+    fn restore_state(&mut self) -> wasmtime::Result<Option<psys_host::ui::UiState>> {
+        Ok(restore_ui_state(self))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.active_elements")
+        )
+    )]
+    fn active_elements(&mut self) -> wasmtime::Result<Vec<String>> {
+        Ok(active_element_ids(self.plugin_name()))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui.render_to_text_card")
+        )
+    )]
     fn render_to_text_card(&mut self, id: String, text: String) -> wasmtime::Result<()> {
         let _ = self.app_handle.emit(
             "plugin-ui-render-to-text-card",
@@ -176,6 +647,88 @@ impl psys_host::ui::Host for PluginCtx {
         Ok(())
     }
 }
+impl psys_host::ui::HostWithStore for PluginCtx {
+    fn update_card<T>(
+        accessor: &Accessor<T, Self>,
+        id: String,
+        content: psys_host::ui::CardContent,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "ui.update_card");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let register_state = accessor.with(|mut access| access.get().register_state());
+        // 元素资源需要在进入异步任务前从资源表中取出
+        let content = accessor.with(|mut access| match content {
+            psys_host::ui::CardContent::Element(element) => {
+                take_or_clone_element(access.get(), element)
+                    .ok()
+                    .and_then(|element| serde_json::to_string(&element).ok())
+                    .map(|ui| {
+                        (
+                            psys_host::register::CardType::Element,
+                            CardContent::Element(ui),
+                        )
+                    })
+            }
+            psys_host::ui::CardContent::Text(text) => {
+                Some((psys_host::register::CardType::Text, CardContent::Text(text)))
+            }
+        });
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let Some((card_type, content)) = content else {
+                        log::warn!(
+                            "[plugin:{}] update_card '{}' rejected: invalid element",
+                            plugin_name,
+                            id
+                        );
+                        return Ok(Err(()));
+                    };
+                    // 内容类型必须与注册的卡片类型一致
+                    if !register_state.has_card(&card_type, &id).await {
+                        log::warn!(
+                            "[plugin:{}] update_card '{}' rejected: no {:?} card registered with this id",
+                            plugin_name,
+                            id,
+                            card_type
+                        );
+                        return Ok(Err(()));
+                    }
+                    set_card_content(&app_handle, &plugin_name, &id, content);
+                    Ok::<core::result::Result<(), ()>, Error>(Ok(()))
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn request_foreground<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "ui.request_foreground");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let result =
+                        request_foreground(&app_handle, permissions.as_ref(), &plugin_name).await;
+                    Ok::<core::result::Result<(), ()>, Error>(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+}
+
 impl psys_host::ui::HostElement for PluginCtx {
     fn new(
         &mut self,
@@ -588,3 +1141,22 @@ impl psys_host::ui::HostElement for PluginCtx {
         return_owned_element(self, self_)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{active_element_ids, remember_render};
+
+    #[test]
+    fn active_elements_cover_nested_children() {
+        assert!(active_element_ids("ui-active-test").is_empty());
+
+        let tree = r#"{"id":"root","event_listeners":[{"id":"cb","event":"CLICK"}],
+            "children":[{"id":"a","children":null},{"id":"b","children":[{"id":"c"}]}]}"#;
+        remember_render("ui-active-test", "main", tree);
+        remember_render("ui-active-test", "side", r#"{"id":"d"}"#);
+        assert_eq!(
+            active_element_ids("ui-active-test"),
+            vec!["a", "b", "c", "d", "root"]
+        );
+    }
+}
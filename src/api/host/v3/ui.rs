@@ -8,7 +8,12 @@ use wasmtime::component::{Accessor, FutureReader, Resource};
 
 use crate::bindings::astrobox::psys_host;
 
-use crate::api::host::PluginCtx;
+use crate::api::host::permission::check_permission_declared;
+use crate::api::host::ui::{
+    begin_ui_batch, clear_task_progress, emit_task_progress, emit_ui_render, end_ui_batch,
+    host_theme_info, request_foreground, restore_ui_state,
+};
+use crate::api::host::{HostCallSpan, PluginCtx};
 
 #[derive(Clone, Serialize)]
 pub struct Element {
@@ -239,6 +244,14 @@ fn return_owned_element(
 }
 
 impl psys_host::ui_v3::Host for PluginCtx {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.render")
+        )
+    )]
     fn render(&mut self, id: String, element: Resource<Element>) -> wasmtime::Result<()> {
         let el = take_or_clone_element(self, element)?;
         let json = match serde_json::to_string(&el) {
@@ -252,18 +265,108 @@ impl psys_host::ui_v3::Host for PluginCtx {
             }
         };
 
-        let _ = self.app_handle.emit(
-            "plugin-ui-render",
-            serde_json::json!({
-                "name": self.plugin_name(),
-                "id": id,
-                "ui": json
-            }),
-        );
+        emit_ui_render(self, id, json);
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.begin_batch")
+        )
+    )]
+    fn begin_batch(&mut self) -> wasmtime::Result<()> {
+        begin_ui_batch(self);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.end_batch")
+        )
+    )]
+    fn end_batch(&mut self) -> wasmtime::Result<()> {
+        end_ui_batch(self);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.set_render_fps")
+        )
+    )]
+    fn set_render_fps(&mut self, fps: u32) -> wasmtime::Result<()> {
+        self.register_state().set_ui_render_fps(fps);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.set_task_progress")
+        )
+    )]
+    fn set_task_progress(&mut self, label: String, percent: Option<u8>) -> wasmtime::Result<()> {
+        emit_task_progress(self, label, percent);
+        Ok(())
+    }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.clear_task_progress")
+        )
+    )]
+    fn clear_task_progress(&mut self) -> wasmtime::Result<()> {
+        clear_task_progress(self);
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.get_theme")
+        )
+    )]
+    fn get_theme(&mut self) -> wasmtime::Result<psys_host::ui::ThemeInfo> {
+        Ok(host_theme_info(&self.app_handle))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.restore_state")
+        )
+    )]
+    fn restore_state(&mut self) -> wasmtime::Result<Option<psys_host::ui::UiState>> {
+        Ok(restore_ui_state(self))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "host_call",
+            skip_all,
+            fields(plugin = %self.plugin_name(), operation = "ui_v3.render_to_text_card")
+        )
+    )]
     fn render_to_text_card(&mut self, id: String, text: String) -> wasmtime::Result<()> {
         let _ = self.app_handle.emit(
             "plugin-ui-render-to-text-card",
@@ -339,18 +442,146 @@ async fn fetch_render_size(app_handle: &AppHandle, plugin_name: String) -> psys_
     }
 }
 
+const PLUGIN_FRONTEND_INVOKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 插件只能调用自身命名空间下的前端处理函数：`plugin/<插件名>/<method>`
+fn plugin_frontend_method(plugin_name: &str, method: &str) -> Option<String> {
+    let method = method.trim();
+    let valid = !method.is_empty()
+        && !method.starts_with('/')
+        && !method.contains("..")
+        && method
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.' | '/'));
+    valid.then(|| format!("plugin/{plugin_name}/{method}"))
+}
+
+async fn invoke_plugin_frontend(
+    app_handle: &AppHandle,
+    plugin_name: &str,
+    method: &str,
+    payload: String,
+) -> core::result::Result<String, ()> {
+    let Some(namespaced) = plugin_frontend_method(plugin_name, method) else {
+        log::warn!(
+            "[plugin:{}] invoke_frontend rejected: invalid method '{}'",
+            plugin_name,
+            method
+        );
+        return Err(());
+    };
+
+    // 负载若为合法 JSON 则按结构转发，否则作为字符串转发
+    let payload = serde_json::from_str::<serde_json::Value>(&payload)
+        .unwrap_or(serde_json::Value::String(payload));
+    let request = frontbridge::invoke_frontend::<serde_json::Value, _>(
+        app_handle,
+        &namespaced,
+        serde_json::json!({
+            "plugin": plugin_name,
+            "payload": payload,
+        }),
+    );
+    match tokio::time::timeout(PLUGIN_FRONTEND_INVOKE_TIMEOUT, request).await {
+        Ok(Ok(serde_json::Value::String(reply))) => Ok(reply),
+        Ok(Ok(reply)) => Ok(reply.to_string()),
+        Ok(Err(err)) => {
+            log::warn!(
+                "[plugin:{}] invoke_frontend '{}' failed: {err}",
+                plugin_name,
+                namespaced
+            );
+            Err(())
+        }
+        Err(_) => {
+            log::warn!(
+                "[plugin:{}] invoke_frontend '{}' timed out",
+                plugin_name,
+                namespaced
+            );
+            Err(())
+        }
+    }
+}
+
 impl psys_host::ui_v3::HostWithStore for PluginCtx {
+    fn invoke_frontend<T>(
+        accessor: &Accessor<T, Self>,
+        method: String,
+        payload: String,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<String, ()>>> + Send
+    {
+        let span = HostCallSpan::new(accessor, "ui_v3.invoke_frontend");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "invoke_frontend",
+                        serde_json::json!({
+                            "plugin": plugin_name.clone(),
+                            "method": method.clone(),
+                        }),
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<String, ()>, anyhow::Error>(Err(()));
+                    }
+
+                    let result =
+                        invoke_plugin_frontend(&app_handle, &plugin_name, &method, payload).await;
+                    Ok::<core::result::Result<String, ()>, anyhow::Error>(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+
+    fn request_foreground<T>(
+        accessor: &Accessor<T, Self>,
+    ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "ui_v3.request_foreground");
+        let instance = accessor.instance();
+        let app_handle = accessor.with(|mut access| access.get().app_handle());
+        let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
+        let permissions = accessor.with(|mut access| access.get().permissions());
+        let future = accessor.with(|mut access| {
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let result =
+                        request_foreground(&app_handle, permissions.as_ref(), &plugin_name).await;
+                    Ok::<core::result::Result<(), ()>, anyhow::Error>(result)
+                }),
+            )
+        });
+        async move { future }
+    }
+
     fn get_render_size<T>(
         accessor: &Accessor<T, Self>,
     ) -> impl core::future::Future<Output = FutureReader<psys_host::ui_v3::RenderSize>> + Send {
+        let span = HostCallSpan::new(accessor, "ui_v3.get_render_size");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let size = fetch_render_size(&app_handle, plugin_name).await;
-                Ok::<psys_host::ui_v3::RenderSize, anyhow::Error>(size)
-            })
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let size = fetch_render_size(&app_handle, plugin_name).await;
+                    Ok::<psys_host::ui_v3::RenderSize, anyhow::Error>(size)
+                }),
+            )
         });
         async move { future }
     }
@@ -676,6 +907,18 @@ impl psys_host::ui_v3::HostElement for PluginCtx {
         return_owned_element(self, self_)
     }
 
+    fn throttle(
+        &mut self,
+        self_: Resource<Element>,
+        id: String,
+        interval_ms: u32,
+    ) -> wasmtime::Result<Resource<Element>> {
+        // 0 表示关闭节流，逐个投递高频事件
+        self.register_state()
+            .set_ui_event_throttle(id, u64::from(interval_ms));
+        return_owned_element(self, self_)
+    }
+
     fn drop(&mut self, rep: Resource<Element>) -> wasmtime::Result<()> {
         if rep.owned() {
             let el = self.table.delete(rep)?;
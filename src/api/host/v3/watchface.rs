@@ -5,7 +5,9 @@ use log::error;
 use serde_json::json;
 use wasmtime::component::{Accessor, FutureReader};
 
-use crate::api::host::{HostString, HostVec, PluginCtx, permission::check_permission_declared};
+use crate::api::host::{
+    HostCallSpan, HostString, HostVec, PluginCtx, permission::check_permission_declared,
+};
 
 impl psys_host::watchface::Host for PluginCtx {}
 
@@ -18,45 +20,53 @@ impl psys_host::watchface::HostWithStore for PluginCtx {
             core::result::Result<HostVec<psys_host::watchface::WatchfaceInfo>, ()>,
         >,
     > + Send {
+        let span = HostCallSpan::new(accessor, "watchface.get_watchface_list");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let addr = addr.to_string();
-                let params = json!({
-                    "plugin": plugin_name,
-                    "addr": addr.clone(),
-                });
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "watchface",
-                    params,
-                )
-                .await
-                {
-                    return Ok::<
-                        core::result::Result<HostVec<psys_host::watchface::WatchfaceInfo>, ()>,
-                        Error,
-                    >(Err(()));
-                }
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = addr.to_string();
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": addr.clone(),
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "watchface",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok::<
+                            core::result::Result<HostVec<psys_host::watchface::WatchfaceInfo>, ()>,
+                            Error,
+                        >(Err(()));
+                    }
 
-                match get_watchface_list_impl(addr).await {
-                    Ok(list) => Ok::<
-                        core::result::Result<HostVec<psys_host::watchface::WatchfaceInfo>, ()>,
-                        Error,
-                    >(Ok(list)),
-                    Err(err) => {
-                        error!("Failed to fetch watchface list: {err:?}");
-                        Ok::<
+                    match get_watchface_list_impl(addr).await {
+                        Ok(list) => Ok::<
                             core::result::Result<HostVec<psys_host::watchface::WatchfaceInfo>, ()>,
                             Error,
-                        >(Err(()))
+                        >(Ok(list)),
+                        Err(err) => {
+                            error!("Failed to fetch watchface list: {err:?}");
+                            Ok::<
+                                core::result::Result<
+                                    HostVec<psys_host::watchface::WatchfaceInfo>,
+                                    (),
+                                >,
+                                Error,
+                            >(Err(()))
+                        }
                     }
-                }
-            })
+                }),
+            )
         });
         async move { future }
     }
@@ -66,38 +76,43 @@ impl psys_host::watchface::HostWithStore for PluginCtx {
         addr: HostString,
         watchface_id: HostString,
     ) -> impl core::future::Future<Output = FutureReader<core::result::Result<(), ()>>> + Send {
+        let span = HostCallSpan::new(accessor, "watchface.set_current_watchface");
         let instance = accessor.instance();
         let app_handle = accessor.with(|mut access| access.get().app_handle());
         let plugin_name = accessor.with(|mut access| access.get().plugin_name().to_string());
         let permissions = accessor.with(|mut access| access.get().permissions());
         let future = accessor.with(|mut access| {
-            FutureReader::new(instance, &mut access, async move {
-                let addr = addr.to_string();
-                let watchface_id = watchface_id.to_string();
-                let params = json!({
-                    "plugin": plugin_name,
-                    "addr": addr.clone(),
-                    "watchfaceId": watchface_id.clone(),
-                });
-                if !check_permission_declared(
-                    &app_handle,
-                    permissions.as_ref(),
-                    "watchface",
-                    params,
-                )
-                .await
-                {
-                    return Ok::<core::result::Result<(), ()>, Error>(Err(()));
-                }
+            FutureReader::new(
+                instance,
+                &mut access,
+                span.instrument(async move {
+                    let addr = addr.to_string();
+                    let watchface_id = watchface_id.to_string();
+                    let params = json!({
+                        "plugin": plugin_name,
+                        "addr": addr.clone(),
+                        "watchfaceId": watchface_id.clone(),
+                    });
+                    if !check_permission_declared(
+                        &app_handle,
+                        permissions.as_ref(),
+                        "watchface",
+                        params,
+                    )
+                    .await
+                    {
+                        return Ok::<core::result::Result<(), ()>, Error>(Err(()));
+                    }
 
-                match set_current_watchface_impl(addr, watchface_id).await {
-                    Ok(()) => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
-                    Err(err) => {
-                        error!("Failed to set current watchface: {err:?}");
-                        Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                    match set_current_watchface_impl(addr, watchface_id).await {
+                        Ok(()) => Ok::<core::result::Result<(), ()>, Error>(Ok(())),
+                        Err(err) => {
+                            error!("Failed to set current watchface: {err:?}");
+                            Ok::<core::result::Result<(), ()>, Error>(Err(()))
+                        }
                     }
-                }
-            })
+                }),
+            )
         });
         async move { future }
     }
@@ -0,0 +1,300 @@
+//! 提供给插件管理界面的 Tauri 命令
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// 连续调用 `plugin_rescan` 时只执行最后一次
+const RESCAN_DEBOUNCE: Duration = Duration::from_millis(300);
+static RESCAN_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 在系统文件管理器中打开指定插件的目录
+#[tauri::command]
+pub async fn plugin_reveal_dir(name: String) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| Box::pin(async move { pm.reveal_plugin_dir(&name) }))
+        .await
+        .and_then(|result| result)
+        .map_err(|err| err.to_string())
+}
+
+/// 清除预编译缓存并重新编译插件，供开发界面的“重新构建”按钮使用
+#[tauri::command]
+pub async fn plugin_recompile(name: String) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| Box::pin(async move { pm.recompile(&name).await }))
+        .await
+        .and_then(|result| result)
+        .map_err(|err| err.to_string())
+}
+
+/// 导出诊断包（zip 字节），供用户反馈问题时附带
+#[tauri::command]
+pub async fn plugin_diagnostic_bundle() -> Result<Vec<u8>, String> {
+    crate::with_plugin_manager_async(|pm| Box::pin(async move { pm.diagnostic_bundle().await }))
+        .await
+        .and_then(|result| result)
+        .map_err(|err| err.to_string())
+}
+
+/// 预热插件运行时，前端在用户即将打开插件（如悬停在打开按钮上）时调用；返回是否已预热
+#[tauri::command]
+pub async fn plugin_prewarm(name: String) -> Result<bool, String> {
+    crate::with_plugin_manager_async(move |pm| Box::pin(async move { pm.prewarm(&name) }))
+        .await
+        .and_then(|result| result)
+        .map_err(|err| err.to_string())
+}
+
+/// 插件声明了但从未请求过的权限
+#[tauri::command]
+pub async fn plugin_unused_permissions(name: String) -> Result<Vec<String>, String> {
+    crate::with_plugin_manager_async(move |pm| {
+        let unused = pm.unused_permissions(&name);
+        Box::pin(
+            async move { unused.ok_or_else(|| anyhow::anyhow!("Plugin '{}' not found", name)) },
+        )
+    })
+    .await
+    .and_then(|result| result)
+    .map_err(|err| err.to_string())
+}
+
+/// 撤销插件的授权，`operations` 为空时撤销全部已声明权限
+#[tauri::command]
+pub async fn plugin_revoke_permissions(
+    name: String,
+    operations: Vec<String>,
+) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.revoke_permissions(&name, operations).await })
+    })
+    .await
+    .and_then(|result| result)
+    .map_err(|err| err.to_string())
+}
+
+/// 所有插件卡片的当前内容，前端挂载卡片区域时调用，之后通过 `plugin-card-update` 事件增量更新
+#[tauri::command]
+pub fn plugin_card_contents() -> Vec<crate::api::host::ui::CardUpdate> {
+    crate::api::host::ui::card_contents()
+}
+
+/// 前端主题（明暗模式或强调色）变化时调用，通知插件重新渲染
+#[tauri::command]
+pub async fn plugin_set_theme(dark: bool, accent_color: Option<String>) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.set_theme(dark, accent_color).await })
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// 已连接设备变化时由前端调用，传入当前所有已连接设备的型号 id
+#[tauri::command]
+pub async fn plugin_set_connected_devices(models: Vec<String>) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.set_connected_devices(models) })
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// 前端收到插件自定义协议的原始帧时调用，按协议名转发给注册该协议的插件
+#[tauri::command]
+pub async fn plugin_dispatch_protocol_frame(
+    addr: String,
+    protocol: String,
+    payload: Vec<u8>,
+) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move {
+            pm.dispatch_custom_protocol_frame(&addr, &protocol, payload)
+                .await
+        })
+    })
+    .await
+    .and_then(|result| result)
+    .map_err(|err| err.to_string())
+}
+
+/// 宿主收到 AstroBox 深链接时调用，按路径前缀路由到对应插件，返回处理该链接的插件名
+#[tauri::command]
+pub async fn plugin_dispatch_deeplink(link: String) -> Result<String, String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.dispatch_deeplink(&link).await })
+    })
+    .await
+    .and_then(|result| result)
+    .map_err(|err| err.to_string())
+}
+
+/// 用户通过系统分享面板分享文件给 AstroBox 时由前端调用，返回处理该文件的插件名，用户取消选择时返回 null
+#[tauri::command]
+pub async fn plugin_share_file(
+    app_handle: tauri::AppHandle,
+    path: String,
+    mime: String,
+) -> Result<Option<String>, String> {
+    crate::share_target::share_file(app_handle, path.into(), mime)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// 宿主网络连接变化时由前端调用，`connection_type` 为 wifi / cellular / ethernet / none，无法判断时传 unknown
+#[tauri::command]
+pub async fn plugin_set_network_status(
+    connected: bool,
+    connection_type: crate::network::ConnectionType,
+) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.set_network_status(connected, connection_type).await })
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// 检查插件声明的权限是否符合给定的允许列表，供管理界面展示违规权限
+#[tauri::command]
+pub async fn plugin_check_policy(
+    name: String,
+    allowed: Vec<String>,
+) -> Result<crate::manager::PolicyResult, String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.check_policy(&name, &allowed) })
+    })
+    .await
+    .and_then(|result| result)
+    .map_err(|err| err.to_string())
+}
+
+/// 读取插件的特性开关（JSON 对象）
+#[tauri::command]
+pub async fn plugin_get_flags(
+    name: String,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    crate::with_plugin_manager_async(move |pm| Box::pin(async move { pm.flags(&name) }))
+        .await
+        .and_then(|result| result)
+        .map_err(|err| err.to_string())
+}
+
+/// 覆盖插件的特性开关并通知插件，无需重新发布插件即可调整其行为
+#[tauri::command]
+pub async fn plugin_set_flags(
+    name: String,
+    flags: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.set_flags(&name, flags).await })
+    })
+    .await
+    .and_then(|result| result)
+    .map_err(|err| err.to_string())
+}
+
+/// 运维调整插件的日志级别（trace / debug / info / warn / error / off），传 null 恢复为宿主全局级别
+#[tauri::command]
+pub async fn plugin_set_log_level(name: String, level: Option<String>) -> Result<(), String> {
+    let level = level
+        .map(|level| level.parse::<log::LevelFilter>())
+        .transpose()
+        .map_err(|err| err.to_string())?;
+    crate::with_plugin_manager_async(move |pm| {
+        let result = pm.set_log_level(&name, level);
+        Box::pin(async move { result })
+    })
+    .await
+    .and_then(|result| result)
+    .map_err(|err| err.to_string())
+}
+
+/// 宿主电源状态变化时由前端调用，`source` 为 charging / battery，无法判断时传 unknown；
+/// 平台无法获取省电模式时 `power_saver` 传 null
+#[tauri::command]
+pub async fn plugin_set_power_state(
+    source: crate::power::PowerSource,
+    power_saver: Option<bool>,
+) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.set_power_state(source, power_saver).await })
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// 插件重载前由前端调用，保存输入内容、滚动位置等界面值（JSON），供插件重载后恢复
+#[tauri::command]
+pub fn plugin_save_ui_state(name: String, state: String) {
+    crate::api::host::ui::save_frontend_ui_state(&name, state);
+}
+
+/// 用户修改遥测偏好时调用，未同意时插件的统计事件会被直接丢弃
+#[tauri::command]
+pub fn plugin_set_telemetry_consent(consent: bool) {
+    crate::analytics::set_telemetry_consent(consent);
+}
+
+/// 各插件当前的后台任务进度，前端全局状态栏打开时调用，之后按 plugin-task-progress 事件更新
+#[tauri::command]
+pub async fn plugin_task_progress()
+-> Result<std::collections::BTreeMap<String, crate::api::host::ui::TaskProgress>, String> {
+    crate::with_plugin_manager_async(move |pm| {
+        let progress = pm.task_progress();
+        Box::pin(async move { progress })
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// 主窗口隐藏（托盘模式）或重新显示时调用，隐藏期间只有后台插件继续运行
+#[tauri::command]
+pub async fn plugin_set_window_hidden(hidden: bool) -> Result<(), String> {
+    crate::with_plugin_manager_async(move |pm| {
+        Box::pin(async move { pm.set_window_hidden(hidden) })
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// 重新扫描插件目录，加载新增的插件并卸载已删除的插件；
+/// 短时间内多次调用会被合并，只有最后一次实际扫描，其余返回 `coalesced`
+#[tauri::command]
+pub async fn plugin_rescan() -> Result<crate::manager::RescanReport, String> {
+    let generation = RESCAN_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    tokio::time::sleep(RESCAN_DEBOUNCE).await;
+    if RESCAN_GENERATION.load(Ordering::SeqCst) != generation {
+        return Ok(crate::manager::RescanReport {
+            coalesced: true,
+            ..Default::default()
+        });
+    }
+    crate::with_plugin_manager_async(move |pm| Box::pin(async move { pm.rescan().await }))
+        .await
+        .and_then(|result| result)
+        .map_err(|err| err.to_string())
+}
+
+/// 调用插件注册的命令，返回插件给出的结果
+#[tauri::command]
+pub async fn plugin_invoke_command(
+    name: String,
+    command: String,
+    args: String,
+) -> Result<String, String> {
+    let target_name = name.clone();
+    let target_command = command.clone();
+    let runtime = crate::with_plugin_manager_async(move |pm| {
+        let target = pm.command_target(&target_name, &target_command);
+        Box::pin(async move { target })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())?;
+    crate::plugin_command::invoke(runtime, &name, &command, args)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// 插件注册的命令列表
+#[tauri::command]
+pub async fn plugin_list_commands(name: String) -> Result<Vec<String>, String> {
+    Ok(crate::plugin_command::list_commands(&name))
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex as StdMutex;
+
+use once_cell::sync::Lazy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeeplinkClaimError {
+    InvalidPrefix,
+    Conflict,
+}
+
+/// 深链接路径前缀路由表：前缀 -> 插件名，按最长前缀匹配路由
+#[derive(Default)]
+pub(crate) struct DeeplinkRoutes {
+    prefixes: BTreeMap<String, String>,
+}
+
+static DEEPLINK_ROUTES: Lazy<StdMutex<DeeplinkRoutes>> =
+    Lazy::new(|| StdMutex::new(DeeplinkRoutes::default()));
+
+fn normalize_path(path: &str) -> Option<String> {
+    let segments = path
+        .trim()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join("/"))
+    }
+}
+
+/// 取出深链接中用于路由的路径，`astrobox://foo/bar?x=1` 得到 `foo/bar`
+pub(crate) fn deeplink_route_path(link: &str) -> Option<String> {
+    match url::Url::parse(link) {
+        Ok(url) => {
+            let host = url.host_str().unwrap_or_default();
+            normalize_path(&format!("{host}/{}", url.path()))
+        }
+        Err(_) => normalize_path(link.split(['?', '#']).next().unwrap_or_default()),
+    }
+}
+
+// 只在路径段边界上匹配，`foo` 不会匹配 `foobar`
+fn prefix_matches(prefix: &str, path: &str) -> bool {
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+impl DeeplinkRoutes {
+    pub(crate) fn claim(&mut self, plugin: &str, prefix: &str) -> Result<(), DeeplinkClaimError> {
+        let prefix = normalize_path(prefix).ok_or(DeeplinkClaimError::InvalidPrefix)?;
+        match self.prefixes.get(&prefix) {
+            Some(owner) if owner != plugin => Err(DeeplinkClaimError::Conflict),
+            Some(_) => Ok(()),
+            None => {
+                self.prefixes.insert(prefix, plugin.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn route(&self, path: &str) -> Option<&str> {
+        self.prefixes
+            .iter()
+            .filter(|(prefix, _)| prefix_matches(prefix, path))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, plugin)| plugin.as_str())
+    }
+
+    pub(crate) fn release_plugin(&mut self, plugin: &str) {
+        self.prefixes.retain(|_, owner| owner != plugin);
+    }
+}
+
+pub(crate) fn claim_deeplink_prefix(plugin: &str, prefix: &str) -> Result<(), DeeplinkClaimError> {
+    DEEPLINK_ROUTES
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .claim(plugin, prefix)
+}
+
+pub(crate) fn route_deeplink(link: &str) -> Option<String> {
+    let path = deeplink_route_path(link)?;
+    DEEPLINK_ROUTES
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .route(&path)
+        .map(str::to_string)
+}
+
+/// 插件停止或重新加载时释放它占用的全部前缀
+pub(crate) fn release_deeplink_prefixes(plugin: &str) {
+    DEEPLINK_ROUTES
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .release_plugin(plugin);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeeplinkClaimError, DeeplinkRoutes, deeplink_route_path};
+
+    #[test]
+    fn routes_to_longest_matching_prefix() {
+        let mut routes = DeeplinkRoutes::default();
+        routes.claim("weather", "/weather").unwrap();
+        routes.claim("radar", "weather/radar/").unwrap();
+
+        assert_eq!(routes.route("weather/today"), Some("weather"));
+        assert_eq!(routes.route("weather/radar/live"), Some("radar"));
+        assert_eq!(routes.route("weatherman"), None);
+
+        routes.release_plugin("radar");
+        assert_eq!(routes.route("weather/radar/live"), Some("weather"));
+    }
+
+    #[test]
+    fn rejects_prefix_owned_by_another_plugin() {
+        let mut routes = DeeplinkRoutes::default();
+        routes.claim("first", "music").unwrap();
+        assert_eq!(routes.claim("first", "/music/"), Ok(()));
+        assert_eq!(
+            routes.claim("second", "music"),
+            Err(DeeplinkClaimError::Conflict)
+        );
+        assert_eq!(
+            routes.claim("second", " / "),
+            Err(DeeplinkClaimError::InvalidPrefix)
+        );
+    }
+
+    #[test]
+    fn extracts_route_path_from_link() {
+        assert_eq!(
+            deeplink_route_path("astrobox://weather/radar?city=1").as_deref(),
+            Some("weather/radar")
+        );
+        assert_eq!(deeplink_route_path("astrobox://").as_deref(), None);
+    }
+}
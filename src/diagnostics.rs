@@ -0,0 +1,84 @@
+//! 诊断包：把插件的 manifest、最近日志、崩溃记录、耗时统计和注册信息打包成 zip，供用户反馈问题时导出。
+//! 不包含插件目录中的文件、密钥存储和键值存储的内容
+
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+pub(crate) struct DiagnosticBundleWriter {
+    zip: ZipWriter<Cursor<Vec<u8>>>,
+}
+
+impl DiagnosticBundleWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            zip: ZipWriter::new(Cursor::new(Vec::new())),
+        }
+    }
+
+    pub(crate) fn add_json<T: Serialize + ?Sized>(&mut self, path: &str, value: &T) -> Result<()> {
+        let content = serde_json::to_vec_pretty(value)?;
+        self.add_bytes(path, &content)
+    }
+
+    pub(crate) fn add_text(&mut self, path: &str, text: &str) -> Result<()> {
+        self.add_bytes(path, text.as_bytes())
+    }
+
+    /// 文件不存在时跳过
+    pub(crate) fn add_file(&mut self, path: &str, source: &Path) -> Result<()> {
+        match std::fs::read(source) {
+            Ok(content) => self.add_bytes(path, &content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).with_context(|| format!("failed to read {}", source.display())),
+        }
+    }
+
+    fn add_bytes(&mut self, path: &str, content: &[u8]) -> Result<()> {
+        self.zip
+            .start_file(path, SimpleFileOptions::default())
+            .with_context(|| format!("failed to add {path} to diagnostic bundle"))?;
+        self.zip.write_all(content)?;
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<Vec<u8>> {
+        Ok(self.zip.finish()?.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use zip::ZipArchive;
+
+    use super::DiagnosticBundleWriter;
+
+    #[test]
+    fn bundle_is_a_readable_zip() {
+        let mut bundle = DiagnosticBundleWriter::new();
+        bundle
+            .add_json("summary.json", &serde_json::json!({ "plugins": 1 }))
+            .unwrap();
+        bundle.add_text("plugins/demo/logs.txt", "hello").unwrap();
+        bundle
+            .add_file("missing.json", std::path::Path::new("/nonexistent/psys"))
+            .unwrap();
+        let bytes = bundle.finish().unwrap();
+
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), 2);
+        let mut logs = String::new();
+        archive
+            .by_name("plugins/demo/logs.txt")
+            .unwrap()
+            .read_to_string(&mut logs)
+            .unwrap();
+        assert_eq!(logs, "hello");
+    }
+}
@@ -0,0 +1,175 @@
+//! 事件洪泛看门狗：统计每个插件发出的插件间事件数量，持续超出预算时限流或停止该插件
+
+use std::collections::HashMap;
+use std::sync::Mutex as StdMutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// 插件因事件洪泛被停止时记录的错误码
+pub const EVENT_FLOOD_CODE: &str = "event-flood";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventFloodAction {
+    /// 丢弃窗口内超出预算的事件
+    Throttle,
+    /// 停止插件并通知前端
+    Disable,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventFloodPolicy {
+    /// 窗口内允许的平均每秒事件数
+    pub max_events_per_sec: u32,
+    /// 统计窗口，窗口内的事件总数超过 `max_events_per_sec * window` 即视为洪泛
+    pub window: Duration,
+    pub action: EventFloodAction,
+}
+
+impl Default for EventFloodPolicy {
+    fn default() -> Self {
+        Self {
+            max_events_per_sec: 100,
+            window: Duration::from_secs(5),
+            action: EventFloodAction::Throttle,
+        }
+    }
+}
+
+impl EventFloodPolicy {
+    fn budget(&self) -> u64 {
+        (f64::from(self.max_events_per_sec) * self.window.as_secs_f64())
+            .ceil()
+            .max(1.0) as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FloodVerdict {
+    Allow,
+    /// 超出预算，丢弃本次事件
+    Drop,
+    /// 首次超出预算且策略为停止插件
+    Disable,
+}
+
+struct RateWindow {
+    started: Instant,
+    count: u64,
+    tripped: bool,
+}
+
+static EVENT_FLOOD_POLICY: Lazy<StdMutex<Option<EventFloodPolicy>>> =
+    Lazy::new(|| StdMutex::new(Some(EventFloodPolicy::default())));
+static EVENT_RATES: Lazy<StdMutex<HashMap<String, RateWindow>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+/// 设置洪泛策略，`None` 表示不限制
+pub fn set_event_flood_policy(policy: Option<EventFloodPolicy>) {
+    *EVENT_FLOOD_POLICY
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner()) = policy;
+}
+
+/// 插件停止或重新加载时清除其计数
+pub(crate) fn reset_event_rate(plugin: &str) {
+    EVENT_RATES
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(plugin);
+}
+
+/// 记录插件发出的一个事件并判断是否超出预算
+pub(crate) fn record_outbound_event(plugin: &str) -> FloodVerdict {
+    let policy = *EVENT_FLOOD_POLICY
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    match policy {
+        Some(policy) => record_at(plugin, &policy, Instant::now()),
+        None => FloodVerdict::Allow,
+    }
+}
+
+pub(crate) fn record_at(plugin: &str, policy: &EventFloodPolicy, now: Instant) -> FloodVerdict {
+    let mut rates = EVENT_RATES
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let rate = rates.entry(plugin.to_string()).or_insert(RateWindow {
+        started: now,
+        count: 0,
+        tripped: false,
+    });
+    if now.duration_since(rate.started) >= policy.window {
+        *rate = RateWindow {
+            started: now,
+            count: 0,
+            tripped: false,
+        };
+    }
+    rate.count = rate.count.saturating_add(1);
+    if rate.count <= policy.budget() {
+        return FloodVerdict::Allow;
+    }
+
+    let first_trip = !rate.tripped;
+    rate.tripped = true;
+    match policy.action {
+        EventFloodAction::Disable if first_trip => FloodVerdict::Disable,
+        EventFloodAction::Throttle if first_trip => {
+            log::warn!(
+                "[plugin:{}] sent more than {} events within {}s, dropping the rest of this window",
+                plugin,
+                policy.budget(),
+                policy.window.as_secs_f64()
+            );
+            FloodVerdict::Drop
+        }
+        _ => FloodVerdict::Drop,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{EventFloodAction, EventFloodPolicy, FloodVerdict, record_at, reset_event_rate};
+
+    #[test]
+    fn sustained_flood_disables_plugin_once() {
+        let policy = EventFloodPolicy {
+            max_events_per_sec: 10,
+            window: Duration::from_secs(1),
+            action: EventFloodAction::Disable,
+        };
+        let now = Instant::now();
+        let verdicts = (0..1000)
+            .map(|_| record_at("flooder", &policy, now))
+            .collect::<Vec<_>>();
+        assert!(verdicts[..10].iter().all(|v| *v == FloodVerdict::Allow));
+        assert_eq!(verdicts[10], FloodVerdict::Disable);
+        assert!(verdicts[11..].iter().all(|v| *v == FloodVerdict::Drop));
+
+        // 新窗口重新计数
+        let later = now + Duration::from_secs(1);
+        assert_eq!(record_at("flooder", &policy, later), FloodVerdict::Allow);
+        reset_event_rate("flooder");
+    }
+
+    #[test]
+    fn throttle_only_drops_excess_events() {
+        let policy = EventFloodPolicy {
+            max_events_per_sec: 2,
+            window: Duration::from_secs(1),
+            action: EventFloodAction::Throttle,
+        };
+        let now = Instant::now();
+        assert_eq!(record_at("chatty", &policy, now), FloodVerdict::Allow);
+        assert_eq!(record_at("chatty", &policy, now), FloodVerdict::Allow);
+        assert_eq!(record_at("chatty", &policy, now), FloodVerdict::Drop);
+        assert_eq!(record_at("quiet", &policy, now), FloodVerdict::Allow);
+        reset_event_rate("chatty");
+        reset_event_rate("quiet");
+    }
+}
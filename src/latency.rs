@@ -0,0 +1,127 @@
+//! 宿主调用与事件派发的耗时直方图，用于定位慢的宿主交互。
+//! 按操作名（固定集合）汇总，内存占用有上限
+
+use std::collections::HashMap;
+use std::sync::Mutex as StdMutex;
+use std::time::{Duration, Instant};
+
+use hdrhistogram::Histogram;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+// 记录上限 10 分钟，超出的按上限计入
+const LATENCY_MAX_US: u64 = 600_000_000;
+const LATENCY_SIGFIGS: u8 = 2;
+/// 耗时汇总写入 debug 日志的间隔
+pub(crate) const LATENCY_SUMMARY_INTERVAL: Duration = Duration::from_secs(300);
+
+static HISTOGRAMS: Lazy<StdMutex<HashMap<&'static str, Histogram<u64>>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub operation: String,
+    pub count: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+pub(crate) fn record(operation: &'static str, elapsed: Duration) {
+    let micros = (elapsed.as_micros() as u64).clamp(1, LATENCY_MAX_US);
+    let mut histograms = HISTOGRAMS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let histogram = match histograms.get_mut(operation) {
+        Some(histogram) => histogram,
+        None => {
+            let Ok(histogram) = Histogram::new_with_bounds(1, LATENCY_MAX_US, LATENCY_SIGFIGS)
+            else {
+                return;
+            };
+            histograms.entry(operation).or_insert(histogram)
+        }
+    };
+    histogram.saturating_record(micros);
+}
+
+/// 离开作用域时记录耗时，被取消的调用同样计入
+pub(crate) struct LatencyTimer {
+    operation: &'static str,
+    started: Instant,
+}
+
+impl LatencyTimer {
+    pub(crate) fn start(operation: &'static str) -> Self {
+        Self {
+            operation,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for LatencyTimer {
+    fn drop(&mut self) {
+        record(self.operation, self.started.elapsed());
+    }
+}
+
+/// 各操作的耗时分位数，按操作名排序
+pub fn latency_stats() -> Vec<LatencyStats> {
+    let histograms = HISTOGRAMS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let mut stats = histograms
+        .iter()
+        .map(|(operation, histogram)| LatencyStats {
+            operation: operation.to_string(),
+            count: histogram.len(),
+            p50_us: histogram.value_at_quantile(0.5),
+            p90_us: histogram.value_at_quantile(0.9),
+            p99_us: histogram.value_at_quantile(0.99),
+            max_us: histogram.max(),
+        })
+        .collect::<Vec<_>>();
+    stats.sort_by(|left, right| left.operation.cmp(&right.operation));
+    stats
+}
+
+pub(crate) fn log_summary() {
+    if !log::log_enabled!(log::Level::Debug) {
+        return;
+    }
+    for stats in latency_stats() {
+        log::debug!(
+            "[pluginsystem] latency {}: n={} p50={}us p90={}us p99={}us max={}us",
+            stats.operation,
+            stats.count,
+            stats.p50_us,
+            stats.p90_us,
+            stats.p99_us,
+            stats.max_us
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{latency_stats, record};
+
+    #[test]
+    fn records_quantiles_per_operation() {
+        for ms in 1..=100 {
+            record("test.latency", Duration::from_millis(ms));
+        }
+        let stats = latency_stats()
+            .into_iter()
+            .find(|stats| stats.operation == "test.latency")
+            .unwrap();
+        assert_eq!(stats.count, 100);
+        assert!((49_000..=51_000).contains(&stats.p50_us));
+        assert!(stats.max_us >= 99_000);
+    }
+}
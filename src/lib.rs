@@ -1,12 +1,13 @@
 use anyhow::Result;
 use futures_util::FutureExt;
-use manager::PluginManager;
+use manager::{PluginLoadReport, PluginManager};
 use once_cell::sync::{Lazy, OnceCell};
 use serde::Serialize;
 use std::future::Future;
 use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
 use std::sync::Mutex;
+use std::time::Duration;
 use std::{cell::RefCell, path::PathBuf, thread};
 use tauri::{AppHandle, Emitter};
 use tokio::sync::{mpsc, oneshot};
@@ -19,6 +20,7 @@ pub mod bindings {
         with:{
             "astrobox:psys-host/ui/element": crate::api::host::ui::Element,
             "astrobox:psys-host/ui-v3/element": crate::api::host::v3::ui::Element,
+            "astrobox:psys-host/sync/lock-handle": crate::api::host::sync::LockHandle,
         },
         imports: {
             "astrobox:psys-host/os/arch": async | store,
@@ -29,8 +31,11 @@ pub mod bindings {
             "astrobox:psys-host/os/astrobox-language": async | store,
             "astrobox:psys-host/os/appearance": async | store,
             "astrobox:psys-host/os/timezone-offset-minutes": async | store,
+            "astrobox:psys-host/os/activity-snapshot": async | store,
             "astrobox:psys-host/transport/send": async | store,
             "astrobox:psys-host/transport/request": async | store,
+            "astrobox:psys-host/transport/ping": async | store,
+            "astrobox:psys-host/transport/send-raw": async | store,
             "astrobox:psys-host/clipboard/read-text": async | store,
             "astrobox:psys-host/clipboard/write-text": async | store,
             "astrobox:psys-host/dialog/show-dialog": async | store,
@@ -39,24 +44,60 @@ pub mod bindings {
             "astrobox:psys-host/dialog/save-file-write-chunk": async | store,
             "astrobox:psys-host/dialog/save-file-finish": async | store,
             "astrobox:psys-host/dialog/save-file-abort": async | store,
+            "astrobox:psys-host/dialog/open-file-start": async | store,
+            "astrobox:psys-host/dialog/open-file-read-chunk": async | store,
+            "astrobox:psys-host/dialog/open-file-finish": async | store,
             "astrobox:psys-host/device/get-device-list": async | store,
             "astrobox:psys-host/device/get-connected-device-list": async | store,
+            "astrobox:psys-host/device/connected-count": async | store,
+            "astrobox:psys-host/device/get-active-device": async | store,
             "astrobox:psys-host/device/disconnect-device": async | store,
+            "astrobox:psys-host/device/vibrate": async | store,
+            "astrobox:psys-host/device/send-watch-notification": async | store,
+            "astrobox:psys-host/device/get-device-time": async | store,
+            "astrobox:psys-host/device/to-device-local": async | store,
+            "astrobox:psys-host/device/from-device-local": async | store,
+            "astrobox:psys-host/device/get-device-settings": async | store,
+            "astrobox:psys-host/device/get-health-snapshot": async | store,
             "astrobox:psys-host/register/register-transport-recv": async | store,
             "astrobox:psys-host/register/register-interconnect-recv": async | store,
             "astrobox:psys-host/register/register-deeplink-action": async | store,
+            "astrobox:psys-host/register/register-deeplink-path": async | store,
+            "astrobox:psys-host/register/register-protocol": async | store,
+            "astrobox:psys-host/register/register-share-target": async | store,
             "astrobox:psys-host/register/register-provider": async | store,
             "astrobox:psys-host/register/register-card": async | store,
+            "astrobox:psys-host/register/unregister-card": async | store,
+            "astrobox:psys-host/plugins/list": async | store,
+            "astrobox:psys-host/plugins/set-enabled": async | store,
+            "astrobox:psys-host/provider/get": async | store,
+            "astrobox:psys-host/provider/resolve": async | store,
+            "astrobox:psys-host/secrets/get": async | store,
+            "astrobox:psys-host/secrets/set": async | store,
+            "astrobox:psys-host/secrets/delete": async | store,
+            "astrobox:psys-host/storage/quota": async | store,
             "astrobox:psys-host/timer/set-timeout": async | store,
             "astrobox:psys-host/timer/set-interval": async | store,
+            "astrobox:psys-host/timer/set-alarm": async | store,
             "astrobox:psys-host/timer/clear-timer": async | store,
+            "astrobox:psys-host/timer/next-idle": async | store,
             "astrobox:psys-host/interconnect/send-qaic-message": async | store,
+            "astrobox:psys-host/interconnect/send-qaic-bytes": async | store,
             "astrobox:psys-host/thirdpartyapp/launch-qa": async | store,
             "astrobox:psys-host/thirdpartyapp/get-thirdparty-app-list": async | store,
+            "astrobox:psys-host/thirdpartyapp/is-installed": async | store,
             "astrobox:psys-host/watchface/get-watchface-list": async | store,
             "astrobox:psys-host/watchface/set-current-watchface": async | store,
             "astrobox:psys-host/i18n/load-json": async | store,
+            "astrobox:psys-host/sync/acquire": async | store,
+            "astrobox:psys-host/self/report-fatal": async | store,
+            "astrobox:psys-host/self/restart-info": async | store,
+            "astrobox:psys-host/self/paths": async | store,
+            "astrobox:psys-host/ui/request-foreground": async | store,
+            "astrobox:psys-host/ui/update-card": async | store,
             "astrobox:psys-host/ui-v3/get-render-size": async | store,
+            "astrobox:psys-host/ui-v3/invoke-frontend": async | store,
+            "astrobox:psys-host/ui-v3/request-foreground": async | store,
             default: trappable
         },
         exports: {
@@ -70,6 +111,7 @@ pub mod bindings_v3 {
         world: "psys-world-v3",
         with:{
             "astrobox:psys-host/ui-v3/element": crate::api::host::v3::ui::Element,
+            "astrobox:psys-host/sync/lock-handle": crate::api::host::sync::LockHandle,
         },
         imports: {
             "astrobox:psys-host/os/arch": async | store,
@@ -80,8 +122,11 @@ pub mod bindings_v3 {
             "astrobox:psys-host/os/astrobox-language": async | store,
             "astrobox:psys-host/os/appearance": async | store,
             "astrobox:psys-host/os/timezone-offset-minutes": async | store,
+            "astrobox:psys-host/os/activity-snapshot": async | store,
             "astrobox:psys-host/transport/send": async | store,
             "astrobox:psys-host/transport/request": async | store,
+            "astrobox:psys-host/transport/ping": async | store,
+            "astrobox:psys-host/transport/send-raw": async | store,
             "astrobox:psys-host/clipboard/read-text": async | store,
             "astrobox:psys-host/clipboard/write-text": async | store,
             "astrobox:psys-host/dialog/show-dialog": async | store,
@@ -90,24 +135,60 @@ pub mod bindings_v3 {
             "astrobox:psys-host/dialog/save-file-write-chunk": async | store,
             "astrobox:psys-host/dialog/save-file-finish": async | store,
             "astrobox:psys-host/dialog/save-file-abort": async | store,
+            "astrobox:psys-host/dialog/open-file-start": async | store,
+            "astrobox:psys-host/dialog/open-file-read-chunk": async | store,
+            "astrobox:psys-host/dialog/open-file-finish": async | store,
             "astrobox:psys-host/device/get-device-list": async | store,
             "astrobox:psys-host/device/get-connected-device-list": async | store,
+            "astrobox:psys-host/device/connected-count": async | store,
+            "astrobox:psys-host/device/get-active-device": async | store,
             "astrobox:psys-host/device/disconnect-device": async | store,
+            "astrobox:psys-host/device/vibrate": async | store,
+            "astrobox:psys-host/device/send-watch-notification": async | store,
+            "astrobox:psys-host/device/get-device-time": async | store,
+            "astrobox:psys-host/device/to-device-local": async | store,
+            "astrobox:psys-host/device/from-device-local": async | store,
+            "astrobox:psys-host/device/get-device-settings": async | store,
+            "astrobox:psys-host/device/get-health-snapshot": async | store,
             "astrobox:psys-host/register/register-transport-recv": async | store,
             "astrobox:psys-host/register/register-interconnect-recv": async | store,
             "astrobox:psys-host/register/register-deeplink-action": async | store,
+            "astrobox:psys-host/register/register-deeplink-path": async | store,
+            "astrobox:psys-host/register/register-protocol": async | store,
+            "astrobox:psys-host/register/register-share-target": async | store,
             "astrobox:psys-host/register/register-provider": async | store,
             "astrobox:psys-host/register/register-card": async | store,
+            "astrobox:psys-host/register/unregister-card": async | store,
+            "astrobox:psys-host/plugins/list": async | store,
+            "astrobox:psys-host/plugins/set-enabled": async | store,
+            "astrobox:psys-host/provider/get": async | store,
+            "astrobox:psys-host/provider/resolve": async | store,
+            "astrobox:psys-host/secrets/get": async | store,
+            "astrobox:psys-host/secrets/set": async | store,
+            "astrobox:psys-host/secrets/delete": async | store,
+            "astrobox:psys-host/storage/quota": async | store,
             "astrobox:psys-host/timer/set-timeout": async | store,
             "astrobox:psys-host/timer/set-interval": async | store,
+            "astrobox:psys-host/timer/set-alarm": async | store,
             "astrobox:psys-host/timer/clear-timer": async | store,
+            "astrobox:psys-host/timer/next-idle": async | store,
             "astrobox:psys-host/interconnect/send-qaic-message": async | store,
+            "astrobox:psys-host/interconnect/send-qaic-bytes": async | store,
             "astrobox:psys-host/thirdpartyapp/launch-qa": async | store,
             "astrobox:psys-host/thirdpartyapp/get-thirdparty-app-list": async | store,
+            "astrobox:psys-host/thirdpartyapp/is-installed": async | store,
             "astrobox:psys-host/watchface/get-watchface-list": async | store,
             "astrobox:psys-host/watchface/set-current-watchface": async | store,
             "astrobox:psys-host/i18n/load-json": async | store,
+            "astrobox:psys-host/sync/acquire": async | store,
+            "astrobox:psys-host/self/report-fatal": async | store,
+            "astrobox:psys-host/self/restart-info": async | store,
+            "astrobox:psys-host/self/paths": async | store,
+            "astrobox:psys-host/ui/request-foreground": async | store,
+            "astrobox:psys-host/ui/update-card": async | store,
             "astrobox:psys-host/ui-v3/get-render-size": async | store,
+            "astrobox:psys-host/ui-v3/invoke-frontend": async | store,
+            "astrobox:psys-host/ui-v3/request-foreground": async | store,
             default: trappable
         },
         exports: {
@@ -115,20 +196,41 @@ pub mod bindings_v3 {
         }
     });
 }
+pub mod analytics;
+pub mod commands;
+mod deeplink;
+mod diagnostics;
+pub mod flood;
+pub mod latency;
+pub mod limits;
 pub mod manager;
 pub mod manifest;
+mod network;
 pub mod plugin;
+pub mod plugin_command;
+mod power;
 pub mod provider_action_bridge;
+mod share_target;
+pub mod sticky;
+mod suspension;
+#[cfg(test)]
+mod test_support;
+mod theme;
 mod transport_runtime;
+pub mod webroot;
 
 pub const PLUGINSYSTEM_READY_EVENT: &str = "astrobox://pluginsystem/ready";
 pub const PLUGINSYSTEM_PROGRESS_EVENT: &str = "astrobox://pluginsystem/progress";
+pub const PLUGINSYSTEM_PERMISSION_DIFF_EVENT: &str = "astrobox://pluginsystem/permission-diff";
+pub const PLUGINSYSTEM_PLUGIN_FAILED_EVENT: &str = "astrobox://pluginsystem/plugin-failed";
 
 #[derive(Debug, Serialize, Clone)]
 struct PluginSystemReadyPayload {
     ok: bool,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     errors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<PluginLoadReport>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -139,12 +241,112 @@ pub struct PluginSystemProgressPayload {
     pub detail: Option<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct PluginPermissionDiffPayload {
+    pub plugin: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PluginFailedPayload {
+    pub plugin: String,
+    pub code: String,
+    pub message: String,
+}
+
+/// 插件线程命令队列已满时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandQueuePolicy {
+    /// 等待队列腾出空间（背压）
+    Wait,
+    /// 立即返回“系统繁忙”错误
+    Reject,
+}
+
+/// 插件间事件派发失败时的重试策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventRetryPolicy {
+    /// 是否重试；关闭时首次失败即进入死信。只重试插件暂时无法接收的失败，wasm trap 直接进入死信
+    pub enabled: bool,
+    /// 首次派发之外的最大重试次数
+    pub max_retries: u32,
+    /// 首次重试前的等待时间，之后每次翻倍
+    pub initial_backoff: Duration,
+}
+
+// 默认不重试：事件处理不一定幂等，由宿主按需开启
+impl Default for EventRetryPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+impl EventRetryPolicy {
+    /// 第 `retry` 次重试（从 1 开始）前的等待时间
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginSystemOptions {
+    pub command_queue_capacity: usize,
+    pub command_queue_policy: CommandQueuePolicy,
+    /// 以安全模式启动：加载插件但不启动任何插件
+    pub safe_mode: bool,
+    /// 同时运行的插件数量上限，`None` 表示不限制
+    pub max_plugins: Option<usize>,
+    /// 定期健康检查的间隔，`None` 表示不做检查
+    pub health_check_interval: Option<Duration>,
+    /// 健康检查发现插件无响应时自动重启
+    pub restart_unhealthy: bool,
+    /// 插件间事件派发失败时的重试策略
+    pub event_retry: EventRetryPolicy,
+    /// 允许插件声明的权限列表，`None` 表示不限制；声明了其他权限的插件会被隔离而不启动
+    pub permission_policy: Option<Vec<String>>,
+    /// 保留最新载荷并在插件启动完成后补发的事件类型
+    pub sticky_events: Vec<sticky::StickyEvent>,
+    /// 插件无活动超过该时长后释放其实例并在下次活动时重新实例化，`None` 表示不挂起
+    pub idle_suspend_after: Option<Duration>,
+    /// 插件发送事件的速率上限及超出时的处理方式，`None` 表示不限制。
+    /// 默认只丢弃超出预算的事件，需要停止洪泛插件时将 `action` 设为 `Disable`
+    pub event_flood: Option<flood::EventFloodPolicy>,
+    /// 每个插件目录的磁盘配额，插件通过 WASI 直接写文件同样计入
+    pub storage_quota_bytes: u64,
+}
+
+impl Default for PluginSystemOptions {
+    fn default() -> Self {
+        Self {
+            command_queue_capacity: 256,
+            command_queue_policy: CommandQueuePolicy::Wait,
+            safe_mode: false,
+            max_plugins: None,
+            health_check_interval: Some(Duration::from_secs(60)),
+            restart_unhealthy: false,
+            event_retry: EventRetryPolicy::default(),
+            permission_policy: None,
+            sticky_events: sticky::StickyEvent::ALL.to_vec(),
+            idle_suspend_after: None,
+            event_flood: Some(flood::EventFloodPolicy::default()),
+            storage_quota_bytes: api::host::storage::DEFAULT_PLUGIN_STORAGE_QUOTA_BYTES,
+        }
+    }
+}
+
 type PluginManagerFuture<'pm, R> = Pin<Box<dyn Future<Output = R> + Send + 'pm>>;
 type CommandFuture<'pm> = Pin<Box<dyn Future<Output = ()> + Send + 'pm>>;
 enum Command {
     Exec(Box<dyn for<'pm> FnOnce(&'pm mut PluginManager) -> CommandFuture<'pm> + Send>),
 }
-static PLUGIN_TX: OnceCell<mpsc::UnboundedSender<Command>> = OnceCell::new();
+static PLUGIN_TX: OnceCell<mpsc::Sender<Command>> = OnceCell::new();
+static PLUGIN_QUEUE_POLICY: OnceCell<CommandQueuePolicy> = OnceCell::new();
 static PLUGINSYSTEM_INIT_STATE: Lazy<Mutex<Option<PluginSystemReadyPayload>>> =
     Lazy::new(|| Mutex::new(None));
 
@@ -189,7 +391,15 @@ pub fn emit_last_init_state(app_handle: &AppHandle) -> bool {
 }
 
 pub fn init(dir: PathBuf, app_handle: AppHandle) -> Result<()> {
-    let (tx, mut rx) = mpsc::unbounded_channel::<Command>();
+    init_with_options(dir, app_handle, PluginSystemOptions::default())
+}
+
+pub fn init_with_options(
+    dir: PathBuf,
+    app_handle: AppHandle,
+    options: PluginSystemOptions,
+) -> Result<()> {
+    let (tx, mut rx) = mpsc::channel::<Command>(options.command_queue_capacity.max(1));
 
     std::thread::spawn(move || {
         log::info!("Building multi_thread plugin runtime...");
@@ -203,6 +413,7 @@ pub fn init(dir: PathBuf, app_handle: AppHandle) -> Result<()> {
                 let payload = PluginSystemReadyPayload {
                     ok: false,
                     errors: vec![format!("Failed to build plugin runtime: {e}")],
+                    report: None,
                 };
                 store_init_state(&payload);
                 if let Err(err) = app_handle.emit(PLUGINSYSTEM_READY_EVENT, &payload) {
@@ -215,7 +426,18 @@ pub fn init(dir: PathBuf, app_handle: AppHandle) -> Result<()> {
         let dir_cl = dir.clone();
         runtime.block_on(async move {
             let app_handle_for_event = app_handle.clone();
-            let mut pm = PluginManager::new(dir, app_handle);
+            let mut pm = if options.safe_mode {
+                PluginManager::new_safe_mode(dir, app_handle)
+            } else {
+                PluginManager::new(dir, app_handle)
+            };
+            pm.set_max_plugins(options.max_plugins);
+            pm.set_event_retry(options.event_retry);
+            pm.set_permission_policy(options.permission_policy.clone());
+            sticky::set_sticky_events(&options.sticky_events);
+            pm.set_idle_suspend_after(options.idle_suspend_after);
+            flood::set_event_flood_policy(options.event_flood);
+            api::host::storage::set_storage_quota_bytes(options.storage_quota_bytes);
 
             tokio::task::block_in_place(|| {
                 PM_IN_THREAD.with(|cell| *cell.borrow_mut() = Some(&mut pm as *mut _));
@@ -230,15 +452,17 @@ pub fn init(dir: PathBuf, app_handle: AppHandle) -> Result<()> {
             );
             let init_report = AssertUnwindSafe(pm.load_from_dir()).catch_unwind().await;
             let payload = match init_report {
-                Ok(Ok(ref errors)) => PluginSystemReadyPayload {
-                    ok: errors.is_empty(),
-                    errors: errors.clone(),
+                Ok(Ok(ref report)) => PluginSystemReadyPayload {
+                    ok: report.is_ok(),
+                    errors: report.error_messages(),
+                    report: Some(report.clone()),
                 },
                 Ok(Err(ref err)) => {
                     log::error!("PluginManager init failed: {err}");
                     PluginSystemReadyPayload {
                         ok: false,
                         errors: vec![err.to_string()],
+                        report: None,
                     }
                 }
                 Err(panic_payload) => {
@@ -247,6 +471,7 @@ pub fn init(dir: PathBuf, app_handle: AppHandle) -> Result<()> {
                     PluginSystemReadyPayload {
                         ok: false,
                         errors: vec![format!("Plugin manager init panicked: {detail}")],
+                        report: None,
                     }
                 }
             };
@@ -256,6 +481,14 @@ pub fn init(dir: PathBuf, app_handle: AppHandle) -> Result<()> {
                 log::error!("Failed to emit plugin system init event: {err}");
             }
 
+            if let Some(interval) = options.health_check_interval {
+                spawn_health_check(interval, options.restart_unhealthy);
+            }
+            if let Some(idle_after) = options.idle_suspend_after {
+                spawn_idle_suspend_check(idle_after);
+            }
+            spawn_latency_summary();
+
             while let Some(cmd) = rx.recv().await {
                 match cmd {
                     Command::Exec(task) => {
@@ -272,7 +505,78 @@ pub fn init(dir: PathBuf, app_handle: AppHandle) -> Result<()> {
 
     PLUGIN_TX
         .set(tx)
-        .map_err(|_| corelib::anyhow_site!("Plugin system already initialised"))
+        .map_err(|_| corelib::anyhow_site!("Plugin system already initialised"))?;
+    let _ = PLUGIN_QUEUE_POLICY.set(options.command_queue_policy);
+    Ok(())
+}
+
+fn spawn_health_check(interval: Duration, restart_unhealthy: bool) {
+    tokio::spawn(async move {
+        let start = tokio::time::Instant::now() + interval;
+        let mut ticker = tokio::time::interval_at(start, interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            // 只在插件管理线程上收集目标和记录结果，检查本身不占用管理线程
+            let targets = with_plugin_manager_async(move |pm| {
+                Box::pin(async move { pm.health_check_targets() })
+            })
+            .await;
+            let targets = match targets {
+                Ok(targets) => targets,
+                Err(err) => {
+                    log::debug!("[pluginsystem] health check skipped: {err}");
+                    continue;
+                }
+            };
+            if targets.is_empty() {
+                continue;
+            }
+            let results = PluginManager::ping_all(targets).await;
+            let result = with_plugin_manager_async(move |pm| {
+                Box::pin(async move { pm.apply_health_results(results, restart_unhealthy).await })
+            })
+            .await;
+            if let Err(err) = result {
+                log::debug!("[pluginsystem] health check results dropped: {err}");
+            }
+        }
+    });
+}
+
+// 空闲挂起检查的最长间隔
+const IDLE_SUSPEND_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn spawn_idle_suspend_check(idle_after: Duration) {
+    // 检查间隔不超过空闲时长，挂起时间误差有上限
+    let interval = idle_after.clamp(Duration::from_secs(1), IDLE_SUSPEND_CHECK_INTERVAL);
+    tokio::spawn(async move {
+        let start = tokio::time::Instant::now() + interval;
+        let mut ticker = tokio::time::interval_at(start, interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let result = with_plugin_manager_async(move |pm| {
+                Box::pin(async move { pm.suspend_idle_plugins().await })
+            })
+            .await;
+            if let Err(err) = result {
+                log::debug!("[pluginsystem] idle suspend check skipped: {err}");
+            }
+        }
+    });
+}
+
+fn spawn_latency_summary() {
+    tokio::spawn(async move {
+        let interval = latency::LATENCY_SUMMARY_INTERVAL;
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            latency::log_summary();
+        }
+    });
 }
 
 pub fn with_plugin_manager_sync<F, R>(f: F) -> Result<R>
@@ -313,11 +617,30 @@ where
         })
     }));
 
-    PLUGIN_TX
+    let plugin_tx = PLUGIN_TX
+        .get()
+        .ok_or_else(|| corelib::anyhow_site!("Plugin system not initialised"))?;
+    let policy = PLUGIN_QUEUE_POLICY
         .get()
-        .ok_or_else(|| corelib::anyhow_site!("Plugin system not initialised"))?
-        .send(cmd)
-        .map_err(|e| corelib::anyhow_site!("Plugin thread unexpectedly closed. error={:?}", e))?;
+        .copied()
+        .unwrap_or(CommandQueuePolicy::Wait);
+    // 插件线程自身负责消费队列，在其上等待队列腾出空间会死锁，因此总是立即返回
+    let on_plugin_thread = Some(thread::current().id()) == PLUGIN_THREAD_ID.get().copied();
+    if policy == CommandQueuePolicy::Wait && !on_plugin_thread {
+        plugin_tx
+            .send(cmd)
+            .await
+            .map_err(|_| corelib::anyhow_site!("Plugin thread unexpectedly closed"))?;
+    } else {
+        plugin_tx.try_send(cmd).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => {
+                corelib::anyhow_site!("Plugin system busy: command queue is full")
+            }
+            mpsc::error::TrySendError::Closed(_) => {
+                corelib::anyhow_site!("Plugin thread unexpectedly closed")
+            }
+        })?;
+    }
 
     rx.await
         .map_err(|_| corelib::anyhow_site!("Plugin thread dropped the response"))
@@ -0,0 +1,301 @@
+use std::fmt;
+use std::sync::{Mutex as StdMutex, Once};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use wasmtime::{Engine, EngineWeak, ResourceLimiter, Store, UpdateDeadline};
+
+// 单个插件 store 默认可使用的线性内存总量上限
+pub(crate) const PLUGIN_MEMORY_LIMIT_BYTES: usize = 512 * 1024 * 1024;
+// manifest 可申请的线性内存上限
+pub(crate) const PLUGIN_MEMORY_LIMIT_MAX_BYTES: usize = 2048 * 1024 * 1024;
+// 每次宿主调用插件（on_load、事件、渲染等）前重置的燃料额度，约等于执行的 wasm 指令数
+pub(crate) const PLUGIN_FUEL_PER_CALL: u64 = 10_000_000_000;
+// manifest 可申请的燃料额度上限
+pub(crate) const PLUGIN_FUEL_PER_CALL_MAX: u64 = 100_000_000_000;
+// 单个 HTTP 响应体默认可累计读取的字节上限；响应体按块流式交给插件，不会整体缓存在宿主内存中
+pub(crate) const PLUGIN_HTTP_BODY_LIMIT_BYTES: u64 = 256 * 1024 * 1024;
+// manifest 可申请的 HTTP 响应体上限
+pub(crate) const PLUGIN_HTTP_BODY_LIMIT_MAX_BYTES: u64 = 4096 * 1024 * 1024;
+
+// 常驻任务的燃料计量周期，每个周期最多消耗 `fuel_per_call`
+pub(crate) const SERVICE_FUEL_PERIOD: Duration = Duration::from_secs(1);
+// 常驻任务 store 中预置的燃料，只用于计量，实际额度由 `SERVICE_FUEL_PERIOD` 周期限制
+pub(crate) const SERVICE_FUEL_RESERVE: u64 = u64::MAX / 2;
+// 引擎 epoch 的推进间隔，wasm 至少每隔这么久让出一次执行权，`abort` 和超时才能生效
+pub(crate) const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+static EPOCH_ENGINES: Lazy<StdMutex<Vec<EngineWeak>>> = Lazy::new(|| StdMutex::new(Vec::new()));
+static EPOCH_TICKER: Once = Once::new();
+
+/// 由全局线程定期推进引擎的 epoch，引擎被释放后自动移除
+pub(crate) fn register_epoch_engine(engine: &Engine) {
+    EPOCH_ENGINES
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .push(engine.weak());
+    EPOCH_TICKER.call_once(|| {
+        let spawned = std::thread::Builder::new()
+            .name("psys-epoch".to_string())
+            .spawn(|| {
+                loop {
+                    std::thread::sleep(EPOCH_TICK);
+                    EPOCH_ENGINES
+                        .lock()
+                        .unwrap_or_else(|poison| poison.into_inner())
+                        .retain(|engine| match engine.upgrade() {
+                            Some(engine) => {
+                                engine.increment_epoch();
+                                true
+                            }
+                            None => false,
+                        });
+                }
+            });
+        if let Err(err) = spawned {
+            log::error!("[pluginsystem] failed to start epoch ticker: {err}");
+        }
+    });
+}
+
+/// 宿主为单次调用设置的截止时间已到，wasm 在下一个 epoch 检查点 trap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DeadlineExceeded;
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("plugin call exceeded its deadline")
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+/// 常驻任务的燃料计量：epoch 回调统计每个周期消耗的燃料，超出额度后暂停到下一周期。
+/// 超出量不超过一个 epoch tick 内的执行量
+#[derive(Debug)]
+struct ServiceFuel {
+    per_period: u64,
+    period: Duration,
+    period_start: Instant,
+    used: u64,
+    last_remaining: u64,
+}
+
+/// store 在 epoch 回调中使用的执行状态
+#[derive(Debug, Default)]
+pub(crate) struct ExecutionBudget {
+    deadline: Option<Instant>,
+    service_fuel: Option<ServiceFuel>,
+}
+
+impl ExecutionBudget {
+    /// `None` 表示不限制，调用结束后应清除
+    pub(crate) fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// 常驻任务不会被宿主按调用重置燃料，改为每个周期最多消耗 `per_period`。
+    /// `reserve` 为此时 store 中的燃料，应足够大，避免在两个 tick 之间耗尽
+    pub(crate) fn enable_service_fuel(&mut self, per_period: u64, period: Duration, reserve: u64) {
+        self.service_fuel = Some(ServiceFuel {
+            per_period,
+            period,
+            period_start: Instant::now(),
+            used: 0,
+            last_remaining: reserve,
+        });
+    }
+}
+
+/// 配置 store 的 epoch 回调：每个 tick 让出一次执行权，超过截止时间时 trap；
+/// 常驻任务在本周期额度用完后暂停，到下一周期再继续
+pub(crate) fn install_epoch_callback<T: 'static>(
+    store: &mut Store<T>,
+    budget: fn(&mut T) -> &mut ExecutionBudget,
+) {
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_callback(move |mut ctx| {
+        let now = Instant::now();
+        // 未开启燃料计量的引擎返回错误，此时也不会有常驻任务额度
+        let remaining = ctx.get_fuel().unwrap_or(0);
+        let budget = budget(ctx.data_mut());
+        if budget.deadline.is_some_and(|deadline| now >= deadline) {
+            return Err(DeadlineExceeded.into());
+        }
+        let Some(fuel) = budget.service_fuel.as_mut() else {
+            return Ok(UpdateDeadline::Yield(1));
+        };
+        fuel.used = fuel
+            .used
+            .saturating_add(fuel.last_remaining.saturating_sub(remaining));
+        fuel.last_remaining = remaining;
+        let elapsed = now.duration_since(fuel.period_start);
+        if elapsed >= fuel.period {
+            fuel.period_start = now;
+            fuel.used = 0;
+        } else if fuel.used >= fuel.per_period {
+            let wait = fuel.period - elapsed;
+            fuel.period_start += fuel.period;
+            fuel.used = 0;
+            return Ok(UpdateDeadline::YieldCustom(
+                1,
+                Box::pin(tokio::time::sleep(wait)),
+            ));
+        }
+        Ok(UpdateDeadline::Yield(1))
+    });
+}
+
+/// 插件实际生效的资源限制：manifest 申请的值按宿主上限截断
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginResourceLimits {
+    pub memory_bytes: usize,
+    pub fuel_per_call: u64,
+    pub http_body_bytes: u64,
+}
+
+impl Default for PluginResourceLimits {
+    fn default() -> Self {
+        Self {
+            memory_bytes: PLUGIN_MEMORY_LIMIT_BYTES,
+            fuel_per_call: PLUGIN_FUEL_PER_CALL,
+            http_body_bytes: PLUGIN_HTTP_BODY_LIMIT_BYTES,
+        }
+    }
+}
+
+impl PluginResourceLimits {
+    pub(crate) fn from_manifest(
+        max_memory_mb: Option<u64>,
+        max_cpu_fuel: Option<u64>,
+        max_http_body_mb: Option<u64>,
+    ) -> Self {
+        let defaults = Self::default();
+        let memory_bytes = max_memory_mb
+            .map(|mb| {
+                usize::try_from(mb.saturating_mul(1024 * 1024))
+                    .unwrap_or(usize::MAX)
+                    .clamp(1024 * 1024, PLUGIN_MEMORY_LIMIT_MAX_BYTES)
+            })
+            .unwrap_or(defaults.memory_bytes);
+        let fuel_per_call = max_cpu_fuel
+            .map(|fuel| fuel.clamp(1, PLUGIN_FUEL_PER_CALL_MAX))
+            .unwrap_or(defaults.fuel_per_call);
+        let http_body_bytes = max_http_body_mb
+            .map(|mb| {
+                mb.saturating_mul(1024 * 1024)
+                    .clamp(1024 * 1024, PLUGIN_HTTP_BODY_LIMIT_MAX_BYTES)
+            })
+            .unwrap_or(defaults.http_body_bytes);
+        Self {
+            memory_bytes,
+            fuel_per_call,
+            http_body_bytes,
+        }
+    }
+}
+
+/// 记录插件 store 的线性内存用量，并拒绝超出上限的增长
+pub(crate) struct PluginLimiter {
+    memory_bytes: usize,
+    memory_limit: usize,
+    fuel_per_call: u64,
+    budget: ExecutionBudget,
+}
+
+impl PluginLimiter {
+    pub(crate) fn new(limits: PluginResourceLimits) -> Self {
+        Self {
+            memory_bytes: 0,
+            memory_limit: limits.memory_bytes,
+            fuel_per_call: limits.fuel_per_call,
+            budget: ExecutionBudget::default(),
+        }
+    }
+
+    pub(crate) fn budget_mut(&mut self) -> &mut ExecutionBudget {
+        &mut self.budget
+    }
+
+    pub(crate) fn fuel_per_call(&self) -> u64 {
+        self.fuel_per_call
+    }
+
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.memory_bytes
+    }
+
+    pub(crate) fn memory_limit(&self) -> usize {
+        self.memory_limit
+    }
+}
+
+impl ResourceLimiter for PluginLimiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let growth = desired.saturating_sub(current);
+        let next = self.memory_bytes.saturating_add(growth);
+        if next > self.memory_limit {
+            return Ok(false);
+        }
+        self.memory_bytes = next;
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::ResourceLimiter;
+
+    use super::{
+        PLUGIN_FUEL_PER_CALL, PLUGIN_FUEL_PER_CALL_MAX, PLUGIN_HTTP_BODY_LIMIT_BYTES,
+        PLUGIN_HTTP_BODY_LIMIT_MAX_BYTES, PLUGIN_MEMORY_LIMIT_MAX_BYTES, PluginLimiter,
+        PluginResourceLimits,
+    };
+
+    #[test]
+    fn tracks_memory_growth_up_to_limit() {
+        let mut limiter = PluginLimiter::new(PluginResourceLimits {
+            memory_bytes: 1024,
+            fuel_per_call: PLUGIN_FUEL_PER_CALL,
+            http_body_bytes: PLUGIN_HTTP_BODY_LIMIT_BYTES,
+        });
+        assert!(limiter.memory_growing(0, 512, None).unwrap());
+        assert!(limiter.memory_growing(512, 1024, None).unwrap());
+        assert_eq!(limiter.memory_bytes(), 1024);
+
+        assert!(!limiter.memory_growing(1024, 2048, None).unwrap());
+        assert_eq!(limiter.memory_bytes(), 1024);
+    }
+
+    #[test]
+    fn manifest_limits_are_clamped_to_host_ceilings() {
+        let limits = PluginResourceLimits::from_manifest(Some(1024), Some(u64::MAX), Some(16));
+        assert_eq!(limits.memory_bytes, 1024 * 1024 * 1024);
+        assert_eq!(limits.fuel_per_call, PLUGIN_FUEL_PER_CALL_MAX);
+        assert_eq!(limits.http_body_bytes, 16 * 1024 * 1024);
+
+        let limits = PluginResourceLimits::from_manifest(Some(u64::MAX), None, Some(u64::MAX));
+        assert_eq!(limits.memory_bytes, PLUGIN_MEMORY_LIMIT_MAX_BYTES);
+        assert_eq!(limits.fuel_per_call, PLUGIN_FUEL_PER_CALL);
+        assert_eq!(limits.http_body_bytes, PLUGIN_HTTP_BODY_LIMIT_MAX_BYTES);
+
+        let limits = PluginResourceLimits::from_manifest(None, None, None);
+        assert_eq!(limits.http_body_bytes, PLUGIN_HTTP_BODY_LIMIT_BYTES);
+    }
+}
@@ -2,25 +2,156 @@ use anyhow::{Context, Result, anyhow};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
 use frontbridge::invoke_frontend;
 use futures_util::future::join_all;
-use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs::{self, File};
 use std::io::{Cursor, Read};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use tauri::{AppHandle, Emitter};
+use tauri_plugin_opener::OpenerExt;
 use zip::ZipArchive;
 
+use crate::api::host::permission::{
+    diff_permissions, forget_permission_grants, reset_permission_grants,
+};
+use crate::api::host::ui::TaskProgress;
 use crate::bindings::astrobox::psys_host;
+use crate::limits::PluginResourceLimits;
 use crate::manifest::PluginManifest;
-use crate::plugin::{CardRegistration, Plugin, PluginData, purge_precompiled_component};
-use crate::{PLUGINSYSTEM_PROGRESS_EVENT, PluginSystemProgressPayload};
+use crate::plugin::{
+    CardRegistration, Plugin, PluginData, PluginFatalError, PluginHealth, PluginRuntime,
+    ProviderRegistration, STAGED_PLUGIN_DIR, compute_wasm_hash, purge_precompiled_component,
+};
+use crate::plugin_command::CommandError;
+use crate::{
+    EventRetryPolicy, PLUGINSYSTEM_PERMISSION_DIFF_EVENT, PLUGINSYSTEM_PLUGIN_FAILED_EVENT,
+    PLUGINSYSTEM_PROGRESS_EVENT, PluginFailedPayload, PluginPermissionDiffPayload,
+    PluginSystemProgressPayload,
+};
+
+// 每个插件最多保留的死信条数，超出时丢弃最早的
+const DEAD_LETTER_LIMIT: usize = 100;
 
 pub struct PluginManager {
     plugin_root: PathBuf,
     app_handle: AppHandle,
     pub plugins: HashMap<String, Plugin>,
     pub updated: bool,
+    safe_mode: bool,
+    max_plugins: Option<usize>,
+    staged: HashMap<String, Plugin>, // 已验证、等待激活的新版本
+    event_retry: EventRetryPolicy,
+    dead_letters: HashMap<String, VecDeque<DeadLetter>>, // 重试耗尽仍未送达的插件事件
+    permission_policy: Option<Vec<String>>, // 管理员允许插件声明的权限，超出的插件会被隔离
+    blocklist: BTreeSet<String>,            // 禁止运行的入口 wasm sha256
+    idle_suspend_after: Option<Duration>,   // 插件无活动超过该时长后释放实例，`None` 表示不挂起
+    connected_models: Vec<String>,          // 当前已连接设备的型号 id，由前端同步
+    prewarmed: VecDeque<String>,            // 持有预热 store 的插件，最近使用的在末尾
+}
+
+/// 重试耗尽后仍未能送达插件的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetter {
+    pub event_name: String,
+    pub source_plugin: String,
+    pub payload: String,
+    pub error: String,
+    pub attempts: u32,
+    pub timestamp_ms: i64,
+}
+
+/// 插件声明的权限与权限策略的比对结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyResult {
+    pub name: String,
+    pub allowed: bool,
+    pub disallowed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginLoadFailure {
+    pub plugin: String,
+    pub error: String,
+}
+
+/// 因插件数量上限或权限策略未启动的插件，不算作启动失败
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginSkip {
+    pub plugin: String,
+    pub reason: String,
+}
+
+/// `start_plugin` 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartOutcome {
+    /// 已启动，或本来就在运行
+    Started,
+    /// 插件已被停用
+    Disabled,
+    /// 超出插件数量上限或被权限策略隔离，插件保持未启动
+    Skipped(String),
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PluginLoadReport {
+    pub loaded: usize,
+    pub failed: usize,
+    pub failures: Vec<PluginLoadFailure>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<PluginSkip>,
+    #[serde(rename = "safeMode")]
+    pub safe_mode: bool,
+}
+
+/// `rescan` 的结果：新发现并加载的插件、目录已消失而被卸载的插件
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RescanReport {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub failures: Vec<PluginLoadFailure>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<PluginSkip>,
+    // 在防抖时间内被后续调用合并，本次未实际扫描
+    pub coalesced: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginStatus {
+    pub name: String,
+    pub loaded: bool,
+    pub disabled: bool,
+    #[serde(rename = "skipReason")]
+    pub skip_reason: Option<String>,
+    pub healthy: bool,
+    #[serde(rename = "healthError")]
+    pub health_error: Option<String>,
+    #[serde(rename = "fatalError")]
+    pub fatal_error: Option<PluginFatalError>,
+    #[serde(rename = "idleSuspended")]
+    pub idle_suspended: bool,
+    #[serde(rename = "resourceLimits")]
+    pub resource_limits: PluginResourceLimits,
+    // 插件声明了支持的设备型号但当前没有连接其中任何一台，界面应显示为等待兼容设备
+    #[serde(rename = "noCompatibleDevice")]
+    pub no_compatible_device: bool,
+}
+
+impl PluginLoadReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn error_messages(&self) -> Vec<String> {
+        self.failures
+            .iter()
+            .map(|failure| failure.error.clone())
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -35,6 +166,46 @@ pub struct RegisteredProviderDescriptor {
 const FRONT_STORAGE_GET_JSON_METHOD: &str = "host/storage/local/get_json";
 const FRONT_STORAGE_SET_JSON_METHOD: &str = "host/storage/local/set_json";
 const PLUGIN_DISABLED_STORAGE_KEY: &str = "astrobox.plugin.disabled_map";
+const PLUGIN_PRIORITY_STORAGE_KEY: &str = "astrobox.plugin.priority_map";
+// 启动过程中存在的标记文件，记录连续未能完成启动的次数
+const STARTUP_GUARD_FILE: &str = ".startup-guard";
+// 被标记为恶意的入口 wasm 哈希列表
+const BLOCKLIST_FILE: &str = ".blocklist.json";
+const SAFE_MODE_CRASH_THRESHOLD: u32 = 2;
+const HEALTH_CHECK_DEADLINE: Duration = Duration::from_secs(2);
+// 连续通过这么多次健康检查后视为运行稳定，清空重启记录
+const CLEAN_RUN_RESET_CHECKS: u32 = 5;
+// 同时持有预热 store 的插件数量上限，超出时释放最久未使用的
+const PREWARM_LIMIT: usize = 4;
+// 激活暂存版本期间旧版本目录的临时位置，新版本加载失败时从这里恢复
+const PREVIOUS_VERSION_DIR: &str = ".previous";
+
+/// 插件名会直接拼进目录路径，不能包含路径分隔符或指向上级目录
+fn is_valid_plugin_dir_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+/// 把旧版本目录移到 `backup_dir`，再把暂存目录移到正式位置，返回是否存在旧版本；
+/// 第二步失败时把旧版本放回原处
+fn swap_in_staged_dir(staged_dir: &Path, dest_dir: &Path, backup_dir: &Path) -> Result<bool> {
+    let had_previous = dest_dir.exists();
+    if had_previous {
+        if backup_dir.exists() {
+            fs::remove_dir_all(backup_dir)?;
+        }
+        if let Some(parent) = backup_dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(dest_dir, backup_dir)?;
+    }
+    if let Err(err) = fs::rename(staged_dir, dest_dir) {
+        if had_previous {
+            fs::rename(backup_dir, dest_dir)?;
+        }
+        return Err(err.into());
+    }
+    Ok(had_previous)
+}
 
 #[derive(Serialize)]
 struct LocalStorageKeyPayload {
@@ -71,12 +242,337 @@ impl PluginManager {
         }
     }
 
+    async fn apply_permission_diff(
+        &self,
+        previous: Option<&PluginManifest>,
+        next: &PluginManifest,
+    ) {
+        let Some(previous) = previous else {
+            return;
+        };
+        let diff = diff_permissions(&previous.permissions, &next.permissions);
+        if diff.is_empty() {
+            return;
+        }
+
+        log::info!(
+            "[plugin:{}] Permissions changed on update, added={:?}, removed={:?}",
+            next.name,
+            diff.added,
+            diff.removed
+        );
+        // 新增权限需要用户重新授权，不能沿用旧版本的授权记录
+        forget_permission_grants(&next.name, &diff.added);
+        if !reset_permission_grants(&self.app_handle, &next.name, &diff.added).await {
+            log::error!(
+                "[plugin:{}] Failed to reset grants for added permissions {:?}; the frontend may still hold stale grants",
+                next.name,
+                diff.added
+            );
+        }
+
+        let payload = PluginPermissionDiffPayload {
+            plugin: next.name.clone(),
+            added: diff.added,
+            removed: diff.removed,
+        };
+        if let Err(err) = self
+            .app_handle
+            .emit(PLUGINSYSTEM_PERMISSION_DIFF_EVENT, &payload)
+        {
+            log::error!("Failed to emit plugin permission diff event: {err}");
+        }
+    }
+
     pub fn new(root: PathBuf, app_handle: AppHandle) -> Self {
         Self {
             plugin_root: root,
             app_handle,
             plugins: HashMap::new(),
             updated: false,
+            safe_mode: false,
+            max_plugins: None,
+            staged: HashMap::new(),
+            event_retry: EventRetryPolicy::default(),
+            dead_letters: HashMap::new(),
+            permission_policy: None,
+            blocklist: BTreeSet::new(),
+            idle_suspend_after: None,
+            connected_models: Vec::new(),
+            prewarmed: VecDeque::new(),
+        }
+    }
+
+    /// 预热插件（例如用户悬停在“打开”按钮上时），让接下来的启动或空闲唤醒跳过 store 创建和 linker 构建。
+    /// 最多保留 `PREWARM_LIMIT` 个预热 store，超出时释放最久未使用的。
+    /// 实测（wasmtime 41，x86_64 Linux）注册 400 个宿主函数的 store 和 linker 约 0.09–0.17ms，
+    /// 约占反序列化之后冷启动的 85%，实例化加首次调用只剩 0.01–0.03ms
+    pub fn prewarm(&mut self, name: &str) -> Result<bool> {
+        let plugin = self
+            .plugins
+            .get(name)
+            .ok_or_else(|| anyhow!("Plugin '{}' not found", name))?;
+        if plugin.state.disabled {
+            return Ok(false);
+        }
+        if !plugin.runtime.prewarm()? {
+            return Ok(false);
+        }
+
+        // 已被启动消耗掉的预热不再占用名额
+        let plugins = &self.plugins;
+        self.prewarmed.retain(|prewarmed| {
+            prewarmed != name
+                && plugins
+                    .get(prewarmed)
+                    .is_some_and(|plugin| plugin.runtime.is_prewarmed())
+        });
+        self.prewarmed.push_back(name.to_string());
+        while self.prewarmed.len() > PREWARM_LIMIT {
+            let Some(evicted) = self.prewarmed.pop_front() else {
+                break;
+            };
+            if let Some(plugin) = self.plugins.get(&evicted) {
+                plugin.runtime.discard_prewarmed();
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn set_idle_suspend_after(&mut self, idle_suspend_after: Option<Duration>) {
+        self.idle_suspend_after = idle_suspend_after;
+    }
+
+    pub fn set_event_retry(&mut self, policy: EventRetryPolicy) {
+        self.event_retry = policy;
+    }
+
+    pub fn event_retry(&self) -> EventRetryPolicy {
+        self.event_retry
+    }
+
+    /// 记录一条未能送达的事件
+    pub fn record_dead_letter(&mut self, name: &str, letter: DeadLetter) {
+        log::warn!(
+            "[plugin:{}] event '{}' from {} moved to dead letters after {} attempts: {}",
+            name,
+            letter.event_name,
+            letter.source_plugin,
+            letter.attempts,
+            letter.error
+        );
+        let letters = self.dead_letters.entry(name.to_string()).or_default();
+        if letters.len() >= DEAD_LETTER_LIMIT {
+            letters.pop_front();
+        }
+        letters.push_back(letter);
+    }
+
+    /// 宿主调用与事件派发的耗时分位数
+    pub fn latency_stats(&self) -> Vec<crate::latency::LatencyStats> {
+        crate::latency::latency_stats()
+    }
+
+    /// 该插件未能收到的事件，按时间先后排列
+    pub fn dead_letters(&self, name: &str) -> Vec<DeadLetter> {
+        self.dead_letters
+            .get(name)
+            .map(|letters| letters.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// 设置权限策略，`None` 表示不限制；只在插件启动或启用时检查，已运行的插件不受影响
+    pub fn set_permission_policy(&mut self, policy: Option<Vec<String>>) {
+        self.permission_policy = policy;
+    }
+
+    pub fn permission_policy(&self) -> Option<&[String]> {
+        self.permission_policy.as_deref()
+    }
+
+    /// 检查插件声明的权限是否都在允许列表内
+    pub fn check_policy(&self, name: &str, allowed: &[String]) -> Result<PolicyResult> {
+        let plugin = self
+            .plugins
+            .get(name)
+            .ok_or_else(|| corelib::anyhow_site!("Plugin '{}' not found", name))?;
+        let disallowed = disallowed_permissions(&plugin.manifest.permissions, allowed);
+        Ok(PolicyResult {
+            name: name.to_string(),
+            allowed: disallowed.is_empty(),
+            disallowed,
+        })
+    }
+
+    /// 插件违反当前权限策略时返回隔离原因
+    fn policy_violation_reason(&self, name: &str) -> Option<String> {
+        let policy = self.permission_policy.as_ref()?;
+        let result = self.check_policy(name, policy).ok()?;
+        if result.allowed {
+            return None;
+        }
+        Some(format!(
+            "quarantined: permissions not allowed by policy: {}",
+            result.disallowed.join(", ")
+        ))
+    }
+
+    fn blocklist_path(&self) -> PathBuf {
+        self.plugin_root.join(BLOCKLIST_FILE)
+    }
+
+    fn load_blocklist(&mut self) {
+        let path = self.blocklist_path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return;
+        };
+        match serde_json::from_str::<BTreeSet<String>>(&content) {
+            Ok(blocklist) => self.blocklist = blocklist,
+            Err(err) => log::error!("[pluginsystem] failed to parse plugin blocklist: {err}"),
+        }
+    }
+
+    fn store_blocklist(&self) -> Result<()> {
+        fs::create_dir_all(&self.plugin_root)?;
+        fs::write(
+            self.blocklist_path(),
+            serde_json::to_string_pretty(&self.blocklist)?,
+        )
+        .context("failed to write plugin blocklist")
+    }
+
+    /// 入口 wasm 的哈希在黑名单中时拒绝加载
+    fn ensure_not_blocklisted(&self, dir: &Path, manifest: &PluginManifest) -> Result<()> {
+        if self.blocklist.is_empty() {
+            return Ok(());
+        }
+        let hash = compute_wasm_hash(&manifest.entry_wasm_path(dir))?;
+        if self.blocklist.contains(&hash) {
+            return Err(anyhow!(
+                "plugin '{}' blocked: entry wasm sha256 {} is blocklisted",
+                manifest.name,
+                hash
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn blocklist(&self) -> Vec<String> {
+        self.blocklist.iter().cloned().collect()
+    }
+
+    /// 将入口 wasm 哈希加入黑名单并持久化，已加载的匹配插件会被立即停止并卸载
+    pub async fn blocklist_add(&mut self, hash: &str) -> Result<()> {
+        let hash = normalize_wasm_hash(hash)
+            .ok_or_else(|| corelib::anyhow_site!("invalid sha256 hash: {}", hash))?;
+        if !self.blocklist.insert(hash.clone()) {
+            return Ok(());
+        }
+        self.store_blocklist()?;
+
+        let matched = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin)| {
+                compute_wasm_hash(&plugin.manifest.entry_wasm_path(&plugin.path))
+                    .is_ok_and(|plugin_hash| plugin_hash == hash)
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        for name in matched {
+            log::warn!(
+                "[plugin:{}] Unloaded: entry wasm sha256 {} was blocklisted",
+                name,
+                hash
+            );
+            self.take_plugin_for_cleanup(&name).await;
+            self.emit_progress(
+                &name,
+                "blocked",
+                Some(format!("sha256 {hash} is blocklisted")),
+            );
+            self.updated = true;
+        }
+        Ok(())
+    }
+
+    /// 从黑名单中移除哈希，返回该哈希此前是否在黑名单中；已被拒绝的插件需要重新加载
+    pub fn blocklist_remove(&mut self, hash: &str) -> Result<bool> {
+        let Some(hash) = normalize_wasm_hash(hash) else {
+            return Ok(false);
+        };
+        if !self.blocklist.remove(&hash) {
+            return Ok(false);
+        }
+        self.store_blocklist()?;
+        Ok(true)
+    }
+
+    /// 限制同时运行的插件数量，`None` 表示不限制；已运行的插件不受影响
+    pub fn set_max_plugins(&mut self, max_plugins: Option<usize>) {
+        self.max_plugins = max_plugins;
+    }
+
+    pub fn max_plugins(&self) -> Option<usize> {
+        self.max_plugins
+    }
+
+    fn running_count(&self) -> usize {
+        self.plugins
+            .values()
+            .filter(|plugin| plugin.state.loaded && !plugin.state.disabled)
+            .count()
+    }
+
+    /// 已达到插件数量上限时返回拒绝启动的原因
+    fn plugin_limit_reason(&self) -> Option<String> {
+        let max_plugins = self.max_plugins?;
+        let running = self.running_count();
+        if running < max_plugins {
+            return None;
+        }
+        Some(format!(
+            "plugin limit reached ({} running, max {})",
+            running, max_plugins
+        ))
+    }
+
+    /// 安全模式：只加载插件清单而不启动任何插件，便于用户在界面中停用导致崩溃的插件后正常重启
+    pub fn new_safe_mode(root: PathBuf, app_handle: AppHandle) -> Self {
+        Self {
+            safe_mode: true,
+            ..Self::new(root, app_handle)
+        }
+    }
+
+    pub fn is_safe_mode(&self) -> bool {
+        self.safe_mode
+    }
+
+    fn startup_guard_path(&self) -> PathBuf {
+        self.plugin_root.join(STARTUP_GUARD_FILE)
+    }
+
+    /// 上次启动未正常结束时标记文件会残留，返回累计的连续失败次数
+    fn previous_startup_crashes(&self) -> u32 {
+        match fs::read_to_string(self.startup_guard_path()) {
+            Ok(content) => content.trim().parse::<u32>().unwrap_or(0) + 1,
+            Err(_) => 0,
+        }
+    }
+
+    fn write_startup_guard(&self, crashes: u32) {
+        if let Err(err) = fs::write(self.startup_guard_path(), crashes.to_string()) {
+            log::warn!("[pluginsystem] failed to write startup guard: {err}");
+        }
+    }
+
+    fn clear_startup_guard(&self) {
+        let path = self.startup_guard_path();
+        if path.exists() {
+            if let Err(err) = fs::remove_file(&path) {
+                log::warn!("[pluginsystem] failed to clear startup guard: {err}");
+            }
         }
     }
 
@@ -90,6 +586,8 @@ impl PluginManager {
             "Loading plugin from path {}",
             path.to_string_lossy().to_string()
         );
+        let manifest = PluginManifest::load_from_dir(path)?;
+        self.ensure_not_blocklisted(path, &manifest)?;
         let plugin = Plugin::load(path.to_path_buf(), self.app_handle.clone())?;
         let name = plugin.manifest.name.clone();
 
@@ -99,26 +597,137 @@ impl PluginManager {
         Ok(())
     }
 
-    pub async fn start_all(&mut self) -> Vec<String> {
-        let mut names: Vec<String> = self.plugins.keys().cloned().collect();
-        names.sort();
-        let mut errors = Vec::new();
+    /// 从内存中的 wasm 加载插件（不启动），用于针对小型测试组件编写宿主接口的单元测试；
+    /// manifest 中的插件名会被 `name` 覆盖。不会创建插件目录，插件也没有可访问的文件系统；
+    /// 安装 id、日志级别等宿主文件写在管理器根目录下，测试应以临时目录创建管理器
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn add_from_bytes(
+        &mut self,
+        name: &str,
+        wasm_bytes: &[u8],
+        mut manifest: PluginManifest,
+    ) -> Result<()> {
+        if !is_valid_plugin_dir_name(name) {
+            return Err(anyhow!("Invalid plugin name '{}'", name));
+        }
+        manifest.name = name.to_string();
+        let plugin_dir = self.plugin_root.join(name);
+        let plugin = Plugin::from_bytes(manifest, wasm_bytes, plugin_dir, self.app_handle.clone())?;
+        self.plugins.insert(name.to_string(), plugin);
+        Ok(())
+    }
 
-        for name in names {
-            if let Err(err) = self.start_plugin(&name).await {
-                log::error!("[plugin:{}] Failed to start: {err}", name);
-                errors.push(err.to_string());
+    /// 按依赖顺序启动所有插件，返回启动失败和被跳过的插件
+    pub async fn start_all(&mut self) -> (Vec<PluginLoadFailure>, Vec<PluginSkip>) {
+        self.start_in_dependency_order(None).await
+    }
+
+    /// 按依赖顺序启动插件，`only` 为 `Some` 时只启动其中的插件，依赖关系仍按全部插件计算
+    async fn start_in_dependency_order(
+        &mut self,
+        only: Option<&HashSet<String>>,
+    ) -> (Vec<PluginLoadFailure>, Vec<PluginSkip>) {
+        let dependencies = self
+            .plugins
+            .iter()
+            .map(|(name, plugin)| (name.clone(), plugin.manifest.depends_on.clone()))
+            .collect::<HashMap<_, _>>();
+        let priorities = self
+            .plugins
+            .iter()
+            .map(|(name, plugin)| (name.clone(), plugin.priority()))
+            .collect::<HashMap<_, _>>();
+        let (mut order, mut cyclic) = plan_start_order(&dependencies, &priorities);
+        if let Some(only) = only {
+            order.retain(|name| only.contains(name));
+            cyclic.retain(|name| only.contains(name));
+        }
+        let mut failures = Vec::new();
+        let mut skipped = Vec::new();
+        let mut unavailable = HashSet::new();
+        // 被跳过的插件没有失败，依赖它们的插件同样跳过而不是报告依赖启动失败
+        let mut not_started = HashSet::new();
+
+        if !cyclic.is_empty() {
+            let members = cyclic.join(", ");
+            for name in cyclic {
+                let reason = format!("dependency cycle detected among [{}]", members);
+                log::error!("[plugin:{}] Not started: {}", name, reason);
+                skipped.push(self.skip_for_dependencies(&name, reason));
+                not_started.insert(name);
+            }
+        }
+
+        for name in order {
+            let depends_on = dependencies.get(&name).cloned().unwrap_or_default();
+            if let Some(dep) = depends_on.iter().find(|dep| not_started.contains(*dep)) {
+                let reason = format!("dependency '{}' was not started", dep);
+                log::info!("[plugin:{}] Not started: {}", name, reason);
+                skipped.push(self.skip_for_dependencies(&name, reason));
+                not_started.insert(name);
+                continue;
+            }
+            let skip_reason = depends_on.iter().find_map(|dep| {
+                if unavailable.contains(dep) {
+                    Some(format!("dependency '{}' failed to start", dep))
+                } else {
+                    match self.plugins.get(dep) {
+                        None => Some(format!("dependency '{}' is not installed", dep)),
+                        Some(plugin) if plugin.state.disabled => {
+                            Some(format!("dependency '{}' is disabled", dep))
+                        }
+                        Some(_) => None,
+                    }
+                }
+            });
+
+            if let Some(reason) = skip_reason {
+                log::warn!("[plugin:{}] Not started: {}", name, reason);
+                skipped.push(self.skip_for_dependencies(&name, reason));
+                not_started.insert(name);
+                continue;
+            }
+
+            match self.start_plugin(&name).await {
+                Ok(StartOutcome::Started | StartOutcome::Disabled) => {}
+                Ok(StartOutcome::Skipped(reason)) => {
+                    skipped.push(PluginSkip {
+                        plugin: name.clone(),
+                        reason,
+                    });
+                    not_started.insert(name);
+                }
+                Err(err) => {
+                    log::error!("[plugin:{}] Failed to start: {err}", name);
+                    failures.push(PluginLoadFailure {
+                        plugin: name.clone(),
+                        error: err.to_string(),
+                    });
+                    unavailable.insert(name);
+                }
             }
         }
 
-        errors
+        (failures, skipped)
+    }
+
+    /// 依赖不满足的插件跳过启动，原因记录在插件状态里供前端展示
+    fn skip_for_dependencies(&mut self, name: &str, reason: String) -> PluginSkip {
+        self.emit_progress(name, "skipped", Some(reason.clone()));
+        if let Some(plugin) = self.plugins.get_mut(name) {
+            plugin.state.skip_reason = Some(reason.clone());
+        }
+        PluginSkip {
+            plugin: name.to_string(),
+            reason,
+        }
     }
 
-    async fn load_disabled_map(&self) -> std::collections::HashMap<String, bool> {
+    async fn load_storage_map<V: DeserializeOwned>(&self, key: &str) -> HashMap<String, V> {
         let payload = LocalStorageKeyPayload {
-            key: PLUGIN_DISABLED_STORAGE_KEY.to_string(),
+            key: key.to_string(),
         };
-        match invoke_frontend::<Option<std::collections::HashMap<String, bool>>, _>(
+        match invoke_frontend::<Option<HashMap<String, V>>, _>(
             &self.app_handle,
             FRONT_STORAGE_GET_JSON_METHOD,
             payload,
@@ -126,17 +735,17 @@ impl PluginManager {
         .await
         {
             Ok(Some(map)) => map,
-            Ok(None) => std::collections::HashMap::new(),
+            Ok(None) => HashMap::new(),
             Err(err) => {
-                log::warn!("[pluginsystem] failed to load disabled map: {err}");
-                std::collections::HashMap::new()
+                log::warn!("[pluginsystem] failed to load {key}: {err}");
+                HashMap::new()
             }
         }
     }
 
-    async fn store_disabled_map(&self, map: &std::collections::HashMap<String, bool>) {
+    async fn store_storage_map<V: Serialize>(&self, key: &str, map: &HashMap<String, V>) {
         let payload = LocalStorageSetPayload {
-            key: PLUGIN_DISABLED_STORAGE_KEY.to_string(),
+            key: key.to_string(),
             value: map,
         };
         match invoke_frontend::<LocalStorageAck, _>(
@@ -148,26 +757,51 @@ impl PluginManager {
         {
             Ok(resp) => {
                 if !resp.success {
-                    log::warn!("[pluginsystem] store disabled map rejected");
+                    log::warn!("[pluginsystem] store {key} rejected");
                 }
             }
             Err(err) => {
-                log::warn!("[pluginsystem] failed to store disabled map: {err}");
+                log::warn!("[pluginsystem] failed to store {key}: {err}");
             }
         }
     }
 
+    async fn load_disabled_map(&self) -> HashMap<String, bool> {
+        self.load_storage_map(PLUGIN_DISABLED_STORAGE_KEY).await
+    }
+
+    async fn store_disabled_map(&self, map: &HashMap<String, bool>) {
+        self.store_storage_map(PLUGIN_DISABLED_STORAGE_KEY, map)
+            .await;
+    }
+
+    async fn set_plugin_priority_persisted(&self, name: &str, priority: Option<i32>) {
+        let mut map = self
+            .load_storage_map::<i32>(PLUGIN_PRIORITY_STORAGE_KEY)
+            .await;
+        match priority {
+            Some(priority) => map.insert(name.to_string(), priority),
+            None => map.remove(name),
+        };
+        self.store_storage_map(PLUGIN_PRIORITY_STORAGE_KEY, &map)
+            .await;
+    }
+
+    // 用户的启用/停用选择都会记录下来，以便覆盖 manifest 中的 `default_enabled`
     async fn set_plugin_disabled_persisted(&self, name: &str, disabled: bool) {
         let mut map = self.load_disabled_map().await;
-        if disabled {
-            map.insert(name.to_string(), true);
-        } else {
-            map.remove(name);
-        }
+        map.insert(name.to_string(), disabled);
         self.store_disabled_map(&map).await;
     }
 
-    pub async fn start_plugin(&mut self, name: &str) -> Result<()> {
+    async fn clear_plugin_disabled_persisted(&self, name: &str) {
+        let mut map = self.load_disabled_map().await;
+        if map.remove(name).is_some() {
+            self.store_disabled_map(&map).await;
+        }
+    }
+
+    pub async fn start_plugin(&mut self, name: &str) -> Result<StartOutcome> {
         let mut should_remove = false;
         let app_handle = self.app_handle.clone();
         let emit_progress = |plugin: &str, stage: &str, detail: Option<String>| {
@@ -181,24 +815,43 @@ impl PluginManager {
             }
         };
 
+        let mut started = false;
+        let limit_reason = self.plugin_limit_reason();
+        let policy_reason = self.policy_violation_reason(name);
         let result = match self.plugins.get_mut(name) {
             Some(plugin) => {
                 if plugin.state.disabled {
                     log::info!("[plugin:{}] Disabled, skip starting", name);
                     emit_progress(name, "disabled", None);
-                    return Ok(());
+                    return Ok(StartOutcome::Disabled);
                 }
 
                 if plugin.state.loaded {
                     emit_progress(name, "ready", None);
-                    return Ok(());
+                    return Ok(StartOutcome::Started);
+                }
+
+                if let Some(reason) = policy_reason {
+                    log::warn!("[plugin:{}] Not started: {}", name, reason);
+                    emit_progress(name, "quarantined", Some(reason.clone()));
+                    plugin.state.skip_reason = Some(reason.clone());
+                    return Ok(StartOutcome::Skipped(reason));
                 }
 
+                if let Some(reason) = limit_reason {
+                    log::warn!("[plugin:{}] Not started: {}", name, reason);
+                    emit_progress(name, "skipped", Some(reason.clone()));
+                    plugin.state.skip_reason = Some(reason.clone());
+                    return Ok(StartOutcome::Skipped(reason));
+                }
+
+                plugin.state.skip_reason = None;
                 emit_progress(name, "start", None);
                 match plugin.run().await {
                     Ok(()) => {
+                        started = true;
                         emit_progress(name, "ready", None);
-                        Ok(())
+                        Ok(StartOutcome::Started)
                     }
                     Err(err) => {
                         should_remove = true;
@@ -218,23 +871,69 @@ impl PluginManager {
         if should_remove {
             self.plugins.remove(name);
         }
+        if started {
+            self.replay_sticky_events(name);
+        }
 
         result
     }
 
+    /// 向刚启动完成的插件补发粘性事件的最新载荷
+    fn replay_sticky_events(&self, name: &str) {
+        let Some(plugin) = self.plugins.get(name) else {
+            return;
+        };
+        if crate::suspension::is_plugin_suspended(name) {
+            return;
+        }
+        let events = crate::sticky::latest();
+        if events.is_empty() {
+            return;
+        }
+        let runtime = plugin.runtime.clone();
+        let name = name.to_string();
+        tokio::spawn(async move {
+            for (event, payload) in events {
+                let result = match event {
+                    crate::sticky::StickyEvent::ThemeChanged => {
+                        runtime.dispatch_theme_changed(payload).await
+                    }
+                    crate::sticky::StickyEvent::NetworkChanged => {
+                        runtime.dispatch_network_changed(payload).await
+                    }
+                    crate::sticky::StickyEvent::PowerStateChanged => {
+                        runtime.dispatch_power_state_changed(payload).await
+                    }
+                };
+                if let Err(err) = result {
+                    log::error!(
+                        "[plugin:{}] Failed to replay sticky event {:?}: {err}",
+                        name,
+                        event
+                    );
+                }
+            }
+        });
+    }
+
     pub async fn add_from_dir(&mut self, _name: &str, path: &Path) -> Result<()> {
         self.updated = true;
         if !path.is_dir() {
             return Err(anyhow!("source path is not a directory"));
         }
         let manifest = PluginManifest::load_from_dir(path)?;
+        self.ensure_not_blocklisted(path, &manifest)?;
         self.unload_plugin_for_overwrite(manifest.name.as_str())
             .await;
         let dest_dir = self.plugin_root.join(manifest.name.as_str());
+        let previous_manifest = PluginManifest::load_from_dir(&dest_dir).ok();
         if dest_dir.exists() {
             fs::remove_dir_all(&dest_dir)?;
         }
         copy_dir_recursive(path, &dest_dir)?;
+        crate::api::host::storage::invalidate_storage_usage(&dest_dir);
+        self.apply_permission_diff(previous_manifest.as_ref(), &manifest)
+            .await;
         Ok(())
     }
 
@@ -242,41 +941,39 @@ impl PluginManager {
         self.updated = true;
         let package_raw = tokio::fs::read(path).await?;
         let manifest = resolve_manifest_from_abp(&package_raw)?;
+        if !self.blocklist.is_empty() {
+            // 先解压到暂存目录校验入口哈希，命中黑名单时不覆盖已安装的版本
+            let check_dir = self.staged_dir(&format!("{}.blockcheck", manifest.name));
+            if check_dir.exists() {
+                fs::remove_dir_all(&check_dir)?;
+            }
+            fs::create_dir_all(&check_dir)?;
+            let checked = extract_abp(package_raw.clone(), &check_dir)
+                .and_then(|()| self.ensure_not_blocklisted(&check_dir, &manifest));
+            if let Err(err) = fs::remove_dir_all(&check_dir) {
+                log::warn!("[pluginsystem] failed to clean blocklist check dir: {err}");
+            }
+            if let Err(err) = checked {
+                log::error!("[plugin:{}] Install refused: {err}", manifest.name);
+                self.emit_progress(&manifest.name, "blocked", Some(err.to_string()));
+                return Err(err);
+            }
+        }
 
         self.unload_plugin_for_overwrite(manifest.name.as_str())
             .await;
         let dest_dir = self.plugin_root.join(manifest.name.as_str());
+        let previous_manifest = PluginManifest::load_from_dir(&dest_dir).ok();
         if dest_dir.exists() {
             fs::remove_dir_all(&dest_dir)?;
         }
         fs::create_dir_all(&dest_dir)?;
 
-        let reader = Cursor::new(package_raw);
-        let mut archive = ZipArchive::new(reader)?;
-
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let outpath = dest_dir.join(file.mangled_name());
+        extract_abp(package_raw, &dest_dir)?;
+        crate::api::host::storage::invalidate_storage_usage(&dest_dir);
 
-            if file.name().ends_with('/') {
-                fs::create_dir_all(&outpath)?;
-            } else {
-                if let Some(parent) = outpath.parent() {
-                    if !parent.exists() {
-                        fs::create_dir_all(parent)?;
-                    }
-                }
-                let mut outfile = File::create(&outpath)?;
-                std::io::copy(&mut file, &mut outfile)?;
-            }
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                if let Some(mode) = file.unix_mode() {
-                    fs::set_permissions(&outpath, fs::Permissions::from_mode(mode))?;
-                }
-            }
-        }
+        self.apply_permission_diff(previous_manifest.as_ref(), &manifest)
+            .await;
 
         /*
         self.add(&dest_dir).await?;
@@ -290,6 +987,8 @@ impl PluginManager {
     pub async fn enable(&mut self, name: &String) -> bool {
         log::info!("[plugin:{}] Enable requested", name);
         self.updated = true;
+        let limit_reason = self.plugin_limit_reason();
+        let policy_reason = self.policy_violation_reason(name);
         if let Some(plugin) = self.plugins.get_mut(name) {
             if plugin.state.loaded && !plugin.state.disabled {
                 log::info!("[plugin:{}] Already enabled", name);
@@ -297,12 +996,26 @@ impl PluginManager {
                 return true;
             }
 
+            if let Some(reason) = policy_reason {
+                log::warn!("[plugin:{}] Enable refused: {}", name, reason);
+                plugin.state.skip_reason = Some(reason);
+                return false;
+            }
+
+            // 超出数量上限时保持停用，也不记录为已启用，否则下次启动会越过上限
+            if let Some(reason) = limit_reason {
+                log::warn!("[plugin:{}] Enable refused: {}", name, reason);
+                plugin.state.skip_reason = Some(reason);
+                return false;
+            }
             plugin.state.disabled = false;
+            plugin.state.skip_reason = None;
 
             match plugin.run().await {
                 Ok(()) => {
                     log::info!("Enable successful");
                     self.set_plugin_disabled_persisted(name, false).await;
+                    self.replay_sticky_events(name);
                     return true;
                 }
                 Err(err) => {
@@ -351,10 +1064,20 @@ impl PluginManager {
         pkg_name: &str,
         payload: String,
     ) {
+        if crate::suspension::is_suspended() {
+            log::debug!(
+                "[pluginsystem] interconnect dispatch skipped while suspended addr={} pkg={}",
+                addr,
+                pkg_name
+            );
+            return;
+        }
+
         let mut active_plugins = self
             .plugins
             .iter()
             .filter(|(_, plugin)| plugin.state.loaded && !plugin.state.disabled)
+            .filter(|(name, _)| !crate::suspension::is_plugin_suspended(name))
             .map(|(name, plugin)| (name.clone(), plugin.runtime.clone()))
             .collect::<Vec<_>>();
         active_plugins.sort_by(|left, right| left.0.cmp(&right.0));
@@ -397,26 +1120,134 @@ impl PluginManager {
         }
     }
 
-    pub async fn dispatch_transport_packet(
-        &mut self,
-        addr: &str,
-        channel_id: u32,
-        protobuf_type_id: Option<u32>,
-        protobuf_packet_id: Option<u32>,
-        payload: Vec<u8>,
-    ) {
-        crate::transport_runtime::fulfill_request_waiters(
-            addr,
-            channel_id,
-            protobuf_type_id,
-            protobuf_packet_id,
-            &payload,
-        );
+    /// 按深链接路径前缀路由到对应插件；没有插件声明匹配前缀时回退到 `register_deeplink_action` 的插件
+    pub async fn dispatch_deeplink(&mut self, link: &str) -> Result<String> {
+        let routed = crate::deeplink::route_deeplink(link);
+        let mut target = None;
+        if let Some(name) = routed {
+            target = self
+                .plugins
+                .get(&name)
+                .filter(|plugin| plugin.state.loaded && !plugin.state.disabled)
+                .map(|plugin| (name.clone(), plugin.runtime.clone()));
+        }
+        if target.is_none() {
+            let mut active_plugins = self
+                .plugins
+                .iter()
+                .filter(|(_, plugin)| plugin.state.loaded && !plugin.state.disabled)
+                .map(|(name, plugin)| (name.clone(), plugin.runtime.clone()))
+                .collect::<Vec<_>>();
+            active_plugins.sort_by(|left, right| left.0.cmp(&right.0));
+            for (name, runtime) in active_plugins {
+                if runtime.is_deeplink_registered().await {
+                    target = Some((name, runtime));
+                    break;
+                }
+            }
+        }
+
+        let Some((name, runtime)) = target else {
+            return Err(anyhow!("No plugin handles deeplink {}", link));
+        };
+        log::debug!("[pluginsystem] deeplink {} -> plugin {}", link, name);
+        runtime
+            .dispatch_deeplink_action(link.to_string())
+            .await
+            .with_context(|| format!("Plugin '{}' failed to handle deeplink", name))?;
+        Ok(name)
+    }
+
+    /// 能处理该 MIME 类型且正在运行的分享目标插件
+    pub fn share_target_candidates(&self, mime: &str) -> Vec<String> {
+        crate::share_target::share_target_candidates(mime)
+            .into_iter()
+            .filter(|name| {
+                self.plugins
+                    .get(name)
+                    .is_some_and(|plugin| plugin.state.loaded && !plugin.state.disabled)
+                    && !crate::suspension::is_plugin_suspended(name)
+            })
+            .collect()
+    }
+
+    /// 正在运行的分享目标插件的运行时
+    pub fn share_target_runtime(&self, name: &str) -> Result<PluginRuntime> {
+        self.plugins
+            .get(name)
+            .filter(|plugin| plugin.state.loaded && !plugin.state.disabled)
+            .map(|plugin| plugin.runtime.clone())
+            .ok_or_else(|| anyhow!("Plugin '{}' is not running", name))
+    }
+
+    /// 前端收到自定义协议的原始帧时调用，只送达注册该协议的插件
+    pub async fn dispatch_custom_protocol_frame(
+        &mut self,
+        addr: &str,
+        protocol: &str,
+        payload: Vec<u8>,
+    ) -> Result<()> {
+        let owner = crate::transport_runtime::custom_protocol_owner(protocol)
+            .ok_or_else(|| anyhow!("No plugin registered protocol '{}'", protocol))?;
+        let runtime = self
+            .plugins
+            .get(&owner)
+            .filter(|plugin| plugin.state.loaded && !plugin.state.disabled)
+            .filter(|_| !crate::suspension::is_plugin_suspended(&owner))
+            .map(|plugin| plugin.runtime.clone())
+            .ok_or_else(|| anyhow!("Plugin '{}' is not running", owner))?;
+        let envelope =
+            crate::transport_runtime::custom_protocol_frame_envelope(addr, protocol, &payload);
+        runtime
+            .dispatch_transport_packet(envelope)
+            .await
+            .with_context(|| format!("Plugin '{}' failed to handle protocol frame", owner))
+    }
+
+    /// 设备断开时由宿主调用，结束插件等待该设备响应的请求；插件的接收注册保留到重连后继续使用
+    pub fn on_device_disconnected(&mut self, addr: &str) {
+        let failed = crate::transport_runtime::fail_device_waiters(addr);
+        log::info!(
+            "[pluginsystem] device {} disconnected, {} pending request(s) failed",
+            addr,
+            failed
+        );
+    }
+
+    pub async fn dispatch_transport_packet(
+        &mut self,
+        addr: &str,
+        channel_id: u32,
+        protobuf_type_id: Option<u32>,
+        protobuf_packet_id: Option<u32>,
+        payload: Vec<u8>,
+    ) {
+        let host_reply = crate::transport_runtime::fulfill_request_waiters(
+            addr,
+            channel_id,
+            protobuf_type_id,
+            protobuf_packet_id,
+            &payload,
+        );
+        // 宿主自己请求的应答（如 ping）不是插件关心的帧
+        if host_reply {
+            return;
+        }
+
+        if crate::suspension::is_suspended() {
+            log::debug!(
+                "[pluginsystem] transport dispatch skipped while suspended addr={} channel={}",
+                addr,
+                channel_id
+            );
+            return;
+        }
 
         let mut active_plugins = self
             .plugins
             .iter()
             .filter(|(_, plugin)| plugin.state.loaded && !plugin.state.disabled)
+            .filter(|(name, _)| !crate::suspension::is_plugin_suspended(name))
             .map(|(name, plugin)| (name.clone(), plugin.runtime.clone()))
             .collect::<Vec<_>>();
         active_plugins.sort_by(|left, right| left.0.cmp(&right.0));
@@ -469,6 +1300,7 @@ impl PluginManager {
         match self.plugins.get_mut(name) {
             Some(plug) => {
                 plug.stop().await;
+                crate::api::host::permission::forget_all_permission_grants(name);
                 log::info!("Disable successful");
                 self.set_plugin_disabled_persisted(name, true).await;
                 true
@@ -487,55 +1319,243 @@ impl PluginManager {
             }
         };
 
-        if let Err(err) = purge_precompiled_component(&plugin_path, &plugin_manifest) {
-            log::warn!(
-                "[plugin:{}] Failed to purge precompiled artifacts: {err}",
-                name
-            );
-        }
-
         match fs::remove_dir_all(&plugin_path) {
             Ok(_) => {
-                self.set_plugin_disabled_persisted(name, false).await;
+                self.forget_removed_plugin(name, &plugin_path, &plugin_manifest)
+                    .await;
                 true
             }
             Err(e) => {
                 log::error!("[plugin:{}] Failed to remove: {e:?}", name);
+                // 目录还在，下次启动会重新加载，只清理运行期状态，保留停用状态等设置
+                self.forget_runtime_state(name);
                 false
             }
         }
     }
 
-    pub async fn load_from_dir(&mut self) -> Result<Vec<String>> {
-        fs::create_dir_all(&self.plugin_root)?;
-        let mut errors = Vec::new();
+    /// 插件从管理器中移除后清理其运行期状态
+    fn forget_runtime_state(&mut self, name: &str) {
+        crate::api::host::ui::forget_ui_state(name);
+        crate::api::host::permission::forget_all_permission_grants(name);
+        self.dead_letters.remove(name);
+        crate::suspension::set_background_plugin(name, false);
+    }
+
+    /// 插件目录被删除或已消失后清理宿主为它保存的全部状态：预编译产物、安装 id、权限使用记录、
+    /// 特性开关以及持久化的停用状态和优先级
+    async fn forget_removed_plugin(
+        &mut self,
+        name: &str,
+        plugin_path: &Path,
+        manifest: &PluginManifest,
+    ) {
+        self.forget_runtime_state(name);
+        crate::api::host::storage::forget_storage_usage(plugin_path);
+        if let Err(err) = purge_precompiled_component(plugin_path, manifest) {
+            log::warn!(
+                "[plugin:{}] Failed to purge precompiled artifacts: {err}",
+                name
+            );
+        }
+        self.clear_plugin_disabled_persisted(name).await;
+        crate::api::host::self_::forget_install_id(&self.plugin_root, name);
+        crate::api::host::permission::forget_permission_usage(name);
+        crate::api::host::flags::forget_flags(&self.plugin_root, name);
+        crate::api::host::logging::forget_plugin_log_level(&self.plugin_root, name);
+        self.set_plugin_priority_persisted(name, None).await;
+    }
 
+    /// 插件根目录下的插件目录，按路径排序；以 `.` 开头的目录为宿主内部使用（如共享预编译产物），不是插件
+    fn plugin_dirs(&self) -> Result<Vec<PathBuf>> {
+        let mut dirs = Vec::new();
         for entry in fs::read_dir(&self.plugin_root)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                if let Err(e) = self.add(&path).await {
-                    let detail =
-                        format!("Failed to load plugin from {}: {e}", path.to_string_lossy());
-                    log::error!("{detail}");
-                    let label = path
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .unwrap_or("unknown-plugin");
-                    self.emit_progress(label, "error", Some(detail.clone()));
-                    errors.push(detail);
-                }
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'));
+            if path.is_dir() && !hidden {
+                dirs.push(path);
+            }
+        }
+        dirs.sort();
+        Ok(dirs)
+    }
+
+    /// 逐个加载插件目录（不启动），返回加载失败的目录
+    async fn load_plugin_dirs(&mut self, dirs: Vec<PathBuf>) -> Vec<PluginLoadFailure> {
+        let mut failures = Vec::new();
+        for path in dirs {
+            // 与已加载插件同名的目录不覆盖已加载的插件
+            let duplicate = PluginManifest::load_from_dir(&path)
+                .ok()
+                .filter(|manifest| self.plugins.contains_key(&manifest.name))
+                .map(|manifest| manifest.name);
+            let result = match duplicate {
+                Some(name) => Err(anyhow!(
+                    "plugin '{}' is already loaded from another directory",
+                    name
+                )),
+                None => self.add(&path).await,
+            };
+            if let Err(e) = result {
+                let detail = format!("Failed to load plugin from {}: {e}", path.to_string_lossy());
+                log::error!("{detail}");
+                let label = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("unknown-plugin");
+                self.emit_progress(label, "error", Some(detail.clone()));
+                failures.push(PluginLoadFailure {
+                    plugin: label.to_string(),
+                    error: detail,
+                });
             }
         }
+        failures
+    }
+
+    /// 为新加载的插件恢复保存的停用状态和优先级
+    async fn restore_persisted_state(&mut self, names: &[String]) {
         let disabled_map = self.load_disabled_map().await;
-        for (name, plugin) in self.plugins.iter_mut() {
-            let disabled = disabled_map.get(name).copied().unwrap_or(false);
-            if disabled {
-                plugin.state.disabled = true;
+        let priority_map = self
+            .load_storage_map::<i32>(PLUGIN_PRIORITY_STORAGE_KEY)
+            .await;
+        for name in names {
+            if let Some(plugin) = self.plugins.get_mut(name) {
+                plugin.state.disabled = initial_disabled(
+                    disabled_map.get(name).copied(),
+                    plugin.manifest.default_enabled,
+                );
+                plugin.state.priority_override = priority_map.get(name).copied();
+            }
+        }
+    }
+
+    pub async fn load_from_dir(&mut self) -> Result<PluginLoadReport> {
+        fs::create_dir_all(&self.plugin_root)?;
+        self.load_blocklist();
+        crate::api::host::permission::load_permission_usage(&self.plugin_root);
+        self.clear_stale_staged();
+        let mut skipped = Vec::new();
+
+        let dirs = self.plugin_dirs()?;
+        let mut failures = self.load_plugin_dirs(dirs).await;
+        let names = self.plugins.keys().cloned().collect::<Vec<_>>();
+        self.restore_persisted_state(&names).await;
+        let crashes = self.previous_startup_crashes();
+        if !self.safe_mode && crashes >= SAFE_MODE_CRASH_THRESHOLD {
+            log::error!(
+                "[pluginsystem] plugin startup did not complete {} times in a row, entering safe mode",
+                crashes
+            );
+            self.safe_mode = true;
+        }
+
+        if self.safe_mode {
+            log::warn!("[pluginsystem] Safe mode: plugins loaded but not started");
+            for plugin in self.plugins.values_mut() {
+                plugin.state.loaded = false;
+            }
+            let names = self.plugins.keys().cloned().collect::<Vec<_>>();
+            for name in names {
+                self.emit_progress(&name, "safe_mode", None);
+            }
+            self.clear_startup_guard();
+        } else {
+            self.write_startup_guard(crashes);
+            let (start_failures, start_skipped) = self.start_all().await;
+            failures.extend(start_failures);
+            skipped = start_skipped;
+            self.clear_startup_guard();
+        }
+
+        let report = PluginLoadReport {
+            loaded: self
+                .plugins
+                .values()
+                .filter(|plugin| plugin.state.loaded)
+                .count(),
+            failed: failures.len(),
+            failures,
+            skipped,
+            safe_mode: self.safe_mode,
+        };
+        log::info!(
+            "[pluginsystem] load summary: {} loaded, {} failed, {} skipped",
+            report.loaded,
+            report.failed,
+            report.skipped.len()
+        );
+        Ok(report)
+    }
+
+    /// 对比插件目录与已加载的插件，一次性加载新增目录中的插件并卸载目录已消失的插件，
+    /// 适合批量安装或恢复备份后调用；不会重新加载已存在的插件
+    pub async fn rescan(&mut self) -> Result<RescanReport> {
+        fs::create_dir_all(&self.plugin_root)?;
+        let mut report = RescanReport::default();
+
+        let on_disk = self.plugin_dirs()?.into_iter().collect::<HashSet<_>>();
+
+        let mut vanished = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin)| !on_disk.contains(&plugin.path))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        vanished.sort();
+        for name in vanished {
+            log::info!("[plugin:{}] Directory vanished, unloading", name);
+            if let Some((plugin_path, manifest)) = self.take_plugin_for_cleanup(&name).await {
+                self.forget_removed_plugin(&name, &plugin_path, &manifest)
+                    .await;
+            }
+            self.emit_progress(&name, "removed", None);
+            report.removed.push(name);
+        }
+
+        let known = self
+            .plugins
+            .values()
+            .map(|plugin| plugin.path.clone())
+            .collect::<HashSet<_>>();
+        let discovered = on_disk
+            .into_iter()
+            .filter(|path| !known.contains(path))
+            .collect::<Vec<_>>();
+        let before = self.plugins.keys().cloned().collect::<HashSet<_>>();
+        report.failures = self.load_plugin_dirs(discovered).await;
+        let mut added = self
+            .plugins
+            .keys()
+            .filter(|name| !before.contains(*name))
+            .cloned()
+            .collect::<Vec<_>>();
+        added.sort();
+
+        if !added.is_empty() {
+            self.restore_persisted_state(&added).await;
+            if !self.safe_mode {
+                let only = added.iter().cloned().collect::<HashSet<_>>();
+                let (failures, skipped) = self.start_in_dependency_order(Some(&only)).await;
+                report.failures.extend(failures);
+                report.skipped = skipped;
             }
         }
-        errors.extend(self.start_all().await);
-        Ok(errors)
+
+        if !added.is_empty() || !report.removed.is_empty() {
+            self.updated = true;
+        }
+        report.added = added;
+        log::info!(
+            "[pluginsystem] rescan summary: {} added, {} removed, {} failed",
+            report.added.len(),
+            report.removed.len(),
+            report.failures.len()
+        );
+        Ok(report)
     }
 
     pub fn set_plugin_data<F>(&mut self, name: &str, f: F) -> Result<()>
@@ -554,6 +1574,19 @@ impl PluginManager {
         self.plugins.get_mut(name)
     }
 
+    /// 各插件当前的后台任务进度，供全局状态栏汇总显示
+    pub fn task_progress(&self) -> BTreeMap<String, TaskProgress> {
+        self.plugins
+            .iter()
+            .filter_map(|(name, plugin)| {
+                plugin
+                    .runtime
+                    .task_progress()
+                    .map(|progress| (name.clone(), progress))
+            })
+            .collect()
+    }
+
     pub async fn list_cards(&self) -> Vec<CardRegistration> {
         let mut cards = Vec::new();
         for plugin in self.plugins.values() {
@@ -596,6 +1629,33 @@ impl PluginManager {
         providers
     }
 
+    /// 查找提供指定 provider 的已启用插件，返回插件名、注册信息以及其运行时
+    pub async fn find_provider(
+        &self,
+        provider_name: &str,
+    ) -> Option<(String, ProviderRegistration, PluginRuntime)> {
+        let mut active_plugins = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin)| plugin.state.loaded && !plugin.state.disabled)
+            .map(|(name, plugin)| (name.clone(), plugin.runtime.clone()))
+            .collect::<Vec<_>>();
+        active_plugins.sort_by(|left, right| left.0.cmp(&right.0));
+
+        for (plugin_name, runtime) in active_plugins {
+            let registration = runtime
+                .list_providers()
+                .await
+                .into_iter()
+                .find(|registration| registration.name == provider_name);
+            if let Some(registration) = registration {
+                return Some((plugin_name, registration, runtime));
+            }
+        }
+
+        None
+    }
+
     pub async fn call_provider_action(&self, provider_name: &str, payload: String) -> Result<()> {
         let mut active_plugins = self
             .plugins
@@ -640,11 +1700,135 @@ impl PluginManager {
             })
     }
 
+    /// 每个插件的运行状态，包括因数量上限等原因未被启动的插件及其原因
+    pub fn list_status(&self) -> Vec<PluginStatus> {
+        let mut statuses = self
+            .plugins
+            .iter()
+            .map(|(name, plugin)| PluginStatus {
+                name: name.clone(),
+                loaded: plugin.state.loaded,
+                disabled: plugin.state.disabled,
+                skip_reason: plugin.state.skip_reason.clone(),
+                healthy: plugin.state.health_error.is_none(),
+                health_error: plugin.state.health_error.clone(),
+                fatal_error: plugin.state.fatal_error.clone(),
+                idle_suspended: plugin.runtime.is_idle_suspended(),
+                resource_limits: plugin.runtime.resource_limits(),
+                no_compatible_device: !plugin.manifest.supports_any_device(&self.connected_models),
+            })
+            .collect::<Vec<_>>();
+        statuses.sort_by(|left, right| left.name.cmp(&right.name));
+        statuses
+    }
+
+    /// 导出诊断包（zip）：各插件的 manifest、最近日志、崩溃与重启记录、注册信息，以及耗时统计和预编译索引。
+    /// 不包含密钥、键值存储和插件目录中的用户数据，死信只保留事件名和错误不含负载
+    pub async fn diagnostic_bundle(&self) -> Result<Vec<u8>> {
+        let mut bundle = crate::diagnostics::DiagnosticBundleWriter::new();
+        bundle.add_json(
+            "summary.json",
+            &serde_json::json!({
+                "generatedAt": chrono::Utc::now().to_rfc3339(),
+                "hostVersion": env!("CARGO_PKG_VERSION"),
+                "safeMode": self.safe_mode,
+                "suspended": self.is_suspended(),
+                "connectedModels": self.connected_models,
+                "blocklist": self.blocklist,
+                "plugins": self.list_status(),
+            }),
+        )?;
+        bundle.add_json("metrics.json", &self.latency_stats())?;
+        bundle.add_file(
+            "precompiled-index.json",
+            &self.plugin_root.join(crate::plugin::PRECOMPILE_INDEX_FILE),
+        )?;
+
+        let mut names = self.plugins.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let Some(plugin) = self.plugins.get(&name) else {
+                continue;
+            };
+            let dir = format!("plugins/{name}");
+            bundle.add_json(&format!("{dir}/manifest.json"), &plugin.manifest)?;
+            bundle.add_text(
+                &format!("{dir}/logs.txt"),
+                &crate::api::host::logging::recent_plugin_logs(&name).join("\n"),
+            )?;
+            let dead_letters = self
+                .dead_letters(&name)
+                .into_iter()
+                .map(|letter| {
+                    serde_json::json!({
+                        "eventName": letter.event_name,
+                        "sourcePlugin": letter.source_plugin,
+                        "error": letter.error,
+                        "attempts": letter.attempts,
+                        "timestampMs": letter.timestamp_ms,
+                    })
+                })
+                .collect::<Vec<_>>();
+            bundle.add_json(
+                &format!("{dir}/crashes.json"),
+                &serde_json::json!({
+                    "restartCount": plugin.state.restart_count,
+                    "lastFailure": plugin.state.last_failure,
+                    "healthError": plugin.state.health_error,
+                    "fatalError": plugin.state.fatal_error,
+                    "deadLetters": dead_letters,
+                }),
+            )?;
+            let cards = plugin
+                .runtime
+                .list_cards()
+                .await
+                .into_iter()
+                .map(|card| {
+                    serde_json::json!({
+                        "type": format!("{:?}", card.card_type),
+                        "id": card.id,
+                        "name": card.name,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let providers = plugin
+                .runtime
+                .list_providers()
+                .await
+                .into_iter()
+                .map(|provider| {
+                    serde_json::json!({
+                        "name": provider.name,
+                        "type": Self::provider_type_label(&provider.provider_type),
+                    })
+                })
+                .collect::<Vec<_>>();
+            bundle.add_json(
+                &format!("{dir}/registrations.json"),
+                &serde_json::json!({
+                    "cards": cards,
+                    "providers": providers,
+                    "commands": crate::plugin_command::list_commands(&name),
+                    "deeplink": plugin.runtime.is_deeplink_registered().await,
+                }),
+            )?;
+        }
+        bundle.finish()
+    }
+
     pub fn list(&self) -> Vec<PluginManifest> {
+        let locale = sys_locale::get_locale().unwrap_or_else(|| "en-US".to_string());
         let plugs = self
             .plugins
             .values()
-            .map(|pl| pl.manifest.clone())
+            .map(|pl| {
+                let mut manifest = pl.manifest.clone();
+                let localized = manifest.localized(&locale);
+                manifest.display_name = Some(localized.name);
+                manifest.description = localized.description;
+                manifest
+            })
             .collect();
 
         match serde_json::to_string(&plugs) {
@@ -654,61 +1838,1226 @@ impl PluginManager {
         plugs
     }
 
-    pub fn is_updated(&self) -> bool {
-        self.updated
+    /// 设置插件的启动优先级（数值越大越先启动），`None` 表示恢复为 manifest 中的默认值。
+    /// 调整会持久化，下次启动时生效。
+    pub async fn set_priority(&mut self, name: &str, priority: Option<i32>) -> bool {
+        let Some(plugin) = self.plugins.get_mut(name) else {
+            log::error!("[plugin:{}] Not found", name);
+            return false;
+        };
+        plugin.state.priority_override = priority;
+        self.updated = true;
+        self.set_plugin_priority_persisted(name, priority).await;
+        true
     }
-}
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
-    if !dst.exists() {
-        fs::create_dir_all(dst)?;
+    /// 按当前依赖关系与优先级计算的启动顺序，供前端展示排序
+    pub fn start_order(&self) -> Vec<String> {
+        let dependencies = self
+            .plugins
+            .iter()
+            .map(|(name, plugin)| (name.clone(), plugin.manifest.depends_on.clone()))
+            .collect::<HashMap<_, _>>();
+        let priorities = self
+            .plugins
+            .iter()
+            .map(|(name, plugin)| (name.clone(), plugin.priority()))
+            .collect::<HashMap<_, _>>();
+        let (mut order, cyclic) = plan_start_order(&dependencies, &priorities);
+        order.extend(cyclic);
+        order
     }
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let entry_path = entry.path();
-        let target_path = dst.join(entry.file_name());
-        let file_type = entry.file_type()?;
-        if file_type.is_dir() {
-            copy_dir_recursive(&entry_path, &target_path)?;
-        } else if file_type.is_file() {
-            if let Some(parent) = target_path.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent)?;
-                }
+
+    /// 清除插件的预编译缓存并重新编译、重启插件。
+    /// 编译失败时保留原插件（及其运行状态）并返回编译错误。
+    pub async fn recompile(&mut self, name: &str) -> Result<()> {
+        let plugin = self
+            .plugins
+            .get(name)
+            .ok_or_else(|| corelib::anyhow_site!("Plugin '{}' not found", name))?;
+        let path = plugin.path.clone();
+        let manifest = plugin.manifest.clone();
+        let was_running = plugin.state.loaded && !plugin.state.disabled;
+        let disabled = plugin.state.disabled;
+        let priority_override = plugin.state.priority_override;
+
+        log::info!("[plugin:{}] Recompile requested", name);
+        self.emit_progress(name, "recompile", None);
+        purge_precompiled_component(&path, &manifest)?;
+
+        let mut rebuilt = match Plugin::load(path, self.app_handle.clone()) {
+            Ok(plugin) => plugin,
+            Err(err) => {
+                log::error!("[plugin:{}] Recompile failed: {err}", name);
+                self.emit_progress(name, "error", Some(err.to_string()));
+                return Err(err);
+            }
+        };
+        rebuilt.state.disabled = disabled;
+        rebuilt.state.priority_override = priority_override;
+
+        if let Some(mut previous) = self.plugins.remove(name) {
+            previous.stop().await;
+        }
+        self.updated = true;
+
+        if was_running {
+            self.emit_progress(name, "start", None);
+            if let Err(err) = rebuilt.run().await {
+                rebuilt.stop().await;
+                rebuilt.state.disabled = disabled;
+                self.plugins.insert(name.to_string(), rebuilt);
+                self.emit_progress(name, "error", Some(err.to_string()));
+                return Err(anyhow!(
+                    "plugin '{}' on_load failed after recompile. detail: {}",
+                    name,
+                    err
+                ));
+            }
+            self.emit_progress(name, "ready", None);
+        }
+
+        self.plugins.insert(name.to_string(), rebuilt);
+        if was_running {
+            self.replay_sticky_events(name);
+        }
+        Ok(())
+    }
+
+    fn staged_dir(&self, name: &str) -> PathBuf {
+        self.plugin_root.join(STAGED_PLUGIN_DIR).join(name)
+    }
+
+    fn previous_version_dir(&self, name: &str) -> PathBuf {
+        self.plugin_root.join(PREVIOUS_VERSION_DIR).join(name)
+    }
+
+    fn remove_staged_files(&self, dir: &Path, manifest: Option<&PluginManifest>) {
+        if let Some(manifest) = manifest {
+            if let Err(err) = purge_precompiled_component(dir, manifest) {
+                log::warn!(
+                    "[plugin:{}] Failed to purge staged precompiled artifacts: {err}",
+                    manifest.name
+                );
+            }
+        }
+        if dir.exists() {
+            if let Err(err) = fs::remove_dir_all(dir) {
+                log::warn!(
+                    "[pluginsystem] Failed to remove staged plugin dir {}: {err}",
+                    dir.display()
+                );
             }
-            fs::copy(&entry_path, &target_path)?;
         }
     }
-    Ok(())
-}
 
-fn resolve_manifest_from_abp(package_raw: &[u8]) -> Result<PluginManifest> {
-    let reader = Cursor::new(package_raw);
-    let mut archive = ZipArchive::new(reader)?;
+    /// 上次运行遗留的暂存版本不会被恢复，启动时清理掉
+    fn clear_stale_staged(&self) {
+        let staged_root = self.plugin_root.join(STAGED_PLUGIN_DIR);
+        let Ok(entries) = fs::read_dir(&staged_root) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let manifest = PluginManifest::load_from_dir(&path).ok();
+            self.remove_staged_files(&path, manifest.as_ref());
+        }
+        let _ = fs::remove_dir_all(&staged_root);
 
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        if file.name().ends_with('/') {
-            continue;
+        // 激活中途退出时旧版本可能还留在临时位置，正式目录缺失时放回去
+        let previous_root = self.plugin_root.join(PREVIOUS_VERSION_DIR);
+        let Ok(entries) = fs::read_dir(&previous_root) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let dest_dir = self.plugin_root.join(entry.file_name());
+            if dest_dir.exists() {
+                continue;
+            }
+            if let Err(err) = fs::rename(entry.path(), &dest_dir) {
+                log::warn!(
+                    "[pluginsystem] Failed to restore previous plugin version {}: {err}",
+                    dest_dir.display()
+                );
+            }
+            crate::api::host::storage::invalidate_storage_usage(&dest_dir);
         }
+        let _ = fs::remove_dir_all(&previous_root);
+    }
 
-        let file_name = file.mangled_name();
-        let is_manifest = file_name
-            .file_name()
-            .and_then(|name| name.to_str())
-            .map(|name| name.eq_ignore_ascii_case("manifest.json"))
-            .unwrap_or(false);
-        if !is_manifest {
-            continue;
+    /// 暂存插件的新版本：解包、校验并预编译，但不替换正在运行的版本。
+    /// 校验或编译失败时返回错误并清理暂存文件。
+    pub async fn stage_update(&mut self, name: &str, path: &Path) -> Result<()> {
+        if !is_valid_plugin_dir_name(name) {
+            return Err(anyhow!("invalid plugin name '{}'", name));
         }
+        self.discard_staged(name).await.ok();
 
-        let mut data = String::new();
-        file.read_to_string(&mut data)?;
-        let manifest: PluginManifest = serde_json::from_str(&data)
-            .context("Failed to resolve plugin manifest from plugin package")?;
-        manifest.validate(std::path::Path::new("manifest.json (plugin package)"))?;
-        return Ok(manifest);
+        let staged_dir = self.staged_dir(name);
+        if staged_dir.exists() {
+            fs::remove_dir_all(&staged_dir)?;
+        }
+        fs::create_dir_all(&staged_dir)?;
+        self.emit_progress(name, "stage", None);
+
+        let unpacked = if path.is_dir() {
+            copy_dir_recursive(path, &staged_dir)
+        } else {
+            match tokio::fs::read(path).await {
+                Ok(package_raw) => extract_abp(package_raw, &staged_dir),
+                Err(err) => Err(err.into()),
+            }
+        };
+        let staged = unpacked
+            .and_then(|()| PluginManifest::load_from_dir(&staged_dir))
+            .and_then(|manifest| {
+                if manifest.name != name {
+                    return Err(anyhow!(
+                        "staged package is plugin '{}', expected '{}'",
+                        manifest.name,
+                        name
+                    ));
+                }
+                Plugin::load(staged_dir.clone(), self.app_handle.clone())
+            });
+
+        match staged {
+            Ok(plugin) => {
+                log::info!(
+                    "[plugin:{}] Staged version {} for activation",
+                    name,
+                    plugin.manifest.version
+                );
+                self.staged.insert(name.to_string(), plugin);
+                self.emit_progress(name, "staged", None);
+                Ok(())
+            }
+            Err(err) => {
+                log::error!("[plugin:{}] Staging update failed: {err}", name);
+                let manifest = PluginManifest::load_from_dir(&staged_dir)
+                    .ok()
+                    .filter(|manifest| manifest.name == name);
+                self.remove_staged_files(&staged_dir, manifest.as_ref());
+                self.emit_progress(name, "error", Some(err.to_string()));
+                Err(err)
+            }
+        }
     }
 
-    Err(anyhow!("manifest.json not found in plugin package"))
+    pub fn has_staged(&self, name: &str) -> bool {
+        self.staged.contains_key(name)
+    }
+
+    /// 用暂存版本替换当前版本；预编译产物已在暂存时生成，切换无需重新编译
+    pub async fn activate_staged(&mut self, name: &str) -> Result<()> {
+        let staged = self
+            .staged
+            .remove(name)
+            .ok_or_else(|| corelib::anyhow_site!("Plugin '{}' has no staged update", name))?;
+        let staged_dir = staged.path.clone();
+        let staged_manifest = staged.manifest.clone();
+        drop(staged);
+
+        let (was_running, disabled, priority_override) = match self.plugins.get(name) {
+            Some(plugin) => (
+                plugin.state.loaded && !plugin.state.disabled,
+                plugin.state.disabled,
+                plugin.state.priority_override,
+            ),
+            None => (false, false, None),
+        };
+        let dest_dir = self.plugin_root.join(name);
+        let previous_manifest = PluginManifest::load_from_dir(&dest_dir).ok();
+
+        log::info!(
+            "[plugin:{}] Activating staged version {}",
+            name,
+            staged_manifest.version
+        );
+        self.updated = true;
+        let previous = match self.plugins.remove(name) {
+            Some(mut plugin) => {
+                plugin.stop().await;
+                Some(plugin)
+            }
+            None => None,
+        };
+
+        // 先把旧版本移到一旁，新版本加载成功后再删除，失败时原样放回
+        let backup_dir = self.previous_version_dir(name);
+        let swapped = swap_in_staged_dir(&staged_dir, &dest_dir, &backup_dir);
+        crate::api::host::storage::invalidate_storage_usage(&dest_dir);
+        let had_previous = match swapped {
+            Ok(had_previous) => had_previous,
+            Err(err) => {
+                log::error!("[plugin:{}] Failed to swap in staged version: {err}", name);
+                self.remove_staged_files(&staged_dir, Some(&staged_manifest));
+                self.restore_previous_version(name, previous, was_running)
+                    .await;
+                self.emit_progress(name, "error", Some(err.to_string()));
+                return Err(err);
+            }
+        };
+
+        let loaded = Plugin::load(dest_dir.clone(), self.app_handle.clone());
+        // 正式版本已引用相同的产物，释放暂存键上的引用
+        if let Err(err) = purge_precompiled_component(&staged_dir, &staged_manifest) {
+            log::warn!(
+                "[plugin:{}] Failed to release staged precompiled artifacts: {err}",
+                name
+            );
+        }
+        let mut plugin = match loaded {
+            Ok(plugin) => plugin,
+            Err(err) => {
+                log::error!(
+                    "[plugin:{}] Staged version failed to load, restoring previous version: {err}",
+                    name
+                );
+                self.roll_back_activation(
+                    name,
+                    &dest_dir,
+                    &backup_dir,
+                    had_previous,
+                    previous,
+                    was_running,
+                )
+                .await;
+                self.emit_progress(name, "error", Some(err.to_string()));
+                return Err(err);
+            }
+        };
+        plugin.state.disabled = disabled;
+        plugin.state.priority_override = priority_override;
+        self.plugins.insert(name.to_string(), plugin);
+
+        self.apply_permission_diff(previous_manifest.as_ref(), &staged_manifest)
+            .await;
+
+        // 旧版本保留到新版本启动成功为止，on_load 失败时同样回滚
+        if was_running {
+            if let Err(err) = self.start_plugin(name).await {
+                log::error!(
+                    "[plugin:{}] Staged version failed to start, restoring previous version: {err}",
+                    name
+                );
+                if let Some(mut plugin) = self.plugins.remove(name) {
+                    plugin.stop().await;
+                }
+                self.roll_back_activation(
+                    name,
+                    &dest_dir,
+                    &backup_dir,
+                    had_previous,
+                    previous,
+                    was_running,
+                )
+                .await;
+                return Err(err);
+            }
+        }
+        if had_previous {
+            if let Err(err) = fs::remove_dir_all(&backup_dir) {
+                log::warn!("[plugin:{}] Failed to remove previous version: {err}", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// 删除新版本目录，把旧版本移回原处并恢复其运行状态
+    async fn roll_back_activation(
+        &mut self,
+        name: &str,
+        dest_dir: &Path,
+        backup_dir: &Path,
+        had_previous: bool,
+        previous: Option<Plugin>,
+        was_running: bool,
+    ) {
+        let restored = fs::remove_dir_all(dest_dir).and_then(|()| {
+            if had_previous {
+                fs::rename(backup_dir, dest_dir)
+            } else {
+                Ok(())
+            }
+        });
+        crate::api::host::storage::invalidate_storage_usage(dest_dir);
+        match restored {
+            Ok(()) => {
+                self.restore_previous_version(name, previous, was_running)
+                    .await
+            }
+            Err(restore_err) => log::error!(
+                "[plugin:{}] Failed to restore previous version: {restore_err}",
+                name
+            ),
+        }
+    }
+
+    /// 激活失败后放回旧版本，激活前在运行的重新启动
+    async fn restore_previous_version(
+        &mut self,
+        name: &str,
+        previous: Option<Plugin>,
+        was_running: bool,
+    ) {
+        let Some(previous) = previous else {
+            return;
+        };
+        self.plugins.insert(name.to_string(), previous);
+        if was_running {
+            if let Err(err) = self.start_plugin(name).await {
+                log::error!(
+                    "[plugin:{}] Failed to restart previous version: {err}",
+                    name
+                );
+            }
+        }
+    }
+
+    /// 丢弃暂存版本及其预编译产物
+    pub async fn discard_staged(&mut self, name: &str) -> Result<()> {
+        let staged = self
+            .staged
+            .remove(name)
+            .ok_or_else(|| corelib::anyhow_site!("Plugin '{}' has no staged update", name))?;
+        log::info!("[plugin:{}] Discarding staged update", name);
+        let staged_dir = staged.path.clone();
+        let staged_manifest = staged.manifest.clone();
+        drop(staged);
+        self.remove_staged_files(&staged_dir, Some(&staged_manifest));
+        Ok(())
+    }
+
+    /// 在系统文件管理器中打开插件的安装目录，便于调试
+    pub fn reveal_plugin_dir(&self, name: &str) -> Result<()> {
+        let plugin = self
+            .plugins
+            .get(name)
+            .ok_or_else(|| corelib::anyhow_site!("Plugin '{}' not found", name))?;
+        let path = plugin.path.to_string_lossy().to_string();
+        self.app_handle
+            .opener()
+            .open_path(path, None::<&str>)
+            .with_context(|| format!("Failed to open plugin directory for '{name}'"))
+    }
+
+    pub fn is_updated(&self) -> bool {
+        self.updated
+    }
+
+    /// 暂停所有插件（例如固件刷写期间）：不卸载插件，但传输类调用会被拒绝、事件不再派发、计时器暂停
+    pub fn suspend_all(&mut self) {
+        if crate::suspension::set_suspended(true) {
+            log::info!("[pluginsystem] All plugins suspended");
+        }
+    }
+
+    pub fn resume_all(&mut self) {
+        if crate::suspension::set_suspended(false) {
+            log::info!("[pluginsystem] All plugins resumed");
+        }
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        crate::suspension::is_suspended()
+    }
+
+    /// 主窗口隐藏或重新显示时调用。
+    /// 隐藏期间非后台插件暂停（计时器不触发、事件和传输回调不派发），
+    /// manifest 声明 `background` 的插件照常运行，直到应用完全退出
+    pub fn set_window_hidden(&mut self, hidden: bool) {
+        if crate::suspension::set_ui_hidden(hidden) {
+            if hidden {
+                log::info!("[pluginsystem] Window hidden, non-background plugins paused");
+            } else {
+                log::info!("[pluginsystem] Window shown, plugins resumed");
+            }
+        }
+    }
+
+    /// 插件上报致命错误：停止插件（不持久化停用状态，下次启动应用或手动启用时重试），
+    /// 在状态列表中记录错误并通知前端
+    pub async fn report_fatal(&mut self, name: &str, code: String, message: String) {
+        let Some(plugin) = self.plugins.get_mut(name) else {
+            return;
+        };
+        log::error!(
+            "[plugin:{}] Fatal error reported ({}): {}",
+            name,
+            code,
+            message
+        );
+        plugin.stop().await;
+        let reason = format!("fatal error ({code}): {message}");
+        plugin.state.skip_reason = Some(reason.clone());
+        plugin.state.fatal_error = Some(PluginFatalError {
+            code: code.clone(),
+            message: message.clone(),
+        });
+        self.emit_progress(name, "quarantined", Some(reason));
+        let payload = PluginFailedPayload {
+            plugin: name.to_string(),
+            code,
+            message,
+        };
+        if let Err(err) = self
+            .app_handle
+            .emit(PLUGINSYSTEM_PLUGIN_FAILED_EVENT, &payload)
+        {
+            log::error!("Failed to emit plugin failed event: {err}");
+        }
+    }
+
+    /// 检查插件能否执行命令，返回用于派发的运行时
+    pub fn command_target(&self, name: &str, command: &str) -> Result<PluginRuntime, CommandError> {
+        let plugin = self.plugins.get(name).ok_or(CommandError::PluginNotFound)?;
+        if plugin.state.disabled || !plugin.state.loaded {
+            return Err(CommandError::PluginDisabled);
+        }
+        if !crate::plugin_command::has_command(name, command) {
+            return Err(CommandError::UnknownCommand);
+        }
+        Ok(plugin.runtime.clone())
+    }
+
+    /// 调用插件注册的命令并等待结果。会在持有管理器的情况下等待插件，
+    /// 前端命令应先用 `command_target` 取得运行时，在锁外等待
+    pub async fn invoke_command(
+        &self,
+        name: &str,
+        command: &str,
+        args: String,
+    ) -> Result<String, CommandError> {
+        let runtime = self.command_target(name, command)?;
+        crate::plugin_command::invoke(runtime, name, command, args).await
+    }
+
+    /// 插件声明了但从未请求过的权限（跨会话累积），供前端提示权限过多的插件；插件不存在时返回 `None`
+    pub fn unused_permissions(&self, name: &str) -> Option<Vec<String>> {
+        self.plugins.get(name).map(|plugin| {
+            crate::api::host::permission::unused_permissions(name, &plugin.manifest.permissions)
+        })
+    }
+
+    /// 撤销插件的授权，`operations` 为空时撤销其声明的全部权限；之后的请求会重新询问用户
+    pub async fn revoke_permissions(&self, name: &str, operations: Vec<String>) -> Result<()> {
+        let plugin = self
+            .plugins
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Plugin '{}' not found", name))?;
+        let operations = if operations.is_empty() {
+            plugin.manifest.permissions.clone()
+        } else {
+            operations
+        };
+        if !reset_permission_grants(&self.app_handle, name, &operations).await {
+            anyhow::bail!("Frontend did not reset the permission grants of '{}'", name);
+        }
+        // 前端确认后才丢弃宿主缓存，只撤销请求的权限，避免两边状态不一致
+        forget_permission_grants(name, &operations);
+        Ok(())
+    }
+
+    /// 插件本次会话的重启次数和最近一次失败原因
+    pub fn restart_info(&self, name: &str) -> Option<(u32, Option<String>)> {
+        self.plugins.get(name).map(|plugin| {
+            (
+                plugin.state.restart_count,
+                plugin.state.last_failure.clone(),
+            )
+        })
+    }
+
+    /// 需要做健康检查的插件；检查本身应在插件管理线程之外进行，避免卡住其他命令
+    pub fn health_check_targets(&self) -> Vec<(String, PluginRuntime)> {
+        if crate::suspension::is_suspended() {
+            return Vec::new();
+        }
+
+        // 窗口隐藏期间暂停的非后台插件不参与检查
+        let mut targets = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin)| plugin.state.loaded && !plugin.state.disabled)
+            .filter(|(name, _)| !crate::suspension::is_plugin_suspended(name))
+            .map(|(name, plugin)| (name.clone(), plugin.runtime.clone()))
+            .collect::<Vec<_>>();
+        targets.sort_by(|left, right| left.0.cmp(&right.0));
+        targets
+    }
+
+    /// 对插件逐个做健康检查，不需要插件管理器
+    pub async fn ping_all(targets: Vec<(String, PluginRuntime)>) -> Vec<(String, PluginHealth)> {
+        let mut results = Vec::with_capacity(targets.len());
+        for (name, runtime) in targets {
+            let health = runtime.ping(HEALTH_CHECK_DEADLINE).await;
+            results.push((name, health));
+        }
+        results
+    }
+
+    /// 记录健康检查结果，返回无响应的插件；`restart_unhealthy` 为真时尝试重启它们
+    pub async fn apply_health_results(
+        &mut self,
+        results: Vec<(String, PluginHealth)>,
+        restart_unhealthy: bool,
+    ) -> Vec<String> {
+        let mut unhealthy = Vec::new();
+        for (name, health) in results {
+            // 检查期间被移除或禁用的插件不再处理
+            let Some(plugin) = self
+                .plugins
+                .get_mut(&name)
+                .filter(|plugin| plugin.state.loaded && !plugin.state.disabled)
+            else {
+                continue;
+            };
+            match health {
+                PluginHealth::Healthy => {
+                    plugin.state.health_error = None;
+                    plugin.state.healthy_streak = plugin.state.healthy_streak.saturating_add(1);
+                    if plugin.state.healthy_streak >= CLEAN_RUN_RESET_CHECKS {
+                        plugin.state.restart_count = 0;
+                        plugin.state.last_failure = None;
+                    }
+                }
+                PluginHealth::Busy => {
+                    log::debug!(
+                        "[plugin:{}] health check skipped: plugin runtime is busy",
+                        name
+                    );
+                }
+                PluginHealth::Unhealthy(reason) => {
+                    log::warn!("[plugin:{}] Health check failed: {}", name, reason);
+                    plugin.state.health_error = Some(reason.clone());
+                    plugin.state.healthy_streak = 0;
+                    self.emit_progress(&name, "unhealthy", Some(reason));
+                    unhealthy.push(name);
+                }
+            }
+        }
+
+        if restart_unhealthy {
+            for name in &unhealthy {
+                self.restart_unhealthy(name).await;
+            }
+        }
+
+        unhealthy
+    }
+
+    /// 挂起空闲超过配置时长的插件，返回本次被挂起的插件名
+    pub async fn suspend_idle_plugins(&mut self) -> Vec<String> {
+        let Some(idle_after) = self.idle_suspend_after else {
+            return Vec::new();
+        };
+        let mut candidates = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin)| plugin.state.loaded && !plugin.state.disabled)
+            .filter(|(_, plugin)| {
+                !plugin.runtime.is_idle_suspended() && plugin.runtime.idle_for() >= idle_after
+            })
+            .map(|(name, plugin)| (name.clone(), plugin.runtime.clone()))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|left, right| left.0.cmp(&right.0));
+
+        let mut suspended = Vec::new();
+        for (name, runtime) in candidates {
+            if runtime.idle_suspend().await {
+                self.emit_progress(&name, "idle", None);
+                suspended.push(name);
+            }
+        }
+        suspended
+    }
+
+    async fn restart_unhealthy(&mut self, name: &str) {
+        let Some(plugin) = self.plugins.get_mut(name) else {
+            return;
+        };
+        log::info!("[plugin:{}] Restarting unhealthy plugin", name);
+        plugin.state.restart_count = plugin.state.restart_count.saturating_add(1);
+        plugin.state.last_failure = plugin.state.health_error.clone();
+        plugin.runtime.clear_instance().await;
+        plugin.state.loaded = false;
+        match plugin.run().await {
+            Ok(()) => {
+                plugin.state.health_error = None;
+                self.emit_progress(name, "ready", None);
+                self.replay_sticky_events(name);
+            }
+            Err(err) => {
+                log::error!(
+                    "[plugin:{}] Restart after failed health check failed: {err}",
+                    name
+                );
+                plugin.stop().await;
+                self.emit_progress(name, "error", Some(err.to_string()));
+            }
+        }
+    }
+
+    /// 前端切换明暗主题或强调色后调用，主题变化时向所有运行中的插件派发 `theme-changed` 事件
+    pub async fn set_theme(&mut self, dark: bool, accent_color: Option<String>) {
+        let theme = crate::theme::HostTheme { dark, accent_color };
+        if !crate::theme::set_theme(theme.clone()) {
+            return;
+        }
+
+        let payload = match serde_json::to_string(&theme) {
+            Ok(payload) => payload,
+            Err(err) => {
+                log::error!("[pluginsystem] Failed to serialize theme payload: {err}");
+                return;
+            }
+        };
+        crate::sticky::remember(crate::sticky::StickyEvent::ThemeChanged, &payload);
+        if crate::suspension::is_suspended() {
+            return;
+        }
+
+        self.broadcast_to_active("theme change", false, |runtime| {
+            let payload = payload.clone();
+            async move { runtime.dispatch_theme_changed(payload).await }
+        })
+        .await;
+    }
+
+    /// 并发向所有已加载且未禁用的插件派发事件并等待完成；`skip_suspended` 时跳过单独挂起的插件
+    async fn broadcast_to_active<F, Fut>(&self, what: &str, skip_suspended: bool, dispatch: F)
+    where
+        F: Fn(PluginRuntime) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut handles = Vec::new();
+        for (name, plugin) in &self.plugins {
+            if !plugin.state.loaded
+                || plugin.state.disabled
+                || (skip_suspended && crate::suspension::is_plugin_suspended(name))
+            {
+                continue;
+            }
+            let name = name.clone();
+            let what = what.to_string();
+            let delivery = dispatch(plugin.runtime.clone());
+            handles.push(tokio::spawn(async move {
+                if let Err(err) = delivery.await {
+                    log::error!("[plugin:{}] Failed to deliver {}: {err}", name, what);
+                }
+            }));
+        }
+
+        for handle in join_all(handles).await {
+            if let Err(err) = handle {
+                log::error!("[pluginsystem] {} dispatch task panicked: {err}", what);
+            }
+        }
+    }
+
+    /// 运维调整插件的日志级别，不受宿主全局级别限制；`None` 恢复为全局级别
+    pub fn set_log_level(&self, name: &str, level: Option<log::LevelFilter>) -> Result<()> {
+        if !self.plugins.contains_key(name) {
+            return Err(corelib::anyhow_site!("Plugin '{}' not found", name));
+        }
+        crate::api::host::logging::set_plugin_log_level(&self.plugin_root, name, level)
+    }
+
+    /// 读取插件当前的特性开关
+    pub fn flags(&self, name: &str) -> Result<serde_json::Map<String, serde_json::Value>> {
+        if !self.plugins.contains_key(name) {
+            return Err(corelib::anyhow_site!("Plugin '{}' not found", name));
+        }
+        Ok(crate::api::host::flags::load_flags(&self.plugin_root, name)
+            .as_ref()
+            .clone())
+    }
+
+    /// 覆盖插件的特性开关，插件正在运行时向其派发 `flags-changed` 事件
+    pub async fn set_flags(
+        &mut self,
+        name: &str,
+        flags: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<()> {
+        if !self.plugins.contains_key(name) {
+            return Err(corelib::anyhow_site!("Plugin '{}' not found", name));
+        }
+        let path = crate::api::host::flags::flags_path(&self.plugin_root, name);
+        crate::api::host::flags::store_flags(&path, &flags)?;
+        self.dispatch_flags_changed(name, &flags)
+    }
+
+    /// 开关文件被直接修改、重新读取后发现内容变化时调用，向运行中的插件派发 `flags-changed` 事件
+    pub fn notify_flags_changed(&self, name: &str) -> Result<()> {
+        if !self.plugins.contains_key(name) {
+            return Ok(());
+        }
+        let flags = crate::api::host::flags::load_flags(&self.plugin_root, name);
+        self.dispatch_flags_changed(name, &flags)
+    }
+
+    fn dispatch_flags_changed(
+        &self,
+        name: &str,
+        flags: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<()> {
+        let Some(plugin) = self.plugins.get(name) else {
+            return Ok(());
+        };
+        if !plugin.state.loaded
+            || plugin.state.disabled
+            || crate::suspension::is_plugin_suspended(name)
+        {
+            return Ok(());
+        }
+        let payload = serde_json::to_string(flags)?;
+        let runtime = plugin.runtime.clone();
+        let name = name.to_string();
+        tokio::spawn(async move {
+            if let Err(err) = runtime.dispatch_flags_changed(payload).await {
+                log::error!("[plugin:{}] Failed to deliver flags change: {err}", name);
+            }
+        });
+        Ok(())
+    }
+
+    /// 已连接设备变化时由前端同步型号列表，兼容状态变化的插件会收到 progress 通知以便界面刷新
+    pub fn set_connected_devices(&mut self, models: Vec<String>) {
+        let previous = std::mem::replace(&mut self.connected_models, models);
+        for (name, plugin) in &self.plugins {
+            let was_met = plugin.manifest.supports_any_device(&previous);
+            let is_met = plugin.manifest.supports_any_device(&self.connected_models);
+            if was_met != is_met {
+                let stage = if is_met {
+                    "device-compatible"
+                } else {
+                    "no-compatible-device"
+                };
+                self.emit_progress(name, stage, None);
+            }
+        }
+    }
+
+    /// 插件的设备要求是否满足（已连接的设备中有支持的型号），插件不存在时返回 `None`
+    pub fn device_requirements_met(&self, name: &str) -> Option<bool> {
+        self.plugins
+            .get(name)
+            .map(|plugin| plugin.manifest.supports_any_device(&self.connected_models))
+    }
+
+    /// 宿主网络连接变化时调用，状态变化时向所有运行中的插件派发 `network-changed` 事件
+    pub async fn set_network_status(
+        &mut self,
+        connected: bool,
+        connection_type: crate::network::ConnectionType,
+    ) {
+        let status = crate::network::NetworkStatus {
+            connected,
+            connection_type,
+        };
+        if !crate::network::set_network(status.clone()) {
+            return;
+        }
+
+        let payload = match serde_json::to_string(&status) {
+            Ok(payload) => payload,
+            Err(err) => {
+                log::error!("[pluginsystem] Failed to serialize network payload: {err}");
+                return;
+            }
+        };
+        crate::sticky::remember(crate::sticky::StickyEvent::NetworkChanged, &payload);
+        if crate::suspension::is_suspended() {
+            return;
+        }
+
+        self.broadcast_to_active("network change", false, |runtime| {
+            let payload = payload.clone();
+            async move { runtime.dispatch_network_changed(payload).await }
+        })
+        .await;
+    }
+
+    /// 宿主电源状态变化时调用，状态变化时向运行中的插件派发 `power-state-changed` 事件；
+    /// 后台插件在主窗口隐藏时同样会收到，便于其降低轮询频率
+    pub async fn set_power_state(
+        &mut self,
+        source: crate::power::PowerSource,
+        power_saver: Option<bool>,
+    ) {
+        let state = crate::power::PowerState {
+            source,
+            power_saver,
+        };
+        if !crate::power::set_power(state.clone()) {
+            return;
+        }
+
+        let payload = match serde_json::to_string(&state) {
+            Ok(payload) => payload,
+            Err(err) => {
+                log::error!("[pluginsystem] Failed to serialize power payload: {err}");
+                return;
+            }
+        };
+        crate::sticky::remember(crate::sticky::StickyEvent::PowerStateChanged, &payload);
+
+        self.broadcast_to_active("power state change", true, |runtime| {
+            let payload = payload.clone();
+            async move { runtime.dispatch_power_state_changed(payload).await }
+        })
+        .await;
+    }
+}
+
+/// 校验并规范化 sha256 十六进制字符串
+fn normalize_wasm_hash(hash: &str) -> Option<String> {
+    let hash = hash.trim().to_ascii_lowercase();
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())).then_some(hash)
+}
+
+/// 找出不在允许列表内的权限，比较前统一去除空白并转为小写
+fn disallowed_permissions(declared: &[String], allowed: &[String]) -> Vec<String> {
+    let allowed = allowed
+        .iter()
+        .map(|permission| permission.trim().to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    let mut disallowed = Vec::new();
+    for permission in declared {
+        let permission = permission.trim().to_ascii_lowercase();
+        if !permission.is_empty()
+            && !allowed.contains(&permission)
+            && !disallowed.contains(&permission)
+        {
+            disallowed.push(permission);
+        }
+    }
+    disallowed
+}
+
+/// 已持久化的用户选择优先，否则使用 manifest 的 `default_enabled`
+fn initial_disabled(persisted: Option<bool>, default_enabled: bool) -> bool {
+    persisted.unwrap_or(!default_enabled)
+}
+
+/// 按 `depends_on` 对插件做拓扑排序，返回启动顺序以及处于依赖环中（或依赖环上插件）的插件。
+/// 同时可启动的插件按优先级从高到低、再按名称排序。
+/// 未安装的依赖不参与排序，由调用方在启动时处理。
+fn plan_start_order(
+    dependencies: &HashMap<String, Vec<String>>,
+    priorities: &HashMap<String, i32>,
+) -> (Vec<String>, Vec<String>) {
+    let priority_of = |name: &str| Reverse(priorities.get(name).copied().unwrap_or(0));
+    let mut pending = HashMap::new();
+    let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (name, depends_on) in dependencies {
+        let known = depends_on
+            .iter()
+            .filter(|dep| dep.as_str() != name.as_str() && dependencies.contains_key(*dep))
+            .collect::<HashSet<_>>();
+        for dep in &known {
+            dependents
+                .entry(dep.as_str())
+                .or_default()
+                .push(name.as_str());
+        }
+        let self_dependent = depends_on.iter().any(|dep| dep == name);
+        pending.insert(name.as_str(), known.len() + usize::from(self_dependent));
+    }
+
+    let mut ready = pending
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(name, _)| (priority_of(*name), *name))
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(dependencies.len());
+
+    while let Some((_, name)) = ready.pop_first() {
+        pending.remove(name);
+        order.push(name.to_string());
+        for dependent in dependents.get(name).into_iter().flatten() {
+            if let Some(count) = pending.get_mut(dependent) {
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert((priority_of(*dependent), *dependent));
+                }
+            }
+        }
+    }
+
+    let mut cyclic = pending.into_keys().map(str::to_string).collect::<Vec<_>>();
+    cyclic.sort();
+    (order, cyclic)
+}
+
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    if !dst.exists() {
+        fs::create_dir_all(dst)?;
+    }
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let entry_path = entry.path();
+        let target_path = dst.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir_recursive(&entry_path, &target_path)?;
+        } else if file_type.is_file() {
+            if let Some(parent) = target_path.parent() {
+                if !parent.exists() {
+                    fs::create_dir_all(parent)?;
+                }
+            }
+            fs::copy(&entry_path, &target_path)?;
+        }
+    }
+    Ok(())
+}
+
+fn extract_abp(package_raw: Vec<u8>, dest_dir: &Path) -> Result<()> {
+    let reader = Cursor::new(package_raw);
+    let mut archive = ZipArchive::new(reader)?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let outpath = dest_dir.join(file.mangled_name());
+
+        if file.name().ends_with('/') {
+            fs::create_dir_all(&outpath)?;
+        } else {
+            if let Some(parent) = outpath.parent() {
+                if !parent.exists() {
+                    fs::create_dir_all(parent)?;
+                }
+            }
+            let mut outfile = File::create(&outpath)?;
+            std::io::copy(&mut file, &mut outfile)?;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = file.unix_mode() {
+                fs::set_permissions(&outpath, fs::Permissions::from_mode(mode))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn resolve_manifest_from_abp(package_raw: &[u8]) -> Result<PluginManifest> {
+    let reader = Cursor::new(package_raw);
+    let mut archive = ZipArchive::new(reader)?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.name().ends_with('/') {
+            continue;
+        }
+
+        let file_name = file.mangled_name();
+        let is_manifest = file_name
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.eq_ignore_ascii_case("manifest.json"))
+            .unwrap_or(false);
+        if !is_manifest {
+            continue;
+        }
+
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+        let manifest: PluginManifest = serde_json::from_str(&data)
+            .context("Failed to resolve plugin manifest from plugin package")?;
+        manifest.validate(std::path::Path::new("manifest.json (plugin package)"))?;
+        return Ok(manifest);
+    }
+
+    Err(anyhow!("manifest.json not found in plugin package"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        PluginManager, disallowed_permissions, initial_disabled, is_valid_plugin_dir_name,
+        normalize_wasm_hash, plan_start_order, swap_in_staged_dir,
+    };
+    use crate::test_support::temp_root;
+
+    fn deps(items: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        items
+            .iter()
+            .map(|(name, depends_on)| {
+                (
+                    name.to_string(),
+                    depends_on.iter().map(|dep| dep.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn start_order_puts_dependencies_first() {
+        let (order, cyclic) = plan_start_order(
+            &deps(&[
+                ("app", &["provider"]),
+                ("provider", &["base"]),
+                ("base", &[]),
+                ("standalone", &["missing"]),
+            ]),
+            &HashMap::new(),
+        );
+        assert_eq!(order, vec!["base", "provider", "app", "standalone"]);
+        assert!(cyclic.is_empty());
+    }
+
+    #[test]
+    fn start_order_reports_cycles() {
+        let (order, cyclic) = plan_start_order(
+            &deps(&[("a", &["b"]), ("b", &["a"]), ("c", &[]), ("d", &["d"])]),
+            &HashMap::new(),
+        );
+        assert_eq!(order, vec!["c"]);
+        assert_eq!(cyclic, vec!["a", "b", "d"]);
+    }
+
+    #[test]
+    fn start_order_prefers_higher_priority() {
+        let priorities = HashMap::from([
+            ("zeta".to_string(), 10),
+            ("child".to_string(), 100),
+            ("alpha".to_string(), -1),
+        ]);
+        let (order, _) = plan_start_order(
+            &deps(&[
+                ("alpha", &[]),
+                ("beta", &[]),
+                ("zeta", &[]),
+                ("child", &["beta"]),
+            ]),
+            &priorities,
+        );
+        assert_eq!(order, vec!["zeta", "beta", "child", "alpha"]);
+    }
+
+    #[test]
+    fn first_load_respects_default_enabled() {
+        assert!(!initial_disabled(None, true));
+        assert!(initial_disabled(None, false));
+    }
+
+    #[test]
+    fn persisted_state_overrides_default_enabled() {
+        assert!(!initial_disabled(Some(false), false));
+        assert!(initial_disabled(Some(true), true));
+    }
+
+    #[test]
+    fn policy_reports_permissions_outside_allowlist() {
+        let allowed = vec!["Network".to_string(), "storage".to_string()];
+        let declared = vec![
+            "network".to_string(),
+            " sockets ".to_string(),
+            "clipboard".to_string(),
+            "sockets".to_string(),
+        ];
+        assert_eq!(
+            disallowed_permissions(&declared, &allowed),
+            vec!["sockets", "clipboard"]
+        );
+        assert!(disallowed_permissions(&declared[..1], &allowed).is_empty());
+    }
+
+    #[test]
+    fn blocklist_hashes_are_normalized() {
+        let hash = "AB".repeat(32);
+        assert_eq!(
+            normalize_wasm_hash(&format!(" {hash} ")),
+            Some("ab".repeat(32))
+        );
+        assert_eq!(normalize_wasm_hash("abc"), None);
+        assert_eq!(normalize_wasm_hash(&"zz".repeat(32)), None);
+    }
+
+    #[test]
+    fn plugin_dir_names_cannot_escape_the_root() {
+        assert!(is_valid_plugin_dir_name("demo-plugin"));
+        assert!(!is_valid_plugin_dir_name(""));
+        assert!(!is_valid_plugin_dir_name("."));
+        assert!(!is_valid_plugin_dir_name(".."));
+        assert!(!is_valid_plugin_dir_name("../demo"));
+        assert!(!is_valid_plugin_dir_name("nested/demo"));
+        assert!(!is_valid_plugin_dir_name("nested\\demo"));
+    }
+
+    #[test]
+    fn failed_swap_restores_the_previous_version() {
+        let temp = temp_root();
+        let root = temp.path();
+        let dest = root.join("demo");
+        let backup = root.join(".previous").join("demo");
+        std::fs::create_dir_all(&dest).unwrap();
+        std::fs::write(dest.join("version"), "1").unwrap();
+
+        // 暂存目录不存在，移入失败后旧版本应回到原处
+        assert!(swap_in_staged_dir(&root.join("missing"), &dest, &backup).is_err());
+        assert_eq!(std::fs::read_to_string(dest.join("version")).unwrap(), "1");
+
+        let staged = root.join(".staged").join("demo");
+        std::fs::create_dir_all(&staged).unwrap();
+        std::fs::write(staged.join("version"), "2").unwrap();
+        assert!(swap_in_staged_dir(&staged, &dest, &backup).unwrap());
+        assert_eq!(std::fs::read_to_string(dest.join("version")).unwrap(), "2");
+        assert_eq!(
+            std::fs::read_to_string(backup.join("version")).unwrap(),
+            "1"
+        );
+    }
+
+    // 真实的 tauri 运行时需要图形环境（Linux 上为 GTK），无法创建时返回 `None`
+    #[cfg(any(windows, target_os = "linux"))]
+    fn test_app() -> tauri::App {
+        tauri::Builder::default()
+            .any_thread()
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .expect("failed to build the tauri runtime")
+    }
+
+    #[cfg(any(windows, target_os = "linux"))]
+    #[tokio::test]
+    #[ignore = "PluginManager needs a Wry AppHandle, which requires a windowing environment"]
+    async fn in_memory_plugin_lives_under_the_manager_root() {
+        let app = test_app();
+        let root = temp_root();
+        let mut pm = PluginManager::new(root.path().to_path_buf(), app.handle().clone());
+        let manifest: crate::manifest::PluginManifest = serde_json::from_value(serde_json::json!({
+            "name": "ignored",
+            "icon": "",
+            "version": "1.0.0",
+            "description": "",
+            "author": "",
+            "website": "",
+            "entry": "plugin.wasm",
+            "wasi_version": 2,
+            "api_level": 3,
+            "permissions": []
+        }))
+        .unwrap();
+
+        pm.add_from_bytes("fixture", b"(component)", manifest.clone())
+            .unwrap();
+        assert!(
+            pm.add_from_bytes("../escape", b"(component)", manifest)
+                .is_err()
+        );
+        let plugin = &pm.plugins["fixture"];
+        assert_eq!(plugin.manifest.name, "fixture");
+        // 宿主按插件目录的上级写安装 id、日志级别等文件，必须落在管理器根目录而不是当前目录
+        assert_eq!(plugin.path, root.path().join("fixture"));
+        // 内存插件不落盘，预热时也不预打开插件目录
+        assert!(!plugin.path.exists());
+        assert!(pm.prewarm("fixture").unwrap());
+    }
 }
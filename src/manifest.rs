@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -22,8 +23,106 @@ pub struct PluginManifest {
     pub enable_settings_button: Option<bool>, // 是否在插件窗口右上角显示设置按钮
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub disable_default_padding: Option<bool>, // 是否去掉插件UI渲染区域的默认内边距
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>, // 插件依赖的其他插件名称，依赖会先于本插件启动
+    #[serde(default = "default_enabled")]
+    pub default_enabled: bool, // 首次加载（尚无用户设置）时是否启用，默认启用
+    #[serde(default)]
+    pub priority: i32, // 启动优先级，数值越大越先启动（依赖关系优先于优先级）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<PluginComponentManifest>, // 附加wasm组件列表，与入口组件共享沙箱和权限
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_ips: Vec<String>, // 可选的出站 IP/CIDR 允许列表，非空时 HTTP 请求解析后的地址必须在列表内
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sockets: Vec<String>, // 允许访问的 host:port 列表，需同时声明 sockets 权限，否则不开放任何 TCP/UDP 访问
+    #[serde(default)]
+    pub background: bool, // 后台插件：主窗口关闭（托盘模式）后计时器和传输回调仍继续运行，仅随应用退出停止
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>, // 申请的线性内存上限（MiB），超出宿主上限时按上限生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cpu_fuel: Option<u64>, // 申请的每次调用燃料额度，超出宿主上限时按上限生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_http_body_mb: Option<u64>, // 申请的单个 HTTP 响应体累计上限（MiB），超出宿主上限时按上限生效
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supported_devices: Vec<String>, // 支持的设备型号 id，为空表示不限设备
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub name_localized: BTreeMap<String, String>, // 本地化显示名称（locale -> 名称），name 仍作为插件标识
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub description_localized: BTreeMap<String, String>, // 本地化简介（locale -> 简介）
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>, // 按宿主语言解析出的显示名称，仅在插件列表中填充
+}
+
+/// 按 locale 解析出的插件显示文本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedManifest {
+    pub name: String,
+    pub description: String,
 }
 
+fn default_enabled() -> bool {
+    true
+}
+
+fn normalize_locale(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+fn locale_language(locale: &str) -> &str {
+    locale.split('-').next().unwrap_or(locale)
+}
+
+// 依次尝试：完整匹配、仅语言匹配（zh-CN -> zh）、同语言的其他地区（zh -> zh-TW）
+fn pick_localized<'a>(values: &'a BTreeMap<String, String>, locale: &str) -> Option<&'a str> {
+    let locale = normalize_locale(locale);
+    if locale.is_empty() {
+        return None;
+    }
+    let language = locale_language(&locale);
+    let entries = values
+        .iter()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(key, value)| (normalize_locale(key), value.as_str()))
+        .collect::<Vec<_>>();
+    entries
+        .iter()
+        .find(|(key, _)| *key == locale)
+        .or_else(|| entries.iter().find(|(key, _)| key == language))
+        .or_else(|| {
+            entries
+                .iter()
+                .find(|(key, _)| locale_language(key) == language)
+        })
+        .map(|(_, value)| *value)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginComponentManifest {
+    pub name: String,  // 组件名称
+    pub entry: String, // 组件wasm文件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>, // 订阅的事件类型（如 "timer"、"plugin-message"），未订阅的事件不会派发给该组件
+}
+
+/// 附加组件可订阅的事件类型，二进制插件事件按 `plugin-message` 订阅
+pub const COMPONENT_EVENT_NAMES: &[&str] = &[
+    "plugin-message",
+    "interconnect-message",
+    "device-action",
+    "provider-action",
+    "deeplink-action",
+    "transport-packet",
+    "timer",
+    "permission-denied",
+    "theme-changed",
+    "network-changed",
+    "power-state-changed",
+    "flags-changed",
+    "command",
+    "file-shared",
+    "health-check",
+];
+
 impl PluginManifest {
     pub const SUPPORTED_API_LEVELS: &'static [u32] = &[2, 3];
 
@@ -49,9 +148,85 @@ impl PluginManifest {
             ));
         }
 
+        if let Some(rule) = self
+            .allowed_ips
+            .iter()
+            .find(|rule| crate::api::host::http::IpRule::parse(rule).is_none())
+        {
+            return Err(corelib::anyhow_site!(
+                "invalid allowed_ips entry '{}' in manifest: {}",
+                rule,
+                manifest_path.display()
+            ));
+        }
+
+        if let Some(rule) = self
+            .sockets
+            .iter()
+            .find(|rule| crate::api::host::sockets::SocketRule::parse(rule).is_none())
+        {
+            return Err(corelib::anyhow_site!(
+                "invalid sockets entry '{}' in manifest: {}",
+                rule,
+                manifest_path.display()
+            ));
+        }
+
+        let mut component_names = std::collections::HashSet::new();
+        for component in &self.components {
+            let name = component.name.trim();
+            if name.is_empty() || component.entry.trim().is_empty() {
+                return Err(corelib::anyhow_site!(
+                    "component name or entry is empty in manifest: {}",
+                    manifest_path.display()
+                ));
+            }
+            if !component_names.insert(name) {
+                return Err(corelib::anyhow_site!(
+                    "duplicate component '{}' in manifest: {}",
+                    name,
+                    manifest_path.display()
+                ));
+            }
+            if let Some(event) = component
+                .events
+                .iter()
+                .find(|event| !COMPONENT_EVENT_NAMES.contains(&event.as_str()))
+            {
+                return Err(corelib::anyhow_site!(
+                    "component '{}' subscribes to unknown event '{}' in manifest: {}",
+                    name,
+                    event,
+                    manifest_path.display()
+                ));
+            }
+        }
+
         Ok(())
     }
 
+    /// 返回与 locale 最匹配的名称和简介，缺失时回退到基础字段
+    pub fn localized(&self, locale: &str) -> LocalizedManifest {
+        LocalizedManifest {
+            name: pick_localized(&self.name_localized, locale)
+                .unwrap_or(&self.name)
+                .to_string(),
+            description: pick_localized(&self.description_localized, locale)
+                .unwrap_or(&self.description)
+                .to_string(),
+        }
+    }
+
+    /// 已连接的设备中是否有插件支持的型号；未声明 `supported_devices` 时始终满足
+    pub fn supports_any_device<S: AsRef<str>>(&self, connected_models: &[S]) -> bool {
+        self.supported_devices.is_empty()
+            || connected_models.iter().any(|model| {
+                self.supported_devices
+                    .iter()
+                    .any(|supported| supported.trim().eq_ignore_ascii_case(model.as_ref().trim()))
+            })
+    }
+
     pub fn load_from_dir(dir: &Path) -> Result<Self> {
         let manifest_path = dir.join("manifest.json");
         let data = fs::read_to_string(&manifest_path).with_context(|| {
@@ -74,4 +249,83 @@ impl PluginManifest {
         let entry = self.entry.clone();
         base_dir.join(entry)
     }
+
+    pub fn component_wasm_path(
+        &self,
+        base_dir: &Path,
+        component: &PluginComponentManifest,
+    ) -> PathBuf {
+        base_dir.join(&component.entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{PluginManifest, pick_localized};
+
+    #[test]
+    fn localized_lookup_falls_back_by_language() {
+        let values = BTreeMap::from([
+            ("en".to_string(), "Weather".to_string()),
+            ("zh-CN".to_string(), "天气".to_string()),
+            ("zh-TW".to_string(), "天氣".to_string()),
+        ]);
+
+        assert_eq!(pick_localized(&values, "zh_TW"), Some("天氣"));
+        assert_eq!(pick_localized(&values, "en-US"), Some("Weather"));
+        assert_eq!(pick_localized(&values, "zh"), Some("天气"));
+        assert_eq!(pick_localized(&values, "ja-JP"), None);
+    }
+
+    #[test]
+    fn supported_devices_match_connected_models() {
+        let mut manifest: PluginManifest = serde_json::from_value(serde_json::json!({
+            "name": "band-only",
+            "icon": "",
+            "version": "1.0.0",
+            "description": "",
+            "author": "",
+            "website": "",
+            "entry": "plugin.wasm",
+            "wasi_version": 2,
+            "api_level": 3,
+            "permissions": []
+        }))
+        .unwrap();
+        assert!(manifest.supports_any_device::<&str>(&[]));
+
+        manifest.supported_devices = vec!["Band-9".to_string()];
+        assert!(!manifest.supports_any_device::<&str>(&[]));
+        assert!(!manifest.supports_any_device(&["watch-s4"]));
+        assert!(manifest.supports_any_device(&["watch-s4", "band-9"]));
+    }
+
+    #[test]
+    fn component_events_must_be_known() {
+        let mut manifest: PluginManifest = serde_json::from_value(serde_json::json!({
+            "name": "split",
+            "icon": "",
+            "version": "1.0.0",
+            "description": "",
+            "author": "",
+            "website": "",
+            "entry": "plugin.wasm",
+            "wasi_version": 2,
+            "api_level": 3,
+            "permissions": [],
+            "components": [
+                { "name": "sync", "entry": "sync.wasm", "events": ["timer", "plugin-message"] },
+                { "name": "export", "entry": "export.wasm" }
+            ]
+        }))
+        .unwrap();
+        let path = std::path::Path::new("manifest.json");
+        assert!(manifest.validate(path).is_ok());
+        assert!(manifest.components[1].events.is_empty());
+
+        manifest.components[1].events = vec!["tick".to_string()];
+        assert!(manifest.validate(path).is_err());
+    }
 }
@@ -0,0 +1,48 @@
+use std::sync::Mutex as StdMutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionType {
+    Wifi,
+    Cellular,
+    Ethernet,
+    None,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NetworkStatus {
+    pub(crate) connected: bool,
+    pub(crate) connection_type: ConnectionType,
+}
+
+// 前端上报的网络状态；尚未上报（或平台无法获取）时视为已连接、类型未知
+static HOST_NETWORK: Lazy<StdMutex<Option<NetworkStatus>>> = Lazy::new(|| StdMutex::new(None));
+
+pub(crate) fn current_network() -> NetworkStatus {
+    HOST_NETWORK
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .clone()
+        .unwrap_or(NetworkStatus {
+            connected: true,
+            connection_type: ConnectionType::Unknown,
+        })
+}
+
+/// 更新网络状态，返回状态是否发生了变化
+pub(crate) fn set_network(status: NetworkStatus) -> bool {
+    let mut guard = HOST_NETWORK
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if guard.as_ref() == Some(&status) {
+        return false;
+    }
+    *guard = Some(status);
+    true
+}
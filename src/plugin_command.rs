@@ -0,0 +1,223 @@
+//! 插件命令：插件注册具名命令，宿主通过 `command` 事件调用并等待插件用 `command::respond` 返回结果
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::plugin::PluginRuntime;
+
+// 插件处理命令的最长时间，超时后调用方收到 `Timeout`
+pub(crate) const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+// 命令名长度上限
+const COMMAND_NAME_MAX_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "camelCase")]
+pub enum CommandError {
+    PluginNotFound,
+    PluginDisabled,
+    UnknownCommand,
+    Timeout,
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PluginNotFound => write!(f, "plugin not found"),
+            Self::PluginDisabled => write!(f, "plugin is disabled or not running"),
+            Self::UnknownCommand => write!(f, "command is not registered by the plugin"),
+            Self::Timeout => write!(
+                f,
+                "plugin did not respond within {}s",
+                COMMAND_TIMEOUT.as_secs()
+            ),
+            Self::Failed(reason) => write!(f, "command failed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+struct PendingCommand {
+    plugin: String,
+    tx: oneshot::Sender<Result<String, String>>,
+}
+
+// 插件名 -> 已注册的命令
+static PLUGIN_COMMANDS: Lazy<Mutex<HashMap<String, BTreeSet<String>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static PENDING_COMMANDS: Lazy<Mutex<HashMap<u64, PendingCommand>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+fn valid_command_name(command: &str) -> bool {
+    !command.is_empty()
+        && command.len() <= COMMAND_NAME_MAX_LEN
+        && command
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+pub(crate) fn register_command(plugin: &str, command: &str) -> bool {
+    if !valid_command_name(command) {
+        return false;
+    }
+    PLUGIN_COMMANDS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .entry(plugin.to_string())
+        .or_default()
+        .insert(command.to_string());
+    true
+}
+
+pub(crate) fn has_command(plugin: &str, command: &str) -> bool {
+    PLUGIN_COMMANDS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .get(plugin)
+        .is_some_and(|commands| commands.contains(command))
+}
+
+/// 插件注册的全部命令，供管理界面展示
+pub fn list_commands(plugin: &str) -> Vec<String> {
+    PLUGIN_COMMANDS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .get(plugin)
+        .map(|commands| commands.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// 插件停止或重新加载时清除其命令，并让等待中的调用立即失败
+pub(crate) fn release_plugin_commands(plugin: &str) {
+    PLUGIN_COMMANDS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(plugin);
+    PENDING_COMMANDS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .retain(|_, pending| pending.plugin != plugin);
+}
+
+/// 等待插件返回命令结果；调用方放弃等待时自动注销
+struct CommandWaiter {
+    id: u64,
+    rx: oneshot::Receiver<Result<String, String>>,
+}
+
+impl Future for CommandWaiter {
+    type Output = Result<Result<String, String>, oneshot::error::RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx)
+    }
+}
+
+impl Drop for CommandWaiter {
+    fn drop(&mut self) {
+        PENDING_COMMANDS
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner())
+            .remove(&self.id);
+    }
+}
+
+fn register_waiter(plugin: &str) -> CommandWaiter {
+    let (tx, rx) = oneshot::channel();
+    let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    PENDING_COMMANDS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .insert(
+            id,
+            PendingCommand {
+                plugin: plugin.to_string(),
+                tx,
+            },
+        );
+    CommandWaiter { id, rx }
+}
+
+/// 插件返回命令结果；只能回应发给自己的请求
+pub(crate) fn respond(plugin: &str, request_id: u64, result: Result<String, String>) -> bool {
+    let mut pending = PENDING_COMMANDS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if pending
+        .get(&request_id)
+        .is_none_or(|waiter| waiter.plugin != plugin)
+    {
+        return false;
+    }
+    let Some(waiter) = pending.remove(&request_id) else {
+        return false;
+    };
+    waiter.tx.send(result).is_ok()
+}
+
+/// 向插件派发命令并等待结果，需在插件管理器锁之外等待，避免阻塞其他操作
+pub(crate) async fn invoke(
+    runtime: PluginRuntime,
+    plugin: &str,
+    command: &str,
+    args: String,
+) -> Result<String, CommandError> {
+    let waiter = register_waiter(plugin);
+    let payload = serde_json::json!({
+        "requestId": waiter.id,
+        "command": command,
+        "args": args,
+    })
+    .to_string();
+    runtime
+        .dispatch_command(payload)
+        .await
+        .map_err(|err| CommandError::Failed(err.to_string()))?;
+    match tokio::time::timeout(COMMAND_TIMEOUT, waiter).await {
+        Ok(Ok(result)) => result.map_err(CommandError::Failed),
+        // 插件在返回结果前被停止
+        Ok(Err(_)) => Err(CommandError::PluginDisabled),
+        Err(_) => Err(CommandError::Timeout),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        PENDING_COMMANDS, has_command, register_command, register_waiter, release_plugin_commands,
+        respond,
+    };
+
+    #[test]
+    fn commands_are_scoped_to_their_plugin() {
+        assert!(register_command("sync", "run-now"));
+        assert!(!register_command("sync", "bad name"));
+        assert!(has_command("sync", "run-now"));
+        assert!(!has_command("other", "run-now"));
+
+        release_plugin_commands("sync");
+        assert!(!has_command("sync", "run-now"));
+    }
+
+    #[tokio::test]
+    async fn only_the_target_plugin_can_respond() {
+        let waiter = register_waiter("owner");
+        let id = waiter.id;
+        assert!(!respond("intruder", id, Ok("forged".to_string())));
+        assert!(respond("owner", id, Ok("done".to_string())));
+        assert_eq!(waiter.await.unwrap(), Ok("done".to_string()));
+        assert!(!PENDING_COMMANDS.lock().unwrap().contains_key(&id));
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::Mutex as StdMutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSource {
+    Charging,
+    Battery,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PowerState {
+    pub(crate) source: PowerSource,
+    // `None` 表示平台无法获取省电模式状态
+    pub(crate) power_saver: Option<bool>,
+}
+
+// 前端上报的电源状态；尚未上报（或平台不支持）时均视为未知
+static HOST_POWER: Lazy<StdMutex<Option<PowerState>>> = Lazy::new(|| StdMutex::new(None));
+
+pub(crate) fn current_power() -> PowerState {
+    HOST_POWER
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .clone()
+        .unwrap_or(PowerState {
+            source: PowerSource::Unknown,
+            power_saver: None,
+        })
+}
+
+/// 更新电源状态，返回状态是否发生了变化
+pub(crate) fn set_power(state: PowerState) -> bool {
+    let mut guard = HOST_POWER
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if guard.as_ref() == Some(&state) {
+        return false;
+    }
+    *guard = Some(state);
+    true
+}
@@ -0,0 +1,294 @@
+//! 分享目标：插件声明自己能处理的 MIME 类型，用户通过系统分享面板把文件分享给 AstroBox 时，
+//! 按 MIME 类型找到对应插件并以 `file-shared` 事件送达
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex as StdMutex;
+
+use anyhow::{Context, Result, anyhow};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
+use frontbridge::invoke_frontend;
+use once_cell::sync::Lazy;
+use tauri::AppHandle;
+
+// 多个插件都能处理分享的文件时由用户选择，返回插件名，取消时返回 null
+const FRONT_SHARE_CHOOSER_METHOD: &str = "host/share/choose_target";
+// 单个插件可声明的 MIME 模式数量上限
+const SHARE_TARGET_MAX_PATTERNS: usize = 32;
+// 不超过该大小的文件直接随事件送达，更大的文件改为分块读取会话
+pub(crate) const SHARE_INLINE_MAX_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ShareTargetError {
+    InvalidPattern(String),
+    TooManyPatterns,
+}
+
+// 插件名 -> MIME 模式，按插件名排序，便于多个插件匹配时给出稳定的候选顺序
+static SHARE_TARGETS: Lazy<StdMutex<BTreeMap<String, Vec<String>>>> =
+    Lazy::new(|| StdMutex::new(BTreeMap::new()));
+
+/// 规范化 MIME 模式，只接受 `type/subtype`、`type/*` 和 `*/*`
+fn normalize_pattern(pattern: &str) -> Option<String> {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    let (kind, subtype) = pattern.split_once('/')?;
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.' | '_'))
+    };
+    match (kind, subtype) {
+        ("*", "*") => Some(pattern),
+        ("*", _) => None,
+        (kind, "*") if valid_part(kind) => Some(pattern),
+        (kind, subtype) if valid_part(kind) && valid_part(subtype) => Some(pattern),
+        _ => None,
+    }
+}
+
+fn pattern_matches(pattern: &str, mime: &str) -> bool {
+    // 忽略 `; charset=...` 之类的参数
+    let mime = mime
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match pattern.split_once('/') {
+        Some(("*", "*")) => true,
+        Some((kind, "*")) => mime
+            .split_once('/')
+            .is_some_and(|(mime_kind, _)| mime_kind == kind),
+        _ => pattern == mime,
+    }
+}
+
+/// 重新注册会覆盖该插件之前声明的全部模式
+pub(crate) fn set_share_target(plugin: &str, patterns: &[String]) -> Result<(), ShareTargetError> {
+    if patterns.len() > SHARE_TARGET_MAX_PATTERNS {
+        return Err(ShareTargetError::TooManyPatterns);
+    }
+    let mut normalized = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        let pattern = normalize_pattern(pattern)
+            .ok_or_else(|| ShareTargetError::InvalidPattern(pattern.clone()))?;
+        if !normalized.contains(&pattern) {
+            normalized.push(pattern);
+        }
+    }
+    let mut targets = SHARE_TARGETS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if normalized.is_empty() {
+        targets.remove(plugin);
+    } else {
+        targets.insert(plugin.to_string(), normalized);
+    }
+    Ok(())
+}
+
+/// 能处理该 MIME 类型的插件，按插件名排序
+pub(crate) fn share_target_candidates(mime: &str) -> Vec<String> {
+    SHARE_TARGETS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .iter()
+        .filter(|(_, patterns)| {
+            patterns
+                .iter()
+                .any(|pattern| pattern_matches(pattern, mime))
+        })
+        .map(|(plugin, _)| plugin.clone())
+        .collect()
+}
+
+/// 插件停止或重新加载时移除其分享目标
+pub(crate) fn release_share_target(plugin: &str) {
+    SHARE_TARGETS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .remove(plugin);
+}
+
+/// 分享文件的事件负载：小文件带 `dataBase64`，大文件带 `sessionId`，
+/// 插件用 `dialog.open_file_read_chunk` 读取
+pub(crate) enum SharedFileContent<'a> {
+    Inline(&'a [u8]),
+    Session(u64),
+}
+
+pub(crate) fn shared_file_envelope(
+    name: &str,
+    mime: &str,
+    size: u64,
+    content: SharedFileContent<'_>,
+) -> String {
+    let mut envelope = serde_json::json!({
+        "name": name,
+        "mime": mime,
+        "size": size,
+    });
+    match content {
+        SharedFileContent::Inline(data) => {
+            envelope["dataBase64"] = BASE64_STANDARD.encode(data).into();
+        }
+        SharedFileContent::Session(session_id) => {
+            envelope["sessionId"] = session_id.into();
+        }
+    }
+    envelope.to_string()
+}
+
+/// 读取分享的文件并生成事件负载，包含阻塞的文件读写
+fn prepare_shared_file(plugin: &str, path: &Path, mime: &str) -> Result<String> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open shared file {}", path.display()))?;
+    let size = file.metadata()?.len();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let envelope = if size <= SHARE_INLINE_MAX_BYTES {
+        let mut data = Vec::with_capacity(size as usize);
+        (&file).read_to_end(&mut data)?;
+        shared_file_envelope(&file_name, mime, size, SharedFileContent::Inline(&data))
+    } else {
+        let session_id = crate::api::host::dialog::open_shared_file_session(plugin, file);
+        shared_file_envelope(
+            &file_name,
+            mime,
+            size,
+            SharedFileContent::Session(session_id),
+        )
+    };
+    log::debug!(
+        "[pluginsystem] shared file {} -> plugin {}",
+        file_name,
+        plugin
+    );
+    Ok(envelope)
+}
+
+/// 用户通过系统分享面板把文件分享给 AstroBox 时调用，送达声明了匹配 MIME 类型的插件。
+/// 返回处理该文件的插件名，用户在选择框中取消时返回 `None`。
+/// 插件管理线程只用于查询候选和运行时，等待用户选择、读文件和派发都在其外进行
+pub(crate) async fn share_file(
+    app_handle: AppHandle,
+    path: PathBuf,
+    mime: String,
+) -> Result<Option<String>> {
+    let candidates = {
+        let mime = mime.clone();
+        crate::with_plugin_manager_async(move |pm| {
+            let candidates = pm.share_target_candidates(&mime);
+            Box::pin(async move { candidates })
+        })
+        .await?
+    };
+
+    let name = match candidates.as_slice() {
+        [] => return Err(anyhow!("No plugin accepts shared files of type {}", mime)),
+        [only] => only.clone(),
+        _ => {
+            let chosen: Option<String> = invoke_frontend(
+                &app_handle,
+                FRONT_SHARE_CHOOSER_METHOD,
+                serde_json::json!({
+                    "mime": mime,
+                    "fileName": path.file_name().map(|name| name.to_string_lossy()),
+                    "candidates": candidates,
+                }),
+            )
+            .await
+            .context("invoke frontend share target chooser")?;
+            match chosen {
+                Some(name) if candidates.contains(&name) => name,
+                Some(name) => {
+                    return Err(anyhow!(
+                        "Plugin '{}' is not a share target for {}",
+                        name,
+                        mime
+                    ));
+                }
+                None => return Ok(None),
+            }
+        }
+    };
+
+    // 用户选择期间插件可能已停止，重新取一次运行时
+    let runtime = {
+        let name = name.clone();
+        crate::with_plugin_manager_async(move |pm| {
+            let runtime = pm.share_target_runtime(&name);
+            Box::pin(async move { runtime })
+        })
+        .await??
+    };
+    let envelope = {
+        let name = name.clone();
+        tokio::task::spawn_blocking(move || prepare_shared_file(&name, &path, &mime))
+            .await
+            .context("shared file preparation task panicked")??
+    };
+    runtime
+        .dispatch_file_shared(envelope)
+        .await
+        .with_context(|| format!("Plugin '{}' failed to handle shared file", name))?;
+    Ok(Some(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ShareTargetError, release_share_target, set_share_target, share_target_candidates,
+    };
+
+    #[test]
+    fn matches_mime_patterns_and_releases_on_stop() {
+        set_share_target("share-gallery", &["image/*".to_string()]).unwrap();
+        set_share_target(
+            "share-converter",
+            &["Image/PNG".to_string(), "application/pdf".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            share_target_candidates("image/png"),
+            vec!["share-converter", "share-gallery"]
+        );
+        assert_eq!(share_target_candidates("image/jpeg"), vec!["share-gallery"]);
+        assert_eq!(
+            share_target_candidates("application/pdf; charset=binary"),
+            vec!["share-converter"]
+        );
+
+        release_share_target("share-gallery");
+        assert_eq!(
+            share_target_candidates("image/png"),
+            vec!["share-converter"]
+        );
+        release_share_target("share-converter");
+        assert!(share_target_candidates("image/png").is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        assert_eq!(
+            set_share_target("share-bad", &["*/png".to_string()]),
+            Err(ShareTargetError::InvalidPattern("*/png".to_string()))
+        );
+        assert_eq!(
+            set_share_target("share-bad", &["image".to_string()]),
+            Err(ShareTargetError::InvalidPattern("image".to_string()))
+        );
+        // 注册表是全局的，只检查本测试的插件没有被注册
+        assert!(
+            !share_target_candidates("image/png")
+                .iter()
+                .any(|plugin| plugin == "share-bad")
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! 粘性事件：宿主保留某些事件类型最近一次的载荷，插件启动完成后立即补发，避免错过启动前已发生的状态变化
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex as StdMutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StickyEvent {
+    ThemeChanged,
+    NetworkChanged,
+    PowerStateChanged,
+}
+
+impl StickyEvent {
+    pub const ALL: [StickyEvent; 3] = [
+        StickyEvent::ThemeChanged,
+        StickyEvent::NetworkChanged,
+        StickyEvent::PowerStateChanged,
+    ];
+}
+
+static STICKY_ENABLED: Lazy<StdMutex<HashSet<StickyEvent>>> =
+    Lazy::new(|| StdMutex::new(StickyEvent::ALL.into_iter().collect()));
+// 每种事件只保留最近一次的载荷
+static STICKY_LATEST: Lazy<StdMutex<HashMap<StickyEvent, String>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+/// 设置哪些事件类型为粘性事件；不再粘性的类型会丢弃已保留的载荷
+pub fn set_sticky_events(events: &[StickyEvent]) {
+    let enabled = events.iter().copied().collect::<HashSet<_>>();
+    STICKY_LATEST
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .retain(|event, _| enabled.contains(event));
+    *STICKY_ENABLED
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner()) = enabled;
+}
+
+/// 记录事件的最新载荷，非粘性事件直接忽略
+pub(crate) fn remember(event: StickyEvent, payload: &str) {
+    let enabled = STICKY_ENABLED
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .contains(&event);
+    if !enabled {
+        return;
+    }
+    STICKY_LATEST
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .insert(event, payload.to_string());
+}
+
+/// 需要补发给新启动插件的事件
+pub(crate) fn latest() -> Vec<(StickyEvent, String)> {
+    STICKY_LATEST
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .iter()
+        .map(|(event, payload)| (*event, payload.clone()))
+        .collect()
+}
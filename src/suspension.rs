@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use std::sync::Mutex as StdMutex;
+
+use once_cell::sync::Lazy;
+use tokio::sync::watch;
+
+static PLUGINS_SUSPENDED: Lazy<watch::Sender<bool>> = Lazy::new(|| watch::channel(false).0);
+// 主窗口是否已隐藏（托盘模式）；隐藏期间只有后台插件继续运行
+static UI_HIDDEN: Lazy<watch::Sender<bool>> = Lazy::new(|| watch::channel(false).0);
+static BACKGROUND_PLUGINS: Lazy<StdMutex<HashSet<String>>> =
+    Lazy::new(|| StdMutex::new(HashSet::new()));
+
+pub(crate) fn is_suspended() -> bool {
+    *PLUGINS_SUSPENDED.borrow()
+}
+
+/// 设置全局挂起状态，返回状态是否发生了变化
+pub(crate) fn set_suspended(suspended: bool) -> bool {
+    PLUGINS_SUSPENDED.send_if_modified(|current| {
+        if *current == suspended {
+            false
+        } else {
+            *current = suspended;
+            true
+        }
+    })
+}
+
+pub(crate) fn is_ui_hidden() -> bool {
+    *UI_HIDDEN.borrow()
+}
+
+/// 设置主窗口隐藏状态，返回状态是否发生了变化
+pub(crate) fn set_ui_hidden(hidden: bool) -> bool {
+    UI_HIDDEN.send_if_modified(|current| {
+        if *current == hidden {
+            false
+        } else {
+            *current = hidden;
+            true
+        }
+    })
+}
+
+pub(crate) fn set_background_plugin(plugin: &str, background: bool) {
+    let mut guard = BACKGROUND_PLUGINS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if background {
+        guard.insert(plugin.to_string());
+    } else {
+        guard.remove(plugin);
+    }
+}
+
+fn is_background_plugin(plugin: &str) -> bool {
+    BACKGROUND_PLUGINS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .contains(plugin)
+}
+
+/// 插件当前是否应暂停：全局挂起时所有插件暂停，窗口隐藏时只暂停非后台插件
+pub(crate) fn is_plugin_suspended(plugin: &str) -> bool {
+    is_suspended() || (is_ui_hidden() && !is_background_plugin(plugin))
+}
+
+pub(crate) async fn wait_until_plugin_resumed(plugin: &str) {
+    let mut suspended = PLUGINS_SUSPENDED.subscribe();
+    let mut hidden = UI_HIDDEN.subscribe();
+    while is_plugin_suspended(plugin) {
+        let closed = tokio::select! {
+            changed = suspended.changed() => changed.is_err(),
+            changed = hidden.changed() => changed.is_err(),
+        };
+        if closed {
+            log::debug!("[pluginsystem] suspension state channel closed");
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        is_plugin_suspended, set_background_plugin, set_ui_hidden, wait_until_plugin_resumed,
+    };
+
+    #[tokio::test]
+    async fn background_plugins_keep_running_while_hidden() {
+        set_background_plugin("suspension-test-bg", true);
+        set_ui_hidden(true);
+
+        assert!(!is_plugin_suspended("suspension-test-bg"));
+        assert!(is_plugin_suspended("suspension-test-fg"));
+        // 后台插件的计时器无需等待窗口重新显示
+        tokio::time::timeout(
+            Duration::from_millis(100),
+            wait_until_plugin_resumed("suspension-test-bg"),
+        )
+        .await
+        .unwrap();
+
+        let waiting = tokio::spawn(wait_until_plugin_resumed("suspension-test-fg"));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+        set_ui_hidden(false);
+        tokio::time::timeout(Duration::from_millis(100), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+
+        set_background_plugin("suspension-test-bg", false);
+    }
+}
@@ -0,0 +1,9 @@
+//! 单元测试共用的辅助函数
+
+/// 为单个测试创建独立的临时目录，返回值被丢弃时目录连同内容一起删除
+pub(crate) fn temp_root() -> tempfile::TempDir {
+    tempfile::Builder::new()
+        .prefix("psys-test-")
+        .tempdir()
+        .expect("failed to create a temporary test directory")
+}
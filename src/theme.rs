@@ -0,0 +1,49 @@
+use std::sync::Mutex as StdMutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HostTheme {
+    pub(crate) dark: bool,
+    pub(crate) accent_color: Option<String>,
+}
+
+// 前端上报的宿主主题；尚未上报时回退到主窗口的系统主题
+static HOST_THEME: Lazy<StdMutex<Option<HostTheme>>> = Lazy::new(|| StdMutex::new(None));
+
+fn window_is_dark(app_handle: &AppHandle) -> bool {
+    app_handle
+        .webview_windows()
+        .values()
+        .next()
+        .and_then(|window| window.theme().ok())
+        .is_some_and(|theme| matches!(theme, tauri::Theme::Dark))
+}
+
+pub(crate) fn current_theme(app_handle: &AppHandle) -> HostTheme {
+    let guard = HOST_THEME
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    match guard.as_ref() {
+        Some(theme) => theme.clone(),
+        None => HostTheme {
+            dark: window_is_dark(app_handle),
+            accent_color: None,
+        },
+    }
+}
+
+/// 更新宿主主题，返回主题是否发生了变化
+pub(crate) fn set_theme(theme: HostTheme) -> bool {
+    let mut guard = HOST_THEME
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if guard.as_ref() == Some(&theme) {
+        return false;
+    }
+    *guard = Some(theme);
+    true
+}
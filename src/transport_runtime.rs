@@ -1,9 +1,24 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
 use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
 use tokio::sync::oneshot;
 
+/// 等待方：插件发起的请求，或宿主自己发起的诊断请求（如 `transport.ping`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaiterOwner {
+    Plugin,
+    Host,
+}
+
 #[derive(Debug)]
 pub struct TransportRequestWaiter {
+    pub id: u64,
+    pub owner: WaiterOwner,
     pub device_addr: String,
     pub channel_id: u32,
     pub protobuf_type_id: Option<u32>,
@@ -13,48 +28,146 @@ pub struct TransportRequestWaiter {
 
 static TRANSPORT_REQUEST_WAITERS: Lazy<Mutex<Vec<TransportRequestWaiter>>> =
     Lazy::new(|| Mutex::new(Vec::new()));
+static NEXT_WAITER_ID: AtomicU64 = AtomicU64::new(1);
 
-pub(crate) fn register_request_waiter(
+/// 等待设备响应的句柄；插件放弃请求导致其被丢弃时会自动注销，不会残留在等待列表中
+pub(crate) struct RequestWaiter {
+    id: u64,
+    rx: oneshot::Receiver<Vec<u8>>,
+}
+
+impl Future for RequestWaiter {
+    type Output = Result<Vec<u8>, oneshot::error::RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx)
+    }
+}
+
+impl Drop for RequestWaiter {
+    fn drop(&mut self) {
+        let mut guard = TRANSPORT_REQUEST_WAITERS
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        guard.retain(|waiter| waiter.id != self.id);
+    }
+}
+
+impl TransportRequestWaiter {
+    fn matches(
+        &self,
+        device_addr: &str,
+        channel_id: u32,
+        protobuf_type_id: Option<u32>,
+        protobuf_packet_id: Option<u32>,
+    ) -> bool {
+        self.device_addr.eq_ignore_ascii_case(device_addr)
+            && self.channel_id == channel_id
+            && self.protobuf_type_id == protobuf_type_id
+            && self.protobuf_packet_id == protobuf_packet_id
+    }
+}
+
+fn push_waiter(
+    guard: &mut Vec<TransportRequestWaiter>,
+    owner: WaiterOwner,
     device_addr: String,
     channel_id: u32,
     protobuf_type_id: Option<u32>,
     protobuf_packet_id: Option<u32>,
-) -> oneshot::Receiver<Vec<u8>> {
+) -> RequestWaiter {
     let (tx, rx) = oneshot::channel();
-    let waiter = TransportRequestWaiter {
+    let id = NEXT_WAITER_ID.fetch_add(1, Ordering::Relaxed);
+    guard.push(TransportRequestWaiter {
+        id,
+        owner,
         device_addr,
         channel_id,
         protobuf_type_id,
         protobuf_packet_id,
         tx,
-    };
+    });
+    RequestWaiter { id, rx }
+}
 
+pub(crate) fn register_request_waiter(
+    device_addr: String,
+    channel_id: u32,
+    protobuf_type_id: Option<u32>,
+    protobuf_packet_id: Option<u32>,
+) -> RequestWaiter {
     let mut guard = TRANSPORT_REQUEST_WAITERS
         .lock()
         .unwrap_or_else(|poison| poison.into_inner());
-    guard.push(waiter);
-    rx
+    push_waiter(
+        &mut guard,
+        WaiterOwner::Plugin,
+        device_addr,
+        channel_id,
+        protobuf_type_id,
+        protobuf_packet_id,
+    )
+}
+
+/// 注册宿主自己发起的请求。设备的应答无法区分是回复谁的，同一设备上已有插件在等待相同
+/// type/id 的应答时返回 `None`，避免宿主拿走插件的应答
+pub(crate) fn register_host_request_waiter(
+    device_addr: String,
+    channel_id: u32,
+    protobuf_type_id: Option<u32>,
+    protobuf_packet_id: Option<u32>,
+) -> Option<RequestWaiter> {
+    let mut guard = TRANSPORT_REQUEST_WAITERS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let plugin_pending = guard.iter().any(|waiter| {
+        waiter.owner == WaiterOwner::Plugin
+            && waiter.matches(
+                &device_addr,
+                channel_id,
+                protobuf_type_id,
+                protobuf_packet_id,
+            )
+    });
+    if plugin_pending {
+        return None;
+    }
+    Some(push_waiter(
+        &mut guard,
+        WaiterOwner::Host,
+        device_addr,
+        channel_id,
+        protobuf_type_id,
+        protobuf_packet_id,
+    ))
 }
 
+/// 把应答交给匹配的等待方。返回 `true` 表示该帧只是宿主请求的应答，不应再转发给插件
 pub(crate) fn fulfill_request_waiters(
     device_addr: &str,
     channel_id: u32,
     protobuf_type_id: Option<u32>,
     protobuf_packet_id: Option<u32>,
     payload: &[u8],
-) {
+) -> bool {
     let mut guard = TRANSPORT_REQUEST_WAITERS
         .lock()
         .unwrap_or_else(|poison| poison.into_inner());
     let mut remaining = Vec::with_capacity(guard.len());
+    let mut host_matched = false;
+    let mut plugin_matched = false;
 
     for waiter in guard.drain(..) {
-        let addr_match = waiter.device_addr.eq_ignore_ascii_case(device_addr);
-        let channel_match = waiter.channel_id == channel_id;
-        let protobuf_type_match = waiter.protobuf_type_id == protobuf_type_id;
-        let protobuf_packet_match = waiter.protobuf_packet_id == protobuf_packet_id;
-
-        if addr_match && channel_match && protobuf_type_match && protobuf_packet_match {
+        if waiter.matches(
+            device_addr,
+            channel_id,
+            protobuf_type_id,
+            protobuf_packet_id,
+        ) {
+            match waiter.owner {
+                WaiterOwner::Host => host_matched = true,
+                WaiterOwner::Plugin => plugin_matched = true,
+            }
             if waiter.tx.send(payload.to_vec()).is_err() {
                 log::debug!("[pluginsystem] transport request waiter receiver dropped");
             }
@@ -64,4 +177,196 @@ pub(crate) fn fulfill_request_waiters(
     }
 
     *guard = remaining;
+    host_matched && !plugin_matched
+}
+
+/// 设备断开时结束等待该设备响应的请求，返回结束的数量。
+/// 插件的接收注册只按地址匹配，不随连接清除，重连后继续生效
+pub(crate) fn fail_device_waiters(device_addr: &str) -> usize {
+    let mut guard = TRANSPORT_REQUEST_WAITERS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let before = guard.len();
+    // 丢弃发送端后等待方立即收到错误，不必等到请求超时
+    guard.retain(|waiter| !waiter.device_addr.eq_ignore_ascii_case(device_addr));
+    before - guard.len()
+}
+
+// 自定义协议名长度上限
+const CUSTOM_PROTOCOL_NAME_MAX_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProtocolClaimError {
+    InvalidName,
+    Conflict,
+}
+
+// 插件注册的自定义传输协议：协议名 -> 插件名，宿主只按协议名转发原始帧
+static CUSTOM_PROTOCOLS: Lazy<Mutex<HashMap<String, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn valid_protocol_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= CUSTOM_PROTOCOL_NAME_MAX_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// 同一协议名只能由一个插件注册
+pub(crate) fn claim_custom_protocol(plugin: &str, name: &str) -> Result<(), ProtocolClaimError> {
+    if !valid_protocol_name(name) {
+        return Err(ProtocolClaimError::InvalidName);
+    }
+    let mut guard = CUSTOM_PROTOCOLS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    match guard.get(name) {
+        Some(owner) if owner != plugin => Err(ProtocolClaimError::Conflict),
+        Some(_) => Ok(()),
+        None => {
+            guard.insert(name.to_string(), plugin.to_string());
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn custom_protocol_owner(name: &str) -> Option<String> {
+    CUSTOM_PROTOCOLS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .get(name)
+        .cloned()
+}
+
+/// 插件停止或重新加载时释放它注册的全部协议
+pub(crate) fn release_custom_protocols(plugin: &str) {
+    CUSTOM_PROTOCOLS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .retain(|_, owner| owner != plugin);
+}
+
+/// 自定义协议原始帧以 `transport-packet` 事件送达，负载为 JSON 而不是 Vela 帧的 base64
+pub(crate) fn custom_protocol_frame_envelope(addr: &str, protocol: &str, payload: &[u8]) -> String {
+    serde_json::json!({
+        "addr": addr,
+        "protocol": protocol,
+        "payloadBase64": BASE64_STANDARD.encode(payload),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::component::{Component, FutureReader, Linker};
+
+    use super::{
+        ProtocolClaimError, TRANSPORT_REQUEST_WAITERS, claim_custom_protocol,
+        custom_protocol_owner, fulfill_request_waiters, register_host_request_waiter,
+        register_request_waiter, release_custom_protocols,
+    };
+
+    fn pending_waiters(device_addr: &str) -> usize {
+        TRANSPORT_REQUEST_WAITERS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|waiter| waiter.device_addr == device_addr)
+            .count()
+    }
+
+    #[tokio::test]
+    async fn abandoned_request_unregisters_waiter() {
+        let waiter = register_request_waiter("AA:00:00:00:00:01".to_string(), 1, Some(2), Some(1));
+        let request = tokio::spawn(waiter);
+        tokio::task::yield_now().await;
+        assert_eq!(pending_waiters("AA:00:00:00:00:01"), 1);
+
+        // 插件丢弃 future 时宿主任务随之被丢弃，而不是等到设备响应或超时
+        request.abort();
+        assert!(request.await.unwrap_err().is_cancelled());
+        assert_eq!(pending_waiters("AA:00:00:00:00:01"), 0);
+    }
+
+    #[tokio::test]
+    async fn guest_dropping_the_future_unregisters_waiter() {
+        let mut config = wasmtime::Config::new();
+        config.wasm_component_model_async(true).async_support(true);
+        let engine = wasmtime::Engine::new(&config).unwrap();
+        let component = Component::new(&engine, "(component)").unwrap();
+        let mut store = wasmtime::Store::new(&engine, ());
+        let instance = Linker::<()>::new(&engine)
+            .instantiate_async(&mut store, &component)
+            .await
+            .unwrap();
+
+        // 与 transport.request 相同：等待者放在交给 FutureReader 的 future 里
+        let waiter = register_request_waiter("AA:00:00:00:00:03".to_string(), 1, Some(2), Some(1));
+        let mut reader = FutureReader::new(instance, &mut store, async move {
+            waiter.await.map_err(wasmtime::Error::from)
+        });
+        assert_eq!(pending_waiters("AA:00:00:00:00:03"), 1);
+
+        // 宿主关闭读端与插件执行 `future.drop-readable` 走同一条路径，都会丢弃宿主侧的 future
+        reader.close(&mut store);
+        assert_eq!(pending_waiters("AA:00:00:00:00:03"), 0);
+    }
+
+    #[tokio::test]
+    async fn fulfilled_request_receives_payload() {
+        let waiter = register_request_waiter("AA:00:00:00:00:02".to_string(), 1, Some(2), Some(1));
+        fulfill_request_waiters("aa:00:00:00:00:02", 1, Some(2), Some(1), b"pong");
+        assert_eq!(waiter.await.unwrap(), b"pong");
+        assert_eq!(pending_waiters("AA:00:00:00:00:02"), 0);
+    }
+
+    #[tokio::test]
+    async fn host_waiters_do_not_take_plugin_replies() {
+        // 插件的同类请求进行中时宿主不发起请求
+        let plugin = register_request_waiter("AA:00:00:00:00:04".to_string(), 1, Some(2), Some(1));
+        assert!(
+            register_host_request_waiter("aa:00:00:00:00:04".to_string(), 1, Some(2), Some(1))
+                .is_none()
+        );
+        assert!(!fulfill_request_waiters(
+            "AA:00:00:00:00:04",
+            1,
+            Some(2),
+            Some(1),
+            b"battery"
+        ));
+        assert_eq!(plugin.await.unwrap(), b"battery");
+
+        // 只有宿主在等待时，应答由宿主消费，不再转发给插件
+        let host =
+            register_host_request_waiter("AA:00:00:00:00:04".to_string(), 1, Some(2), Some(1))
+                .unwrap();
+        assert!(fulfill_request_waiters(
+            "AA:00:00:00:00:04",
+            1,
+            Some(2),
+            Some(1),
+            b"battery"
+        ));
+        assert_eq!(host.await.unwrap(), b"battery");
+    }
+
+    #[test]
+    fn custom_protocol_is_owned_by_one_plugin() {
+        assert_eq!(claim_custom_protocol("garmin", "fit-link"), Ok(()));
+        assert_eq!(claim_custom_protocol("garmin", "fit-link"), Ok(()));
+        assert_eq!(
+            claim_custom_protocol("other", "fit-link"),
+            Err(ProtocolClaimError::Conflict)
+        );
+        assert_eq!(
+            claim_custom_protocol("other", "bad name"),
+            Err(ProtocolClaimError::InvalidName)
+        );
+        assert_eq!(custom_protocol_owner("fit-link").as_deref(), Some("garmin"));
+
+        release_custom_protocols("garmin");
+        assert_eq!(custom_protocol_owner("fit-link"), None);
+    }
 }
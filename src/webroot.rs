@@ -0,0 +1,307 @@
+//! 插件网页资源：插件注册一个目录后，webview 可通过 `plugin://<插件标识>/<路径>` 访问其中的文件。
+//! Windows 和 Android 上 Tauri 只路由 `http://plugin.localhost/`，所有插件共用这一个 origin，
+//! 插件标识放在路径的第一段。
+//! 宿主应用需要在 Tauri Builder 上注册该协议：
+//! `.register_uri_scheme_protocol(pluginsystem::webroot::PLUGIN_PROTOCOL_SCHEME, |_, request| pluginsystem::webroot::handle_plugin_protocol(&request))`
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex as StdMutex;
+
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use tauri::http::{Request, Response, StatusCode, header};
+
+use crate::api::host::assets::{declared_files, sanitize_declared};
+
+pub const PLUGIN_PROTOCOL_SCHEME: &str = "plugin";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WebRootError {
+    // 目录越出插件目录
+    InvalidDir,
+    // 插件名无法转换成 origin 标识，或标识已被其他插件占用
+    OriginConflict,
+}
+
+struct WebRoot {
+    plugin: String,
+    plugin_root: PathBuf,
+    // 相对插件目录的网页根目录，空字符串表示插件目录本身
+    dir: String,
+    // 注册时展开的声明文件（相对插件目录），请求时只做查找
+    files: BTreeSet<String>,
+}
+
+// origin 标识 -> 网页根目录
+static WEB_ROOTS: Lazy<StdMutex<HashMap<String, WebRoot>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+/// 插件名转换为可作为域名标签的 origin 标识：小写字母、数字和 `-`，最长 63 个字符
+fn origin_label(plugin: &str) -> Option<String> {
+    let label = plugin
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .take(63)
+        .collect::<String>();
+    let label = label.trim_matches('-');
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+/// 插件网页根目录对应的 webview 地址。
+/// Windows 和 Android 上 Tauri 把自定义协议映射为 `http://<scheme>.localhost`，
+/// 子域名不会被路由，因此插件之间共享 origin（localStorage、cookie 等互相可见）
+pub(crate) fn web_root_url(label: &str) -> String {
+    if cfg!(any(target_os = "windows", target_os = "android")) {
+        format!("http://{PLUGIN_PROTOCOL_SCHEME}.localhost/{label}/")
+    } else {
+        format!("{PLUGIN_PROTOCOL_SCHEME}://{label}/")
+    }
+}
+
+/// 注册插件的网页根目录，只有 manifest `additional_files` 中声明的文件会被提供给 webview
+pub(crate) fn register_web_root(
+    plugin: &str,
+    plugin_root: &Path,
+    additional_files: &[String],
+    dir: &str,
+) -> Result<String, WebRootError> {
+    let dir = match dir.trim().trim_matches('/') {
+        "" | "." => String::new(),
+        dir => sanitize_declared(dir)
+            .ok_or(WebRootError::InvalidDir)?
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()
+            .ok_or(WebRootError::InvalidDir)?
+            .join("/"),
+    };
+    let label = origin_label(plugin).ok_or(WebRootError::OriginConflict)?;
+    let prefix = format!("{dir}/");
+    let files = declared_files(plugin_root, additional_files)
+        .into_iter()
+        .filter(|name| dir.is_empty() || name.starts_with(&prefix))
+        .collect();
+
+    let mut roots = WEB_ROOTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if roots.get(&label).is_some_and(|root| root.plugin != plugin) {
+        return Err(WebRootError::OriginConflict);
+    }
+    roots.insert(
+        label.clone(),
+        WebRoot {
+            plugin: plugin.to_string(),
+            plugin_root: plugin_root.to_path_buf(),
+            dir,
+            files,
+        },
+    );
+    Ok(web_root_url(&label))
+}
+
+/// 插件停止或重新加载时撤销其网页根目录
+pub(crate) fn release_web_root(plugin: &str) {
+    WEB_ROOTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .retain(|_, root| root.plugin != plugin);
+}
+
+// 从请求地址中取出 origin 标识和网页根目录内的相对路径
+fn split_request_path(uri: &str) -> Option<(String, String)> {
+    let url = url::Url::parse(uri).ok()?;
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    let mut segments = url
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .map(|segment| percent_decode_str(segment).decode_utf8().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let label = if host == format!("{PLUGIN_PROTOCOL_SCHEME}.localhost") {
+        if segments.is_empty() {
+            return None;
+        }
+        segments.remove(0).to_ascii_lowercase()
+    } else if url.scheme() == PLUGIN_PROTOCOL_SCHEME {
+        host
+    } else {
+        return None;
+    };
+    let path = segments
+        .iter()
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.as_ref())
+        .collect::<Vec<_>>()
+        .join("/");
+    (!label.is_empty()).then_some((label, path))
+}
+
+/// 解析请求对应的插件和文件，路径必须落在网页根目录内且属于声明的文件
+fn resolve_request(label: &str, path: &str) -> Result<(String, PathBuf), StatusCode> {
+    let roots = WEB_ROOTS
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let root = roots.get(label).ok_or(StatusCode::NOT_FOUND)?;
+    let path = if path.is_empty() { "index.html" } else { path };
+    let relative = sanitize_declared(path).ok_or(StatusCode::FORBIDDEN)?;
+    let name = Path::new(&root.dir)
+        .join(relative)
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()
+        .ok_or(StatusCode::NOT_FOUND)?
+        .join("/");
+    if !root.files.contains(&name) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok((root.plugin.clone(), root.plugin_root.join(name)))
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "wasm" => "application/wasm",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn status_response(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
+
+/// `plugin://` 协议处理函数，由宿主应用注册到 Tauri
+pub fn handle_plugin_protocol(request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Some((label, path)) = split_request_path(&request.uri().to_string()) else {
+        return status_response(StatusCode::BAD_REQUEST);
+    };
+    let (plugin, file) = match resolve_request(&label, &path) {
+        Ok(resolved) => resolved,
+        Err(status) => {
+            log::warn!(
+                "[pluginsystem] web request '{}' for origin '{}' rejected: {}",
+                path,
+                label,
+                status
+            );
+            return status_response(status);
+        }
+    };
+    match fs::read(&file) {
+        Ok(data) => {
+            let mut response = Response::new(data);
+            if let Ok(value) = header::HeaderValue::from_str(content_type(&file)) {
+                response.headers_mut().insert(header::CONTENT_TYPE, value);
+            }
+            response
+        }
+        Err(err) => {
+            log::warn!(
+                "[plugin:{}] failed to read web asset {}: {err}",
+                plugin,
+                file.display()
+            );
+            status_response(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tauri::http::StatusCode;
+
+    use super::{
+        WebRootError, origin_label, register_web_root, release_web_root, resolve_request,
+        split_request_path,
+    };
+    use crate::test_support::temp_root;
+
+    #[test]
+    fn splits_custom_scheme_and_localhost_urls() {
+        assert_eq!(
+            split_request_path("plugin://weather/assets/app%20main.js"),
+            Some(("weather".to_string(), "assets/app main.js".to_string()))
+        );
+        assert_eq!(
+            split_request_path("http://plugin.localhost/weather/index.html"),
+            Some(("weather".to_string(), "index.html".to_string()))
+        );
+        // Tauri 不路由子域名，这类地址不属于插件协议
+        assert_eq!(
+            split_request_path("http://weather.plugin.localhost/index.html"),
+            None
+        );
+    }
+
+    #[test]
+    fn each_plugin_gets_its_own_origin_label() {
+        assert_eq!(origin_label("Weather_Pro").as_deref(), Some("weather-pro"));
+        assert_eq!(origin_label("__").as_deref(), None);
+        assert_eq!(origin_label(&"a".repeat(80)).unwrap().len(), 63);
+    }
+
+    #[test]
+    fn only_declared_files_under_web_root_are_served() {
+        let temp = temp_root();
+        let root = temp.path();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::write(root.join("web/index.html"), "<html></html>").unwrap();
+        fs::write(root.join("web/secret.html"), "").unwrap();
+        fs::write(root.join("config.json"), "{}").unwrap();
+
+        let declared = vec!["web/index.html".to_string(), "config.json".to_string()];
+        assert_eq!(
+            register_web_root("site", root, &declared, "../web"),
+            Err(WebRootError::InvalidDir)
+        );
+        register_web_root("site", root, &declared, "web").unwrap();
+        assert_eq!(
+            register_web_root("Site", root, &declared, "web"),
+            Err(WebRootError::OriginConflict)
+        );
+
+        assert_eq!(
+            resolve_request("site", "").unwrap(),
+            ("site".to_string(), root.join("web/index.html"))
+        );
+        assert_eq!(
+            resolve_request("site", "secret.html"),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            resolve_request("site", "../config.json"),
+            Err(StatusCode::FORBIDDEN)
+        );
+
+        release_web_root("site");
+        assert_eq!(resolve_request("site", ""), Err(StatusCode::NOT_FOUND));
+    }
+}